@@ -0,0 +1,138 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+       http://www.apache.org/licenses/LICENSE-2.0
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! `Signer` that keeps the secret key in a keystore. This is an extension from the
+//! substrate-api-client repo.
+
+use ac_primitives::{AccountId, GenericAddress, Signer, SignerError};
+use sp_core::{
+    crypto::{ByteArray, CryptoTypePublicPair, KeyTypeId},
+    ecdsa, ed25519, sr25519,
+};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{traits::IdentifyAccount, MultiSignature, MultiSigner};
+
+/// Signs with a key of a `SyncCryptoStore`, e.g. a [`crate::LocalKeystore`], via
+/// `SyncCryptoStore::sign_with`. The secret key never leaves the keystore.
+#[derive(Clone)]
+pub struct KeystoreSigner {
+    keystore: SyncCryptoStorePtr,
+    key_type: KeyTypeId,
+    public: MultiSigner,
+}
+
+impl KeystoreSigner {
+    /// Signer for the key `public` of type `key_type`.
+    ///
+    /// Returns `None` if the keystore does not hold the secret key.
+    pub fn new(
+        keystore: SyncCryptoStorePtr,
+        key_type: KeyTypeId,
+        public: impl Into<MultiSigner>,
+    ) -> Option<Self> {
+        let public = public.into();
+        let raw_public = crypto_type_public_pair(&public).1;
+        if !SyncCryptoStore::has_keys(&*keystore, &[(raw_public, key_type)]) {
+            return None;
+        }
+        Some(Self {
+            keystore,
+            key_type,
+            public,
+        })
+    }
+
+    /// The public key this signer signs with.
+    pub fn public(&self) -> &MultiSigner {
+        &self.public
+    }
+}
+
+impl Signer for KeystoreSigner {
+    type AccountId = AccountId;
+    type Address = GenericAddress;
+    type Signature = MultiSignature;
+
+    fn account_id(&self) -> AccountId {
+        self.public.clone().into_account()
+    }
+
+    /// Panics if the keystore fails to sign, e.g. because the key has been removed in the
+    /// meantime. Use [`Signer::try_sign`] to handle these cases.
+    fn sign(&self, payload: &[u8]) -> MultiSignature {
+        self.try_sign(payload).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_sign(&self, payload: &[u8]) -> Result<MultiSignature, SignerError> {
+        let raw = SyncCryptoStore::sign_with(
+            &*self.keystore,
+            self.key_type,
+            &crypto_type_public_pair(&self.public),
+            payload,
+        )
+        .map_err(|e| SignerError(format!("keystore failed to sign: {}", e)))?
+        .ok_or_else(|| SignerError("key has been removed from the keystore".into()))?;
+
+        match self.public {
+            MultiSigner::Ed25519(_) => {
+                ed25519::Signature::from_slice(&raw).map(MultiSignature::Ed25519)
+            }
+            MultiSigner::Sr25519(_) => {
+                sr25519::Signature::from_slice(&raw).map(MultiSignature::Sr25519)
+            }
+            MultiSigner::Ecdsa(_) => ecdsa::Signature::from_slice(&raw).map(MultiSignature::Ecdsa),
+        }
+        .map_err(|_| SignerError("keystore returned a signature of invalid length".into()))
+    }
+}
+
+fn crypto_type_public_pair(public: &MultiSigner) -> CryptoTypePublicPair {
+    match public {
+        MultiSigner::Ed25519(public) => {
+            CryptoTypePublicPair(ed25519::CRYPTO_ID, public.to_raw_vec())
+        }
+        MultiSigner::Sr25519(public) => {
+            CryptoTypePublicPair(sr25519::CRYPTO_ID, public.to_raw_vec())
+        }
+        MultiSigner::Ecdsa(public) => CryptoTypePublicPair(ecdsa::CRYPTO_ID, public.to_raw_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalKeystore;
+    use sp_core::testing::SR25519;
+    use sp_runtime::traits::Verify;
+    use std::{fs, sync::Arc};
+    use tempfile::TempDir;
+
+    #[test]
+    fn try_sign_fails_once_the_key_is_removed() {
+        let temp_dir = TempDir::new().unwrap();
+        let keystore = Arc::new(LocalKeystore::open(temp_dir.path(), None).unwrap());
+        let public = SyncCryptoStore::sr25519_generate_new(&*keystore, SR25519, None).unwrap();
+        let signer = KeystoreSigner::new(keystore, SR25519, public).unwrap();
+
+        let signature = signer.try_sign(b"payload").unwrap();
+        assert!(signature.verify(&b"payload"[..], &signer.account_id()));
+
+        for entry in fs::read_dir(temp_dir.path()).unwrap() {
+            fs::remove_file(entry.unwrap().path()).unwrap();
+        }
+        assert_eq!(
+            signer.try_sign(b"payload"),
+            Err(SignerError("key has been removed from the keystore".into()))
+        );
+    }
+}
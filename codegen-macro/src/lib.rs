@@ -0,0 +1,102 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Attribute macro generating typed Rust code from a SCALE encoded runtime metadata file, see
+//! `ac-codegen`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use std::path::PathBuf;
+use syn::{parse_macro_input, AttributeArgs, Lit, Meta, NestedMeta};
+
+/// Generates a module per pallet of the metadata into the annotated module, e.g.
+///
+/// ```ignore
+/// #[ac_codegen_macro::runtime_metadata(path = "metadata/node_template.scale")]
+/// pub mod node_template {}
+///
+/// let call = api.metadata.static_call(node_template::balances::calls::transfer(dest, value))?;
+/// let deposit = api.get_static_constant(&node_template::balances::constants::existential_deposit())?;
+/// ```
+///
+/// Arguments:
+/// * `path` - The metadata file, as SCALE encoded V14 or V15 metadata returned by
+///   `Api::get_metadata` or `Api::get_versioned_metadata`, relative to the crate's `Cargo.toml`.
+/// * `crate_path` - Optional path of the api client crate, `::substrate_api_client` by default.
+#[proc_macro_attribute]
+pub fn runtime_metadata(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let item_mod = parse_macro_input!(input as syn::ItemMod);
+
+    let mut path = None;
+    let mut crate_path: syn::Path = syn::parse_quote!(::substrate_api_client);
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("path") => match nv.lit {
+                Lit::Str(s) => path = Some(s.value()),
+                lit => return error(lit, "expected a string"),
+            },
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("crate_path") => {
+                match nv.lit {
+                    Lit::Str(s) => match s.parse() {
+                        Ok(p) => crate_path = p,
+                        Err(e) => return e.to_compile_error().into(),
+                    },
+                    lit => return error(lit, "expected a string"),
+                }
+            }
+            other => return error(other, "expected `path = \"..\"` or `crate_path = \"..\"`"),
+        }
+    }
+    let path = match path {
+        Some(path) => path,
+        None => {
+            return error(
+                &item_mod.ident,
+                "missing `path` argument with the metadata file",
+            )
+        }
+    };
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = PathBuf::from(manifest_dir).join(path);
+    let bytes = match std::fs::read(&file) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return error(
+                &item_mod.ident,
+                &format!("cannot read metadata file {}: {}", file.display(), e),
+            )
+        }
+    };
+
+    let ident = item_mod.ident.clone();
+    match ac_codegen::generate_runtime_api_from_bytes(item_mod, &bytes, &crate_path) {
+        Ok(code) => {
+            let file = file.to_string_lossy();
+            quote! {
+                #code
+                // Rebuild when the metadata file changes.
+                const _: &[u8] = include_bytes!(#file);
+            }
+            .into()
+        }
+        Err(e) => error(ident, &e.to_string()),
+    }
+}
+
+fn error<T: quote::ToTokens>(tokens: T, message: &str) -> TokenStream {
+    syn::Error::new_spanned(tokens, message)
+        .to_compile_error()
+        .into()
+}
@@ -0,0 +1,410 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Generate typed Rust code from the runtime metadata of a node: a module per pallet with call
+//! builders, event structs, storage key builders and constant accessors, plus the types of the
+//! metadata type registry in a `runtime_types` module.
+//!
+//! The generated code refers to the api client (`substrate_api_client` by default) for the
+//! `StaticCall`, `StaticEvent`, `StaticStorageAddress` and `StaticConstantAddress` support,
+//! which resolve the pallet and call indices, storage hashers and constant values in the live
+//! `Metadata` at runtime. Use it through the `runtime_metadata` attribute of
+//! `ac-codegen-macro`, or from a build script.
+
+use ac_node_api::{
+    metadata::InvalidMetadataError,
+    v15::{VersionedMetadata, SUPPORTED_METADATA_VERSIONS},
+};
+use codec::Decode;
+use frame_metadata::META_RESERVED;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+mod pallets;
+mod types;
+
+pub use pallets::PalletGenerator;
+pub use types::{TypeGenerator, RUNTIME_TYPES};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CodegenError {
+    #[error("Error decoding the metadata: {0}")]
+    Decode(#[from] codec::Error),
+    #[error("Invalid metadata prefix")]
+    InvalidPrefix,
+    #[error("Unsupported metadata version {0}, only V14 and V15 are supported")]
+    UnsupportedVersion(u32),
+    #[error("Invalid metadata: {0:?}")]
+    InvalidMetadata(InvalidMetadataError),
+}
+
+/// Decode SCALE encoded metadata, as returned by `Api::get_metadata` or
+/// `Api::get_versioned_metadata`, and generate the code.
+pub fn generate_runtime_api_from_bytes(
+    item_mod: syn::ItemMod,
+    bytes: &[u8],
+    crate_path: &syn::Path,
+) -> Result<TokenStream, CodegenError> {
+    // The version byte follows the magic number.
+    if let Some(&version) = bytes.get(4) {
+        if !SUPPORTED_METADATA_VERSIONS.contains(&version.into()) {
+            return Err(CodegenError::UnsupportedVersion(version.into()));
+        }
+    }
+    let metadata = VersionedMetadata::decode(&mut &bytes[..])?;
+    generate_runtime_api(item_mod, metadata, crate_path)
+}
+
+/// Generate the code into the module `item_mod`, keeping the items it already has.
+///
+/// V15 metadata is generated from its V14 view, as the pallet items are the same.
+pub fn generate_runtime_api(
+    item_mod: syn::ItemMod,
+    metadata: VersionedMetadata,
+    crate_path: &syn::Path,
+) -> Result<TokenStream, CodegenError> {
+    if metadata.prefix() != META_RESERVED {
+        return Err(CodegenError::InvalidPrefix);
+    }
+    let metadata = match metadata {
+        VersionedMetadata::V14(_, metadata) => metadata,
+        VersionedMetadata::V15(_, metadata) => {
+            metadata.to_v14().map_err(CodegenError::InvalidMetadata)?
+        }
+    };
+
+    let types = TypeGenerator::new(&metadata.types, crate_path);
+    let pallet_generator = PalletGenerator::new(&types, &metadata.types, crate_path);
+    let pallets = metadata
+        .pallets
+        .iter()
+        .map(|pallet| pallet_generator.generate(pallet));
+    let runtime_types = types.generate_types_module();
+
+    let attrs = &item_mod.attrs;
+    let vis = &item_mod.vis;
+    let name = &item_mod.ident;
+    let items = item_mod
+        .content
+        .as_ref()
+        .map(|(_, items)| items.clone())
+        .unwrap_or_default();
+    Ok(quote! {
+        #(#attrs)*
+        #[allow(dead_code, unused_imports, non_camel_case_types, clippy::all)]
+        #vis mod #name {
+            #(#items)*
+            #(#pallets)*
+            #runtime_types
+        }
+    })
+}
+
+/// Identifier for a name of the metadata, raw if it is a keyword.
+pub(crate) fn ident(name: &str) -> Ident {
+    match name {
+        "self" | "Self" | "super" | "crate" => Ident::new(&format!("{}_", name), Span::call_site()),
+        _ if syn::parse_str::<Ident>(name).is_ok() => Ident::new(name, Span::call_site()),
+        _ => Ident::new_raw(name, Span::call_site()),
+    }
+}
+
+/// Doc attributes of the lines of `docs`.
+pub(crate) fn docs(docs: &[String]) -> TokenStream {
+    quote!(#(#[doc = #docs])*)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ac_node_api::{
+        test_utils,
+        v15::{
+            CustomMetadata, ExtrinsicMetadataV15, OuterEnums, PalletMetadataV15, RuntimeMetadataV15,
+        },
+    };
+    use codec::Encode;
+    use frame_metadata::{
+        ExtrinsicMetadata, PalletCallMetadata, PalletConstantMetadata, PalletEventMetadata,
+        PalletMetadata, PalletStorageMetadata, RuntimeMetadataLastVersion, StorageEntryMetadata,
+        StorageEntryModifier, StorageHasher,
+    };
+    use scale_info::{build::Fields, meta_type, Path, Type, TypeInfo, TypeParameter};
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum Call {
+        transfer {
+            dest: [u8; 32],
+            #[codec(compact)]
+            value: u128,
+        },
+        batch {
+            calls: Vec<Call>,
+        },
+        sudo {
+            call: Box<Call>,
+        },
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Event {
+        Transfer { from: [u8; 32], amount: u128 },
+        Sudid(Result<(), u8>),
+    }
+
+    /// Found by `RuntimeMetadataV15::to_v14` in the type registry, by its path and the unit
+    /// extrinsic parts of `runtime_metadata_v15`.
+    struct UncheckedExtrinsic;
+
+    impl TypeInfo for UncheckedExtrinsic {
+        type Identity = Self;
+
+        fn type_info() -> Type {
+            Type::builder()
+                .path(Path::new(
+                    "UncheckedExtrinsic",
+                    "sp_runtime::generic::unchecked_extrinsic",
+                ))
+                .type_params(
+                    ["Address", "Call", "Signature", "Extra"]
+                        .into_iter()
+                        .map(|name| TypeParameter::new(name, Some(meta_type::<()>())))
+                        .collect::<Vec<_>>(),
+                )
+                .composite(Fields::unnamed().field(|f| f.ty::<Vec<u8>>()))
+        }
+    }
+
+    fn runtime_metadata() -> RuntimeMetadataLastVersion {
+        let pallet = PalletMetadata {
+            storage: Some(PalletStorageMetadata {
+                prefix: "Balances",
+                entries: vec![StorageEntryMetadata {
+                    name: "Account",
+                    modifier: StorageEntryModifier::Default,
+                    ty: frame_metadata::StorageEntryType::Map {
+                        hashers: vec![StorageHasher::Blake2_128Concat],
+                        key: meta_type::<[u8; 32]>(),
+                        value: meta_type::<u128>(),
+                    },
+                    default: vec![0; 16],
+                    docs: vec![],
+                }],
+            }),
+            calls: Some(PalletCallMetadata {
+                ty: meta_type::<Call>(),
+            }),
+            event: Some(PalletEventMetadata {
+                ty: meta_type::<Event>(),
+            }),
+            constants: vec![PalletConstantMetadata {
+                name: "ExistentialDeposit",
+                ty: meta_type::<u128>(),
+                value: vec![0; 16],
+                docs: vec![],
+            }],
+            ..test_utils::pallet("Balances", 5)
+        };
+        let extrinsic = ExtrinsicMetadata {
+            ty: meta_type::<UncheckedExtrinsic>(),
+            ..test_utils::extrinsic()
+        };
+        test_utils::runtime_metadata_with_extrinsic(vec![pallet], extrinsic)
+    }
+
+    /// `runtime_metadata` in the V15 layout.
+    fn runtime_metadata_v15() -> RuntimeMetadataV15 {
+        let v14 = runtime_metadata();
+        let unit = v14.ty;
+        RuntimeMetadataV15 {
+            types: v14.types,
+            pallets: v14
+                .pallets
+                .into_iter()
+                .map(|pallet| PalletMetadataV15 {
+                    name: pallet.name,
+                    storage: pallet.storage,
+                    calls: pallet.calls,
+                    event: pallet.event,
+                    constants: pallet.constants,
+                    error: pallet.error,
+                    index: pallet.index,
+                    docs: vec![],
+                })
+                .collect(),
+            extrinsic: ExtrinsicMetadataV15 {
+                version: v14.extrinsic.version,
+                address_ty: unit,
+                call_ty: unit,
+                signature_ty: unit,
+                extra_ty: unit,
+                signed_extensions: v14.extrinsic.signed_extensions,
+            },
+            ty: unit,
+            apis: vec![],
+            outer_enums: OuterEnums {
+                call_enum_ty: unit,
+                event_enum_ty: unit,
+                error_enum_ty: unit,
+            },
+            custom: CustomMetadata::default(),
+        }
+    }
+
+    fn generate_from(metadata: VersionedMetadata) -> Result<syn::ItemMod, CodegenError> {
+        let item_mod: syn::ItemMod = syn::parse_quote!(
+            pub mod runtime {}
+        );
+        let crate_path: syn::Path = syn::parse_quote!(::substrate_api_client);
+        let code = generate_runtime_api_from_bytes(item_mod, &metadata.encode(), &crate_path)?;
+        Ok(syn::parse2(code).expect("generated code parses"))
+    }
+
+    fn generate() -> syn::ItemMod {
+        generate_from(VersionedMetadata::V14(META_RESERVED, runtime_metadata())).unwrap()
+    }
+
+    /// Items of the module at `path` in `item_mod`.
+    fn module<'a>(item_mod: &'a syn::ItemMod, path: &[&str]) -> &'a [syn::Item] {
+        let items = |item_mod: &'a syn::ItemMod| &item_mod.content.as_ref().unwrap().1[..];
+        path.iter().fold(items(item_mod), |parent, name| {
+            parent
+                .iter()
+                .find_map(|item| match item {
+                    syn::Item::Mod(item_mod) if item_mod.ident == name => Some(items(item_mod)),
+                    _ => None,
+                })
+                .unwrap_or_else(|| panic!("module {} is missing", name))
+        })
+    }
+
+    fn function<'a>(items: &'a [syn::Item], name: &str) -> &'a syn::Signature {
+        items
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Fn(function) if function.sig.ident == name => Some(&function.sig),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("function {} is missing", name))
+    }
+
+    /// Value of the associated constant `name` of the trait implementation for `self_ty`.
+    fn associated_const<'a>(items: &'a [syn::Item], self_ty: &str, name: &str) -> &'a syn::Expr {
+        let self_ty: syn::Type = syn::parse_str(self_ty).unwrap();
+        items
+            .iter()
+            .filter_map(|item| match item {
+                syn::Item::Impl(item_impl) if *item_impl.self_ty == self_ty => Some(item_impl),
+                _ => None,
+            })
+            .flat_map(|item_impl| &item_impl.items)
+            .find_map(|item| match item {
+                syn::ImplItem::Const(constant) if constant.ident == name => Some(&constant.expr),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("constant {} is missing", name))
+    }
+
+    /// Field types of the variant `name` of the enum `ty`.
+    fn variant_fields<'a>(items: &'a [syn::Item], ty: &str, name: &str) -> Vec<&'a syn::Type> {
+        items
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Enum(item_enum) if item_enum.ident == ty => item_enum
+                    .variants
+                    .iter()
+                    .find(|variant| variant.ident == name),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("variant {}::{} is missing", ty, name))
+            .fields
+            .iter()
+            .map(|field| &field.ty)
+            .collect()
+    }
+
+    #[test]
+    fn generates_pallet_items() {
+        let runtime = generate();
+        let calls = module(&runtime, &["balances", "calls"]);
+        let transfer: syn::Signature = syn::parse_quote!(
+            fn transfer(
+                dest: [u8; 32usize],
+                value: ::substrate_api_client::codec::Compact<u128>
+            ) -> Transfer
+        );
+        assert_eq!(function(calls, "transfer"), &transfer);
+        let call_name: syn::Expr = syn::parse_quote!("transfer");
+        assert_eq!(associated_const(calls, "Transfer", "CALL"), &call_name);
+
+        let events = module(&runtime, &["balances", "events"]);
+        let event_name: syn::Expr = syn::parse_quote!("Sudid");
+        assert_eq!(associated_const(events, "Sudid", "EVENT"), &event_name);
+
+        let storage = module(&runtime, &["balances", "storage"]);
+        let account: syn::Signature = syn::parse_quote!(
+            fn account(_0: &[u8; 32usize]) -> ::substrate_api_client::StaticStorageAddress<u128>
+        );
+        assert_eq!(function(storage, "account"), &account);
+
+        let constants = module(&runtime, &["balances", "constants"]);
+        let existential_deposit: syn::Signature = syn::parse_quote!(
+            fn existential_deposit() -> ::substrate_api_client::StaticConstantAddress<u128>
+        );
+        assert_eq!(
+            function(constants, "existential_deposit"),
+            &existential_deposit
+        );
+    }
+
+    #[test]
+    fn boxes_recursive_types() {
+        let runtime = generate();
+        let types = module(&runtime, &[RUNTIME_TYPES, "ac_codegen", "tests"]);
+        let call: syn::Type =
+            syn::parse_quote!(super::super::super::runtime_types::ac_codegen::tests::Call);
+        let boxed: syn::Type = syn::parse_quote!(::std::boxed::Box<#call>);
+        assert_eq!(variant_fields(types, "Call", "sudo"), vec![&boxed]);
+        // Behind a heap allocation already.
+        let calls: syn::Type = syn::parse_quote!(::std::vec::Vec<#call>);
+        assert_eq!(variant_fields(types, "Call", "batch"), vec![&calls]);
+    }
+
+    #[test]
+    fn generates_the_same_code_from_v15() {
+        let v15 = VersionedMetadata::V15(META_RESERVED, runtime_metadata_v15());
+        assert_eq!(generate_from(v15).unwrap(), generate());
+    }
+
+    #[test]
+    fn rejects_other_versions_and_prefixes() {
+        let mut encoded = VersionedMetadata::V14(META_RESERVED, runtime_metadata()).encode();
+        encoded[4] = 13;
+        let item_mod: syn::ItemMod = syn::parse_quote!(
+            pub mod runtime {}
+        );
+        let crate_path: syn::Path = syn::parse_quote!(::substrate_api_client);
+        assert!(matches!(
+            generate_runtime_api_from_bytes(item_mod, &encoded, &crate_path),
+            Err(CodegenError::UnsupportedVersion(13))
+        ));
+
+        let metadata = VersionedMetadata::V14(0, runtime_metadata());
+        assert!(matches!(
+            generate_from(metadata),
+            Err(CodegenError::InvalidPrefix)
+        ));
+    }
+}
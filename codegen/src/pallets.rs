@@ -0,0 +1,228 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! A module per pallet with its calls, events, storage entries and constants.
+
+use crate::{docs, ident, types::TypeGenerator};
+use frame_metadata::{PalletMetadata, StorageEntryType};
+use heck::{ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use scale_info::{form::PortableForm, TypeDef, Variant};
+
+/// Depth of the items of a pallet module, e.g. `balances::calls`, below the generated root
+/// module.
+const ITEM_DEPTH: usize = 2;
+
+pub struct PalletGenerator<'a> {
+    types: &'a TypeGenerator<'a>,
+    registry: &'a scale_info::PortableRegistry,
+    crate_path: &'a syn::Path,
+}
+
+impl<'a> PalletGenerator<'a> {
+    pub fn new(
+        types: &'a TypeGenerator<'a>,
+        registry: &'a scale_info::PortableRegistry,
+        crate_path: &'a syn::Path,
+    ) -> Self {
+        Self {
+            types,
+            registry,
+            crate_path,
+        }
+    }
+
+    pub fn generate(&self, pallet: &PalletMetadata<PortableForm>) -> TokenStream {
+        let module = ident(&pallet.name.to_snake_case());
+        let name = &pallet.name;
+        let calls = pallet
+            .calls
+            .as_ref()
+            .map(|calls| self.calls(name, calls.ty.id()));
+        let events = pallet
+            .event
+            .as_ref()
+            .map(|event| self.events(name, event.ty.id()));
+        let storage = pallet.storage.as_ref().map(|storage| {
+            let entries = storage.entries.iter().map(|entry| {
+                let docs = docs(&entry.docs);
+                let function = ident(&entry.name.to_snake_case());
+                let entry_name = &entry.name;
+                let (value, key_types) = match &entry.ty {
+                    StorageEntryType::Plain(value) => (value.id(), Vec::new()),
+                    StorageEntryType::Map {
+                        hashers,
+                        key,
+                        value,
+                    } => (value.id(), self.key_types(key.id(), hashers.len())),
+                };
+                let value = self.types.type_path(value, ITEM_DEPTH);
+                let keys: Vec<_> = (0..key_types.len()).map(|i| format_ident!("_{}", i)).collect();
+                let key_types = key_types
+                    .iter()
+                    .map(|id| self.types.type_path(*id, ITEM_DEPTH));
+                let crate_path = self.crate_path;
+                quote! {
+                    #docs
+                    pub fn #function(#(#keys: &#key_types),*) -> #crate_path::StaticStorageAddress<#value> {
+                        #crate_path::StaticStorageAddress::new(
+                            PALLET,
+                            #entry_name,
+                            ::std::vec![#(#crate_path::codec::Encode::encode(#keys)),*],
+                        )
+                    }
+                }
+            });
+            quote! {
+                pub mod storage {
+                    use super::PALLET;
+                    #(#entries)*
+                }
+            }
+        });
+        let constants = pallet.constants.iter().map(|constant| {
+            let docs = docs(&constant.docs);
+            let function = ident(&constant.name.to_snake_case());
+            let constant_name = &constant.name;
+            let ty = self.types.type_path(constant.ty.id(), ITEM_DEPTH);
+            let crate_path = self.crate_path;
+            quote! {
+                #docs
+                pub fn #function() -> #crate_path::StaticConstantAddress<#ty> {
+                    #crate_path::StaticConstantAddress::new(PALLET, #constant_name)
+                }
+            }
+        });
+
+        quote! {
+            pub mod #module {
+                /// Name of the pallet.
+                pub const PALLET: &str = #name;
+
+                #calls
+                #events
+                #storage
+
+                pub mod constants {
+                    #[allow(unused_imports)]
+                    use super::PALLET;
+                    #(#constants)*
+                }
+            }
+        }
+    }
+
+    /// A struct per call, implementing `StaticCall`, and a function building it.
+    fn calls(&self, pallet: &str, calls_ty: u32) -> TokenStream {
+        let crate_path = self.crate_path;
+        let derives = self.types.derives();
+        let calls = self.variants(calls_ty).iter().map(|call| {
+            let docs = docs(call.docs());
+            let call_name = call.name();
+            let name = format_ident!("{}", call_name.to_upper_camel_case());
+            let function = ident(&call_name.to_snake_case());
+            let args: Vec<_> = call
+                .fields()
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    field
+                        .name()
+                        .map(|name| ident(&name.to_snake_case()))
+                        .unwrap_or_else(|| format_ident!("_{}", i))
+                })
+                .collect();
+            let arg_types: Vec<_> = call
+                .fields()
+                .iter()
+                .map(|field| self.types.type_path(field.ty().id(), ITEM_DEPTH))
+                .collect();
+            quote! {
+                #docs
+                #derives
+                pub struct #name {
+                    #(pub #args: #arg_types,)*
+                }
+
+                impl #crate_path::calls::StaticCall for #name {
+                    const PALLET: &'static str = #pallet;
+                    const CALL: &'static str = #call_name;
+
+                    fn encoded_args(&self) -> ::std::vec::Vec<::std::vec::Vec<u8>> {
+                        ::std::vec![#(#crate_path::codec::Encode::encode(&self.#args)),*]
+                    }
+                }
+
+                #docs
+                pub fn #function(#(#args: #arg_types),*) -> #name {
+                    #name { #(#args),* }
+                }
+            }
+        });
+        quote! {
+            pub mod calls {
+                #(#calls)*
+            }
+        }
+    }
+
+    /// A struct per event, implementing `StaticEvent`.
+    fn events(&self, pallet: &str, events_ty: u32) -> TokenStream {
+        let crate_path = self.crate_path;
+        let derives = self.types.derives();
+        let events = self.variants(events_ty).iter().map(|event| {
+            let docs = docs(event.docs());
+            let event_name = event.name();
+            let name = ident(event_name);
+            let fields = self
+                .types
+                .struct_fields(events_ty, event.fields(), ITEM_DEPTH);
+            quote! {
+                #docs
+                #derives
+                pub struct #name #fields
+
+                impl #crate_path::StaticEvent for #name {
+                    const PALLET: &'static str = #pallet;
+                    const EVENT: &'static str = #event_name;
+                }
+            }
+        });
+        quote! {
+            pub mod events {
+                #(#events)*
+            }
+        }
+    }
+
+    fn variants(&self, id: u32) -> &'a [Variant<PortableForm>] {
+        match self.registry.resolve(id).map(|ty| ty.type_def()) {
+            Some(TypeDef::Variant(variant)) => variant.variants(),
+            _ => &[],
+        }
+    }
+
+    /// Types of the map keys, one per hasher. Several hashers hash the fields of a tuple key.
+    fn key_types(&self, key: u32, hashers: usize) -> Vec<u32> {
+        if hashers > 1 {
+            if let Some(TypeDef::Tuple(tuple)) = self.registry.resolve(key).map(|ty| ty.type_def())
+            {
+                if tuple.fields().len() == hashers {
+                    return tuple.fields().iter().map(|field| field.id()).collect();
+                }
+            }
+        }
+        vec![key]
+    }
+}
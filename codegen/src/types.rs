@@ -0,0 +1,476 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Rust types for the types of the metadata type registry.
+
+use crate::{docs, ident};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use scale_info::{
+    form::PortableForm, Field, PortableRegistry, Type, TypeDef, TypeDefPrimitive, Variant,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Name of the module holding the types of the registry.
+pub const RUNTIME_TYPES: &str = "runtime_types";
+
+/// Types of the registry with a well-known Rust counterpart, which are not generated.
+enum WellKnown {
+    Option,
+    Result,
+    /// A composite type wrapping its single field with the same encoding, e.g. `Cow` or
+    /// `BoundedVec`.
+    Transparent,
+    BTreeMap,
+    BTreeSet,
+}
+
+/// Generates a Rust type for each composite and variant type of the registry, placed in a
+/// module tree following the type's path, and resolves references to registry types.
+///
+/// Types are generated per registry entry, i.e. without generic parameters: each instance of a
+/// generic type gets its own Rust type, numbered if there are several.
+pub struct TypeGenerator<'a> {
+    registry: &'a PortableRegistry,
+    crate_path: &'a syn::Path,
+    /// Module path and name of each generated type.
+    names: BTreeMap<u32, (Vec<String>, String)>,
+}
+
+impl<'a> TypeGenerator<'a> {
+    pub fn new(registry: &'a PortableRegistry, crate_path: &'a syn::Path) -> Self {
+        let mut by_path = BTreeMap::<Vec<String>, Vec<u32>>::new();
+        for ty in registry.types() {
+            if Self::is_generated(ty.ty()) {
+                let mut path = ty.ty().path().segments().to_vec();
+                if path.is_empty() {
+                    path.push(format!("Type{}", ty.id()));
+                }
+                by_path.entry(path).or_default().push(ty.id());
+            }
+        }
+
+        let mut names = BTreeMap::new();
+        for (mut path, ids) in by_path {
+            let name = path.pop().expect("path is not empty; qed");
+            let numbered = ids.len() > 1;
+            for (n, id) in ids.into_iter().enumerate() {
+                let name = if numbered {
+                    format!("{}{}", name, n + 1)
+                } else {
+                    name.clone()
+                };
+                names.insert(id, (path.clone(), name));
+            }
+        }
+
+        Self {
+            registry,
+            crate_path,
+            names,
+        }
+    }
+
+    fn is_generated(ty: &Type<PortableForm>) -> bool {
+        matches!(ty.type_def(), TypeDef::Composite(_) | TypeDef::Variant(_))
+            && Self::well_known(ty).is_none()
+    }
+
+    fn well_known(ty: &Type<PortableForm>) -> Option<WellKnown> {
+        let segments = ty.path().segments();
+        let single_field = match ty.type_def() {
+            TypeDef::Composite(composite) => composite.fields().len() == 1,
+            _ => false,
+        };
+        match (segments.len(), segments.last().map(String::as_str)) {
+            (1, Some("Option")) => Some(WellKnown::Option),
+            (1, Some("Result")) => Some(WellKnown::Result),
+            (1, Some("BTreeMap")) if single_field => Some(WellKnown::BTreeMap),
+            (1, Some("BTreeSet")) if single_field => Some(WellKnown::BTreeSet),
+            (1, Some("Cow")) if single_field => Some(WellKnown::Transparent),
+            (_, Some("BoundedVec" | "WeakBoundedVec" | "BoundedBTreeMap" | "BoundedBTreeSet"))
+                if single_field =>
+            {
+                Some(WellKnown::Transparent)
+            }
+            _ => None,
+        }
+    }
+
+    fn resolve(&self, id: u32) -> &'a Type<PortableForm> {
+        self.registry
+            .resolve(id)
+            .unwrap_or_else(|| panic!("type {} is missing from the registry", id))
+    }
+
+    /// Rust type referring to the registry type `id`, from a module `depth` levels below the
+    /// root module of the generated code.
+    pub fn type_path(&self, id: u32, depth: usize) -> TokenStream {
+        if let Some((modules, name)) = self.names.get(&id) {
+            let supers = (0..depth).map(|_| quote!(super::));
+            let runtime_types = format_ident!("{}", RUNTIME_TYPES);
+            let modules = modules.iter().map(|m| ident(m));
+            let name = ident(name);
+            return quote!(#(#supers)* #runtime_types :: #(#modules ::)* #name);
+        }
+
+        let ty = self.resolve(id);
+        match ty.type_def() {
+            TypeDef::Composite(composite) => {
+                let fields = composite.fields();
+                match Self::well_known(ty) {
+                    Some(WellKnown::Transparent) => self.type_path(fields[0].ty().id(), depth),
+                    Some(WellKnown::BTreeMap) => {
+                        let (key, value) = self.map_entry(fields[0].ty().id(), depth);
+                        quote!(::std::collections::BTreeMap<#key, #value>)
+                    }
+                    Some(WellKnown::BTreeSet) => {
+                        let element = self.sequence_element(fields[0].ty().id(), depth);
+                        quote!(::std::collections::BTreeSet<#element>)
+                    }
+                    _ => unreachable!("other composites are generated; qed"),
+                }
+            }
+            TypeDef::Variant(variant) => {
+                let field_of = |name: &str| {
+                    variant
+                        .variants()
+                        .iter()
+                        .find(|v| v.name() == name)
+                        .and_then(|v| v.fields().first())
+                        .map(|field| self.type_path(field.ty().id(), depth))
+                        .unwrap_or_else(|| quote!(()))
+                };
+                match Self::well_known(ty) {
+                    Some(WellKnown::Option) => {
+                        let some = field_of("Some");
+                        quote!(::core::option::Option<#some>)
+                    }
+                    Some(WellKnown::Result) => {
+                        let (ok, err) = (field_of("Ok"), field_of("Err"));
+                        quote!(::core::result::Result<#ok, #err>)
+                    }
+                    _ => unreachable!("other variants are generated; qed"),
+                }
+            }
+            TypeDef::Sequence(sequence) => {
+                let element = self.type_path(sequence.type_param().id(), depth);
+                quote!(::std::vec::Vec<#element>)
+            }
+            TypeDef::Array(array) => {
+                let element = self.type_path(array.type_param().id(), depth);
+                let len = array.len() as usize;
+                quote!([#element; #len])
+            }
+            TypeDef::Tuple(tuple) => {
+                let elements = tuple
+                    .fields()
+                    .iter()
+                    .map(|field| self.type_path(field.id(), depth));
+                quote!((#(#elements,)*))
+            }
+            TypeDef::Primitive(primitive) => primitive_path(primitive),
+            TypeDef::Compact(compact) => {
+                let inner = self.compact_inner(compact.type_param().id(), depth);
+                let crate_path = self.crate_path;
+                quote!(#crate_path::codec::Compact<#inner>)
+            }
+            TypeDef::BitSequence(bit_sequence) => {
+                let store = self.type_path(bit_sequence.bit_store_type().id(), depth);
+                let crate_path = self.crate_path;
+                quote!(#crate_path::RawBitSequence<#store>)
+            }
+        }
+    }
+
+    /// Compact encoding is defined for unsigned integers and single field wrappers of them.
+    /// The wrappers are represented by their encoding compatible integer.
+    fn compact_inner(&self, id: u32, depth: usize) -> TokenStream {
+        match self.resolve(id).type_def() {
+            TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+                self.compact_inner(composite.fields()[0].ty().id(), depth)
+            }
+            TypeDef::Composite(composite) if composite.fields().is_empty() => quote!(()),
+            TypeDef::Tuple(tuple) if tuple.fields().len() == 1 => {
+                self.compact_inner(tuple.fields()[0].id(), depth)
+            }
+            _ => self.type_path(id, depth),
+        }
+    }
+
+    fn sequence_element(&self, id: u32, depth: usize) -> TokenStream {
+        match self.resolve(id).type_def() {
+            TypeDef::Sequence(sequence) => self.type_path(sequence.type_param().id(), depth),
+            _ => quote!(()),
+        }
+    }
+
+    /// Key and value type of a map encoded as sequence of key value tuples.
+    fn map_entry(&self, id: u32, depth: usize) -> (TokenStream, TokenStream) {
+        if let TypeDef::Sequence(sequence) = self.resolve(id).type_def() {
+            if let TypeDef::Tuple(tuple) = self.resolve(sequence.type_param().id()).type_def() {
+                if let [key, value] = tuple.fields() {
+                    return (
+                        self.type_path(key.id(), depth),
+                        self.type_path(value.id(), depth),
+                    );
+                }
+            }
+        }
+        (quote!(()), quote!(()))
+    }
+
+    /// The `runtime_types` module with all generated types.
+    pub fn generate_types_module(&self) -> TokenStream {
+        let mut root = Module::default();
+        for (id, (modules, name)) in self.names.iter() {
+            let depth = 1 + modules.len();
+            let item = self.generate_type(*id, name, depth);
+            modules
+                .iter()
+                .fold(&mut root, |module, segment| {
+                    module.children.entry(segment.clone()).or_default()
+                })
+                .items
+                .push(item);
+        }
+        root.into_tokens(RUNTIME_TYPES)
+    }
+
+    fn generate_type(&self, id: u32, name: &str, depth: usize) -> TokenStream {
+        let ty = self.resolve(id);
+        let docs = docs(ty.docs());
+        let name = ident(name);
+        let derives = self.derives();
+        match ty.type_def() {
+            TypeDef::Composite(composite) => {
+                let fields = self.struct_fields(id, composite.fields(), depth);
+                quote! {
+                    #docs
+                    #derives
+                    pub struct #name #fields
+                }
+            }
+            TypeDef::Variant(variant) => {
+                let variants = variant
+                    .variants()
+                    .iter()
+                    .map(|v| self.variant(id, v, depth));
+                quote! {
+                    #docs
+                    #derives
+                    pub enum #name {
+                        #(#variants,)*
+                    }
+                }
+            }
+            _ => unreachable!("only composites and variants are generated; qed"),
+        }
+    }
+
+    /// Derives of generated types, with the codec derives taken from the api client.
+    pub fn derives(&self) -> TokenStream {
+        let crate_path = self.crate_path;
+        quote! {
+            #[derive(
+                #crate_path::codec::Encode,
+                #crate_path::codec::Decode,
+                Clone,
+                Debug,
+                PartialEq,
+                Eq,
+                PartialOrd,
+                Ord
+            )]
+            #[codec(crate = #crate_path::codec)]
+        }
+    }
+
+    fn variant(&self, owner: u32, variant: &Variant<PortableForm>, depth: usize) -> TokenStream {
+        let docs = docs(variant.docs());
+        let name = ident(variant.name());
+        let index = variant.index();
+        let fields = match self.fields(owner, variant.fields(), depth, false) {
+            Fields::Named(fields) => quote!({ #(#fields,)* }),
+            Fields::Unnamed(fields) => quote!((#(#fields,)*)),
+            Fields::Unit => quote!(),
+        };
+        quote! {
+            #docs
+            #[codec(index = #index)]
+            #name #fields
+        }
+    }
+
+    /// Fields of a struct named after a type `owner` of the registry, including the trailing
+    /// semicolon if needed.
+    pub fn struct_fields(
+        &self,
+        owner: u32,
+        fields: &[Field<PortableForm>],
+        depth: usize,
+    ) -> TokenStream {
+        match self.fields(owner, fields, depth, true) {
+            Fields::Named(fields) => quote!({ #(#fields,)* }),
+            Fields::Unnamed(fields) => quote!((#(#fields,)*);),
+            Fields::Unit => quote!(;),
+        }
+    }
+
+    fn fields(
+        &self,
+        owner: u32,
+        fields: &[Field<PortableForm>],
+        depth: usize,
+        public: bool,
+    ) -> Fields {
+        if fields.is_empty() {
+            return Fields::Unit;
+        }
+        let visibility = if public { quote!(pub) } else { quote!() };
+        let types = fields.iter().map(|field| {
+            let ty = self.type_path(field.ty().id(), depth);
+            if self.needs_box(owner, field.ty().id()) {
+                quote!(::std::boxed::Box<#ty>)
+            } else {
+                ty
+            }
+        });
+        if fields.iter().all(|field| field.name().is_some()) {
+            Fields::Named(
+                fields
+                    .iter()
+                    .zip(types)
+                    .map(|(field, ty)| {
+                        let docs = docs(field.docs());
+                        let name = ident(field.name().expect("all fields are named; qed"));
+                        quote!(#docs #visibility #name: #ty)
+                    })
+                    .collect(),
+            )
+        } else {
+            Fields::Unnamed(types.map(|ty| quote!(#visibility #ty)).collect())
+        }
+    }
+
+    /// Generated types embedded in a value of type `id`, i.e. not behind a heap allocation.
+    fn embedded(&self, id: u32, embedded: &mut BTreeSet<u32>) {
+        if self.names.contains_key(&id) {
+            embedded.insert(id);
+            return;
+        }
+        let ty = self.resolve(id);
+        match ty.type_def() {
+            TypeDef::Composite(composite) => {
+                if let Some(WellKnown::Transparent) = Self::well_known(ty) {
+                    self.embedded(composite.fields()[0].ty().id(), embedded)
+                }
+            }
+            TypeDef::Variant(variant) => {
+                for field in variant.variants().iter().flat_map(|v| v.fields()) {
+                    self.embedded(field.ty().id(), embedded)
+                }
+            }
+            TypeDef::Array(array) => self.embedded(array.type_param().id(), embedded),
+            TypeDef::Tuple(tuple) => {
+                for field in tuple.fields() {
+                    self.embedded(field.id(), embedded)
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Generated types embedded in the fields of the generated type `id`.
+    fn embedded_in_fields(&self, id: u32) -> BTreeSet<u32> {
+        let mut embedded = BTreeSet::new();
+        let fields: Vec<&Field<PortableForm>> = match self.resolve(id).type_def() {
+            TypeDef::Composite(composite) => composite.fields().iter().collect(),
+            TypeDef::Variant(variant) => {
+                variant.variants().iter().flat_map(|v| v.fields()).collect()
+            }
+            _ => Vec::new(),
+        };
+        for field in fields {
+            self.embedded(field.ty().id(), &mut embedded);
+        }
+        embedded
+    }
+
+    /// A field of type `field_ty` in the generated type `owner` must be boxed if it embeds
+    /// `owner` itself, directly or through other types, to give `owner` a finite size.
+    fn needs_box(&self, owner: u32, field_ty: u32) -> bool {
+        let mut embedded = BTreeSet::new();
+        self.embedded(field_ty, &mut embedded);
+        let mut pending: Vec<u32> = embedded.into_iter().collect();
+        let mut visited = BTreeSet::new();
+        while let Some(id) = pending.pop() {
+            if id == owner {
+                return true;
+            }
+            if visited.insert(id) {
+                pending.extend(self.embedded_in_fields(id));
+            }
+        }
+        false
+    }
+}
+
+enum Fields {
+    Named(Vec<TokenStream>),
+    Unnamed(Vec<TokenStream>),
+    Unit,
+}
+
+#[derive(Default)]
+struct Module {
+    children: BTreeMap<String, Module>,
+    items: Vec<TokenStream>,
+}
+
+impl Module {
+    fn into_tokens(self, name: &str) -> TokenStream {
+        let name = ident(name);
+        let items = self.items;
+        let children = self
+            .children
+            .into_iter()
+            .map(|(name, module)| module.into_tokens(&name));
+        quote! {
+            pub mod #name {
+                #(#items)*
+                #(#children)*
+            }
+        }
+    }
+}
+
+fn primitive_path(primitive: &TypeDefPrimitive) -> TokenStream {
+    match primitive {
+        TypeDefPrimitive::Bool => quote!(bool),
+        TypeDefPrimitive::Char => quote!(char),
+        TypeDefPrimitive::Str => quote!(::std::string::String),
+        TypeDefPrimitive::U8 => quote!(u8),
+        TypeDefPrimitive::U16 => quote!(u16),
+        TypeDefPrimitive::U32 => quote!(u32),
+        TypeDefPrimitive::U64 => quote!(u64),
+        TypeDefPrimitive::U128 => quote!(u128),
+        // Little endian bytes.
+        TypeDefPrimitive::U256 | TypeDefPrimitive::I256 => quote!([u8; 32]),
+        TypeDefPrimitive::I8 => quote!(i8),
+        TypeDefPrimitive::I16 => quote!(i16),
+        TypeDefPrimitive::I32 => quote!(i32),
+        TypeDefPrimitive::I64 => quote!(i64),
+        TypeDefPrimitive::I128 => quote!(i128),
+    }
+}
@@ -20,8 +20,8 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 // re-export for macro resolution
+pub use ac_node_api as node_api;
 pub use ac_primitives as primitives;
-#[cfg(feature = "std")]
 pub use codec;
 #[cfg(feature = "std")]
 pub use log;
@@ -36,6 +36,8 @@ pub use sp_runtime;
 /// * 'call' - Call name as &str
 /// * 'args' - Optional sequence of arguments of the call. They are not checked against the metadata.
 /// As of now the user needs to check himself that the correct arguments are supplied.
+///
+/// Panics if the pallet or the call does not exist. Use `try_compose_call!` to get an error instead.
 #[macro_export]
 macro_rules! compose_call {
 ($node_metadata: expr, $pallet: expr, $call_name: expr $(, $args: expr) *) => {
@@ -49,40 +51,124 @@ macro_rules! compose_call {
     };
 }
 
+/// Generates the extrinsic's call field like `compose_call!`, but returns
+/// `Result<call, MetadataError>` instead of panicking.
+///
+/// Fails with `MetadataError::PalletNotFound` or `MetadataError::CallNotFound` if the metadata
+/// does not know the pallet or the call.
+/// # Arguments
+///
+/// * 'node_metadata' - This crate's parsed node metadata as field of the API.
+/// * 'module' - Module name as &str for which the call is composed.
+/// * 'call' - Call name as &str
+/// * 'args' - Optional sequence of arguments of the call. They are not checked against the metadata.
+#[macro_export]
+macro_rules! try_compose_call {
+($node_metadata: expr, $pallet: expr, $call_name: expr $(, $args: expr) *) => {
+        {
+            let call_name = $call_name;
+            $node_metadata.pallet($pallet).and_then(|pallet| {
+                pallet
+                    .calls
+                    .get(call_name)
+                    .map(|call_index| [pallet.index, *call_index as u8])
+                    .ok_or($crate::node_api::metadata::MetadataError::CallNotFound(call_name))
+            })
+            .map(|call_index| (call_index $(, ($args)) *))
+        }
+    };
+}
+
+/// Generates the extrinsic's call field like `compose_call!`, but checks the arguments against
+/// the call's field types in the metadata before.
+///
+/// Returns `Result<call, MetadataError>`. The error names the mismatching field if an argument
+/// does not decode to exactly its field type, e.g. because `Compact` is missing or superfluous,
+/// an enum variant does not exist, or the number of arguments is wrong.
+/// # Arguments
+///
+/// * 'node_metadata' - This crate's parsed node metadata as field of the API.
+/// * 'module' - Module name as &str for which the call is composed.
+/// * 'call' - Call name as &str
+/// * 'args' - Optional sequence of arguments of the call.
+#[macro_export]
+macro_rules! compose_call_checked {
+($node_metadata: expr, $pallet: expr, $call_name: expr $(, $args: expr) *) => {
+        $crate::__compose_call_checked!(@bind $node_metadata, $pallet, $call_name; []; $($args),*)
+    };
+}
+
+/// Binds every argument of `compose_call_checked!` to a variable, so each one is evaluated
+/// exactly once but can be encoded for the check and moved into the call afterwards.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __compose_call_checked {
+    (@bind $node_metadata: expr, $pallet: expr, $call_name: expr; [$($bound: ident)*];) => {
+        {
+            let metadata = &$node_metadata;
+            metadata
+                .checked_call_index(
+                    $pallet,
+                    $call_name,
+                    &[$($crate::codec::Encode::encode(&$bound)),*],
+                )
+                .map(|call_index| (call_index $(, $bound) *))
+        }
+    };
+    (@bind $node_metadata: expr, $pallet: expr, $call_name: expr; [$($bound: ident)*]; $head: expr $(, $tail: expr) *) => {
+        {
+            let arg = $head;
+            $crate::__compose_call_checked!(@bind $node_metadata, $pallet, $call_name; [$($bound)* arg]; $($tail),*)
+        }
+    };
+}
+
 /// Generates an Unchecked extrinsic for a given call
 /// # Arguments
 ///
-/// * 'signer' - AccountKey that is used to sign the extrinsic.
+/// * 'signer' - Any `Signer`, e.g. a `Pair`, that is used to sign the extrinsic.
 /// * 'call' - call as returned by the compose_call! macro or via substrate's call enums.
-/// * 'nonce' - signer's account nonce: u32
+/// * 'nonce' - signer's account nonce, see `Config::Index`
 /// * 'era' - Era for extrinsic to be valid
-/// * 'genesis_hash' - sp-runtime::Hash256/[u8; 32].
+/// * 'genesis_hash' - genesis hash of the chain, see `Config::Hash`
 /// * 'runtime_spec_version' - RuntimeVersion.spec_version/u32
 #[macro_export]
 macro_rules! compose_extrinsic_offline {
     ($signer: expr,
     $call: expr,
     $params: expr) => {{
-        use $crate::primitives::{
-            ExtrinsicParams, GenericAddress, SignedPayload, UncheckedExtrinsicV4,
-        };
-        use $crate::sp_core::crypto::Pair;
-        use $crate::sp_runtime::{generic::Era, traits::IdentifyAccount, MultiSigner};
+        use $crate::primitives::{ExtrinsicParams, SignedPayload, Signer, UncheckedExtrinsicV4};
 
+        let signer = &$signer;
         let extra = $params.signed_extra();
         let raw_payload =
-            SignedPayload::from_raw($call.clone(), extra, $params.additional_signed());
+            SignedPayload::from_raw($call.clone(), extra.clone(), $params.additional_signed());
 
-        let signature = raw_payload.using_encoded(|payload| $signer.sign(payload));
+        let signature = raw_payload.using_encoded(|payload| Signer::sign(signer, payload));
 
-        let multi_signer: MultiSigner = $signer.public().into();
+        UncheckedExtrinsicV4::new_signed($call, Signer::address(signer), signature, extra)
+    }};
+}
+
+/// Generates an Unchecked extrinsic like `compose_extrinsic_offline!`, but returns
+/// `Result<extrinsic, SignerError>` instead of panicking if the signer fails to sign.
+#[macro_export]
+macro_rules! try_compose_extrinsic_offline {
+    ($signer: expr,
+    $call: expr,
+    $params: expr) => {{
+        use $crate::primitives::{ExtrinsicParams, SignedPayload, Signer, UncheckedExtrinsicV4};
+
+        let signer = &$signer;
+        let extra = $params.signed_extra();
+        let raw_payload =
+            SignedPayload::from_raw($call.clone(), extra.clone(), $params.additional_signed());
 
-        UncheckedExtrinsicV4::new_signed(
-            $call,
-            GenericAddress::from(multi_signer.into_account()),
-            signature.into(),
-            extra,
-        )
+        raw_payload
+            .using_encoded(|payload| Signer::try_sign(signer, payload))
+            .map(|signature| {
+                UncheckedExtrinsicV4::new_signed($call, Signer::address(signer), signature, extra)
+            })
     }};
 }
 
@@ -94,6 +180,9 @@ macro_rules! compose_extrinsic_offline {
 /// * 'call' - Call name as &str
 /// * 'args' - Optional sequence of arguments of the call. They are not checked against the metadata.
 /// As of now the user needs to check himself that the correct arguments are supplied.
+///
+/// Panics if the call cannot be composed or the nonce cannot be fetched. Use
+/// `try_compose_extrinsic!` to get an error instead.
 #[macro_export]
 #[cfg(feature = "std")]
 macro_rules! compose_extrinsic {
@@ -110,9 +199,9 @@ macro_rules! compose_extrinsic {
 
             debug!("Composing generic extrinsic for module {:?} and call {:?}", $module, $call);
             let call = $crate::compose_call!($api.metadata.clone(), $module, $call $(, ($args)) *);
-            if let Some(signer) = $api.signer.clone() {
+            if let Some(signer) = $api.signer.as_ref() {
                 $crate::compose_extrinsic_offline!(
-                    signer,
+                    *signer,
                     call.clone(),
                     $api.extrinsic_params($api.get_nonce().unwrap())
                 )
@@ -125,3 +214,50 @@ macro_rules! compose_extrinsic {
 		}
     };
 }
+
+/// Generates an Unchecked extrinsic like `compose_extrinsic!`, but returns `ApiResult<extrinsic>`
+/// instead of panicking.
+///
+/// Metadata errors (unknown pallet or call) and RPC errors while fetching the signer's nonce
+/// are propagated.
+/// # Arguments
+///
+/// * 'api' - This instance of API. If the *signer* field is not set, an unsigned extrinsic will be generated.
+/// * 'module' - Module name as &str for which the call is composed.
+/// * 'call' - Call name as &str
+/// * 'args' - Optional sequence of arguments of the call. They are not checked against the metadata.
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! try_compose_extrinsic {
+	($api: expr,
+	$module: expr,
+	$call: expr
+	$(, $args: expr) *) => {
+		{
+            #[allow(unused_imports)] // For when extrinsic does not use Compact
+            use $crate::codec::Compact;
+            use $crate::log::debug;
+            use $crate::primitives::UncheckedExtrinsicV4;
+
+            debug!("Composing generic extrinsic for module {:?} and call {:?}", $module, $call);
+            match $crate::try_compose_call!($api.metadata, $module, $call $(, ($args)) *) {
+                Ok(call) => {
+                    if let Some(signer) = $api.signer.as_ref() {
+                        $api.get_nonce()
+                            .and_then(|nonce| $api.try_extrinsic_params(nonce))
+                            .and_then(|params| {
+                                $crate::try_compose_extrinsic_offline!(*signer, call, params)
+                                    .map_err(Into::into)
+                            })
+                    } else {
+                        Ok(UncheckedExtrinsicV4 {
+                            signature: None,
+                            function: call,
+                        })
+                    }
+                }
+                Err(e) => Err(e.into()),
+            }
+		}
+    };
+}
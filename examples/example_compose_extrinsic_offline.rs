@@ -26,7 +26,7 @@ use sp_runtime::MultiAddress;
 
 use substrate_api_client::rpc::WsRpcClient;
 use substrate_api_client::{
-    compose_extrinsic_offline, Api, PlainTipExtrinsicParams, UncheckedExtrinsicV4, XtStatus,
+    compose_extrinsic_offline, Api, NodeTemplateConfig, UncheckedExtrinsicV4, XtStatus,
 };
 
 fn main() {
@@ -37,7 +37,7 @@ fn main() {
     let from = AccountKeyring::Alice.pair();
     let client = WsRpcClient::new(&url);
 
-    let api = Api::<_, _, PlainTipExtrinsicParams>::new(client)
+    let api = Api::<_, _, NodeTemplateConfig>::new(client)
         .map(|api| api.set_signer(from))
         .unwrap();
 
@@ -20,7 +20,7 @@ use std::sync::mpsc::channel;
 use clap::{load_yaml, App};
 use codec::Decode;
 use sp_keyring::AccountKeyring;
-use substrate_api_client::{rpc::WsRpcClient, AccountId, Api, PlainTipExtrinsicParams, XtStatus};
+use substrate_api_client::{rpc::WsRpcClient, AccountId, Api, NodeTemplateConfig, XtStatus};
 
 #[allow(unused)]
 #[derive(Decode)]
@@ -36,7 +36,7 @@ fn main() {
     // initialize api and set the signer (sender) that is used to sign the extrinsics
     let from = AccountKeyring::Alice.pair();
     let client = WsRpcClient::new(&url);
-    let api = Api::<_, _, PlainTipExtrinsicParams>::new(client)
+    let api = Api::<_, _, NodeTemplateConfig>::new(client)
         .map(|api| api.set_signer(from))
         .unwrap();
     println!("[+] Alice's Account Nonce is {}", api.get_nonce().unwrap());
@@ -54,13 +54,9 @@ fn main() {
     api.subscribe_events(events_in)
         .expect("cannot subscribe to events");
 
-    let xt = api.contract_instantiate_with_code(
-        1_000_000_000_000_000,
-        500_000,
-        wasm,
-        vec![1u8],
-        vec![1u8],
-    );
+    let xt = api
+        .contract_instantiate_with_code(1_000_000_000_000_000, 500_000, wasm, vec![1u8], vec![1u8])
+        .unwrap();
 
     println!(
         "[+] Creating a contract instance with extrinsic:\n\n{:?}\n",
@@ -82,7 +78,9 @@ fn main() {
         args.contract
     );
 
-    let xt = api.contract_call(args.contract.into(), 500_000, 500_000, vec![0u8]);
+    let xt = api
+        .contract_call(args.contract.into(), 500_000, 500_000, vec![0u8])
+        .unwrap();
 
     println!(
         "[+] Calling the contract with extrinsic Extrinsic:\n{:?}\n\n",
@@ -25,7 +25,7 @@ use sp_runtime::generic::Era;
 use sp_runtime::MultiAddress;
 
 use substrate_api_client::rpc::WsRpcClient;
-use substrate_api_client::{compose_extrinsic_offline, Api, AssetTipExtrinsicParams, UncheckedExtrinsicV4, XtStatus, AssetTip};
+use substrate_api_client::{compose_extrinsic_offline, Api, Db3Config, UncheckedExtrinsicV4, XtStatus, AssetTip};
 
 fn main() {
     env_logger::init();
@@ -35,7 +35,7 @@ fn main() {
     let from = AccountKeyring::Alice.pair();
     let client = WsRpcClient::new(&url);
 
-    let api = Api::<_, _, AssetTipExtrinsicParams>::new(client)
+    let api = Api::<_, _, Db3Config>::new(client)
         .map(|api| api.set_signer(from))
         .unwrap();
 
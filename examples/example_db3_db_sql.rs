@@ -27,7 +27,7 @@ use node_runtime::{Header};
 use sp_keyring::AccountKeyring;
 use sp_runtime::generic::Era;
 use substrate_api_client::rpc::WsRpcClient;
-use substrate_api_client::{compose_extrinsic_offline, Api, AssetTipExtrinsicParams, UncheckedExtrinsicV4, XtStatus, AssetTip, MultiAddress};
+use substrate_api_client::{compose_extrinsic_offline, Api, Db3Config, UncheckedExtrinsicV4, XtStatus, AssetTip, MultiAddress};
 use serde::{Deserialize};
 
 fn run_sql_by_owner_and_add_delegate(owner: &AccountKeyring, delegate_address: &db3_runtime::Address) {
@@ -36,7 +36,7 @@ fn run_sql_by_owner_and_add_delegate(owner: &AccountKeyring, delegate_address: &
     let client = WsRpcClient::new(&url);
 
     // initialize api and set the signer (sender) that is used to sign the extrinsics
-    let api = Api::<_, _, AssetTipExtrinsicParams>::new(client)
+    let api = Api::<_, _, Db3Config>::new(client)
         .map(|api| api.set_signer(owner.pair()))
         .unwrap();
 
@@ -193,7 +193,7 @@ fn run_sql_by_delegate(delegate : &AccountKeyring, owner_address: &db3_runtime::
 
     // initialize api and set the signer (sender) that is used to sign the extrinsics
 
-    let api = Api::<_, _, AssetTipExtrinsicParams>::new(client)
+    let api = Api::<_, _, Db3Config>::new(client)
         .map(|api| api.set_signer(delegate.pair()))
         .unwrap();
 
@@ -332,7 +332,7 @@ pub fn get_node_url_from_cli() -> String {
     url
 }
 
-fn generate_tx_param<P>(api: &Api<P, WsRpcClient, AssetTipExtrinsicParams>) -> AssetTipExtrinsicParamsBuilder {
+fn generate_tx_param<P>(api: &Api<P, WsRpcClient, Db3Config>) -> AssetTipExtrinsicParamsBuilder {
     let head = api.get_finalized_head().unwrap().unwrap();
     let h: Header = api.get_header(Some(head)).unwrap().unwrap();
     let period = 5;
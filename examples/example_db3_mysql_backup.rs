@@ -27,7 +27,7 @@ use node_runtime::{Header};
 use sp_keyring::AccountKeyring;
 use sp_runtime::generic::Era;
 use substrate_api_client::rpc::WsRpcClient;
-use substrate_api_client::{compose_extrinsic_offline, Api, AssetTipExtrinsicParams, UncheckedExtrinsicV4, XtStatus, AssetTip, MultiAddress};
+use substrate_api_client::{compose_extrinsic_offline, Api, Db3Config, UncheckedExtrinsicV4, XtStatus, AssetTip, MultiAddress};
 use serde::{Deserialize};
 #[derive(Clone, Debug, PartialEq, Deserialize)]
 struct ResponseBody<'a> {
@@ -86,7 +86,7 @@ fn main() -> Result<(), Error> {
     let delegate = AccountKeyring::Bob;
     let owner = AccountKeyring::Alice;
 
-    let mut api = Api::<_, _, AssetTipExtrinsicParams>::new(WsRpcClient::new(&url))
+    let mut api = Api::<_, _, Db3Config>::new(WsRpcClient::new(&url))
         .map(|api| api.set_signer(delegate.pair()))
         .unwrap();
 
@@ -161,7 +161,7 @@ fn create_ns_with_delegate(url: &str, owner: &AccountKeyring,
     let client = WsRpcClient::new(url);
 
     // initialize api and set the signer (sender) that is used to sign the extrinsics
-    let api = Api::<_, _, AssetTipExtrinsicParams>::new(client)
+    let api = Api::<_, _, Db3Config>::new(client)
         .map(|api| api.set_signer(owner.pair()))
         .unwrap();
 
@@ -201,7 +201,7 @@ fn create_ns_with_delegate(url: &str, owner: &AccountKeyring,
 }
 
 
-fn generate_tx_param<P>(api: &Api<P, WsRpcClient, AssetTipExtrinsicParams>) -> AssetTipExtrinsicParamsBuilder {
+fn generate_tx_param<P>(api: &Api<P, WsRpcClient, Db3Config>) -> AssetTipExtrinsicParamsBuilder {
     let head = api.get_finalized_head().unwrap().unwrap();
     let h: Header = api.get_header(Some(head)).unwrap().unwrap();
     let period = 5;
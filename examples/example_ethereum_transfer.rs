@@ -0,0 +1,69 @@
+/*
+    Copyright 2019 Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+///! Transfer on an Ethereum compatible chain (e.g. a Moonbeam dev node) with 20 byte accounts
+use clap::{load_yaml, App};
+use sp_core::{ecdsa, Pair};
+
+use substrate_api_client::rpc::WsRpcClient;
+use substrate_api_client::{AccountId20, Api, EthereumConfig, EthereumPair, Signer, XtStatus};
+
+// Private key of the development account `Alith`.
+const ALITH_SEED: &str = "5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133";
+// Development account `Baltathar`.
+const BALTATHAR: &str = "0x3Cd0A705a2DC65e5b1E1205896BaA2be8A07c6e0";
+
+fn main() {
+    env_logger::init();
+    let url = get_node_url_from_cli();
+
+    let seed = hex::decode(ALITH_SEED).unwrap();
+    let from = EthereumPair::from(ecdsa::Pair::from_seed_slice(&seed).unwrap());
+    let client = WsRpcClient::new(&url);
+    let api = Api::<_, _, EthereumConfig>::new(client)
+        .map(|api| api.set_signer(from.clone()))
+        .unwrap();
+
+    let to: AccountId20 = BALTATHAR.parse().unwrap();
+
+    // generate extrinsic
+    let xt = api.balance_transfer(to, 1000).unwrap();
+
+    println!(
+        "Sending an extrinsic from Alith ({}),\n\nto Baltathar ({})\n",
+        from.account_id(),
+        to
+    );
+
+    // send and watch extrinsic until in block
+    let tx_hash = api
+        .send_extrinsic(xt.hex_encode(), XtStatus::InBlock)
+        .unwrap();
+    println!("[+] Transaction got included. Hash: {:?}\n", tx_hash);
+
+    let baltathar = api.get_account_data(&to).unwrap().unwrap();
+    println!("[+] Baltathar's Free Balance is now {}\n", baltathar.free);
+}
+
+pub fn get_node_url_from_cli() -> String {
+    let yml = load_yaml!("cli.yml");
+    let matches = App::from_yaml(yml).get_matches();
+
+    let node_ip = matches.value_of("node-server").unwrap_or("ws://127.0.0.1");
+    let node_port = matches.value_of("node-port").unwrap_or("9944");
+    let url = format!("{}:{}", node_ip, node_port);
+    println!("Interacting with node on {}\n", url);
+    url
+}
@@ -21,7 +21,7 @@ use sp_keyring::AccountKeyring;
 
 use substrate_api_client::rpc::WsRpcClient;
 use substrate_api_client::{
-    compose_extrinsic, Api, GenericAddress, PlainTipExtrinsicParams, UncheckedExtrinsicV4, XtStatus,
+    compose_extrinsic, Api, GenericAddress, NodeTemplateConfig, UncheckedExtrinsicV4, XtStatus,
 };
 
 fn main() {
@@ -31,7 +31,7 @@ fn main() {
     // initialize api and set the signer (sender) that is used to sign the extrinsics
     let from = AccountKeyring::Alice.pair();
     let client = WsRpcClient::new(&url);
-    let api = Api::<_, _, PlainTipExtrinsicParams>::new(client)
+    let api = Api::<_, _, NodeTemplateConfig>::new(client)
         .map(|api| api.set_signer(from))
         .unwrap();
 
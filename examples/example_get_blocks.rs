@@ -26,7 +26,7 @@ use sp_core::sr25519;
 use sp_runtime::generic::SignedBlock as SignedBlockG;
 use std::sync::mpsc::channel;
 use substrate_api_client::rpc::WsRpcClient;
-use substrate_api_client::{Api, PlainTipExtrinsicParams};
+use substrate_api_client::{Api, NodeTemplateConfig};
 
 type SignedBlock = SignedBlockG<Block>;
 
@@ -35,7 +35,7 @@ fn main() {
     let url = get_node_url_from_cli();
 
     let client = WsRpcClient::new(&url);
-    let api = Api::<sr25519::Pair, _, PlainTipExtrinsicParams>::new(client).unwrap();
+    let api = Api::<sr25519::Pair, _, NodeTemplateConfig>::new(client).unwrap();
 
     let head = api.get_finalized_head().unwrap().unwrap();
 
@@ -54,7 +54,7 @@ fn main() {
 
     println!(
         "Latest Header: \n {:?} \n",
-        api.get_header::<Header>(None).unwrap()
+        api.get_header(None).unwrap()
     );
 
     println!(
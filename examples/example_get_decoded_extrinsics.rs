@@ -0,0 +1,60 @@
+/*
+    Copyright 2019 Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+
+        http://www.apache.org/licenses/LICENSE-2.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+///! Example that shows how to decode the extrinsics of a block into pallet, call and arguments
+///! without depending on the node's runtime crate.
+
+#[macro_use]
+extern crate clap;
+
+use clap::App;
+
+use sp_core::sr25519;
+use substrate_api_client::rpc::WsRpcClient;
+use substrate_api_client::{Api, NodeTemplateConfig};
+
+fn main() {
+    env_logger::init();
+    let url = get_node_url_from_cli();
+
+    let client = WsRpcClient::new(&url);
+    let api = Api::<sr25519::Pair, _, NodeTemplateConfig>::new(client).unwrap();
+
+    let head = api.get_finalized_head().unwrap().unwrap();
+    println!("Finalized Head:\n {} \n", head);
+
+    let extrinsics = api.get_decoded_extrinsics(Some(head)).unwrap().unwrap();
+    for xt in extrinsics {
+        let signer = xt.signature.as_ref().map(|s| &s.address);
+        println!("{}::{} signed by {:?}", xt.pallet, xt.call, signer);
+        println!("    arguments: {:?}", xt.args);
+        if let Some(signature) = xt.signature {
+            for (extension, value) in signature.extensions {
+                println!("    {}: {:?}", extension, value);
+            }
+        }
+    }
+}
+
+pub fn get_node_url_from_cli() -> String {
+    let yml = load_yaml!("cli.yml");
+    let matches = App::from_yaml(yml).get_matches();
+
+    let node_ip = matches.value_of("node-server").unwrap_or("ws://127.0.0.1");
+    let node_port = matches.value_of("node-port").unwrap_or("9944");
+    let url = format!("{}:{}", node_ip, node_port);
+    println!("Interacting with node on {}\n", url);
+    url
+}
@@ -25,14 +25,14 @@ use sp_core::sr25519;
 
 use std::convert::TryFrom;
 use substrate_api_client::rpc::WsRpcClient;
-use substrate_api_client::{Api, Metadata, PlainTipExtrinsicParams};
+use substrate_api_client::{Api, Metadata, NodeTemplateConfig};
 
 fn main() {
     env_logger::init();
     let url = get_node_url_from_cli();
 
     let client = WsRpcClient::new(&url);
-    let api = Api::<sr25519::Pair, _, PlainTipExtrinsicParams>::new(client).unwrap();
+    let api = Api::<sr25519::Pair, _, NodeTemplateConfig>::new(client).unwrap();
 
     let meta = Metadata::try_from(api.get_metadata().unwrap()).unwrap();
 
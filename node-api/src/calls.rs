@@ -0,0 +1,528 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Checks composed calls against the call definitions in the metadata, and describes the
+//! calls with their arguments.
+//!
+//! This file is **not** from subxt.
+
+use crate::{
+    error::Error,
+    metadata::{Metadata, MetadataError},
+    value::ValueDecodingError,
+};
+use codec::{Decode, Encode};
+use scale_info::{
+    form::PortableForm, prelude::format, Field, PortableRegistry, TypeDef, TypeDefPrimitive,
+    Variant,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Nesting of generic parameters up to which `type_path` resolves types.
+const MAX_TYPE_PATH_DEPTH: usize = 8;
+
+/// A call with a static Rust type holding its arguments, identified by its pallet and call
+/// names, e.g. as generated by `ac-codegen`.
+pub trait StaticCall: Encode {
+    /// Name of the pallet of the call.
+    const PALLET: &'static str;
+    /// Name of the call.
+    const CALL: &'static str;
+
+    /// The SCALE encoded arguments, one per call field.
+    fn encoded_args(&self) -> Vec<Vec<u8>>;
+}
+
+/// A call of a pallet with its arguments, see `Metadata::call_by_index`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct CallMetadata {
+    pub pallet: String,
+    pub call: String,
+    pub args: Vec<CallArgMetadata>,
+    pub variant: Variant<PortableForm>,
+}
+
+impl CallMetadata {
+    pub(crate) fn new(
+        pallet: &str,
+        variant: &Variant<PortableForm>,
+        registry: &PortableRegistry,
+    ) -> Self {
+        let args = variant
+            .fields()
+            .iter()
+            .map(|field| CallArgMetadata {
+                name: field.name().cloned(),
+                ty: field.ty().id(),
+                type_name: field.type_name().cloned(),
+                type_path: type_path(registry, field.ty().id()),
+            })
+            .collect();
+        Self {
+            pallet: pallet.to_string(),
+            call: variant.name().clone(),
+            args,
+            variant: variant.clone(),
+        }
+    }
+
+    /// Get the name of the pallet of the call.
+    pub fn pallet(&self) -> &str {
+        &self.pallet
+    }
+
+    /// Get the name of the call.
+    pub fn call(&self) -> &str {
+        &self.call
+    }
+
+    /// Get the index of the call within its pallet.
+    pub fn index(&self) -> u8 {
+        self.variant.index()
+    }
+
+    /// Get the arguments of the call, in the order they are encoded.
+    pub fn args(&self) -> &[CallArgMetadata] {
+        &self.args
+    }
+
+    /// Get the documentation of the call.
+    pub fn docs(&self) -> &[String] {
+        self.variant.docs()
+    }
+
+    /// Get the type def variant for the call.
+    pub fn variant(&self) -> &Variant<PortableForm> {
+        &self.variant
+    }
+}
+
+/// An argument of a call.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct CallArgMetadata {
+    /// Name of the argument, `None` for an unnamed field.
+    pub name: Option<String>,
+    /// Id of the type in the type registry.
+    pub ty: u32,
+    /// Name of the type as written in the pallet source, e.g. `T::Balance`.
+    pub type_name: Option<String>,
+    /// Resolved type, e.g. `sp_runtime::multiaddress::MultiAddress<sp_core::crypto::AccountId32, ()>`.
+    pub type_path: String,
+}
+
+/// Readable path of a type of the registry with its generic parameters, e.g. `Vec<u8>` or
+/// `Option<sp_core::crypto::AccountId32>`.
+pub fn type_path(registry: &PortableRegistry, id: u32) -> String {
+    type_path_at_depth(registry, id, 0)
+}
+
+fn type_path_at_depth(registry: &PortableRegistry, id: u32, depth: usize) -> String {
+    let ty = match registry.resolve(id) {
+        Some(ty) => ty,
+        None => return format!("<unknown type #{}>", id),
+    };
+    if depth > MAX_TYPE_PATH_DEPTH {
+        return "..".into();
+    }
+    let inner = |id: u32| type_path_at_depth(registry, id, depth + 1);
+
+    if !ty.path().is_empty() {
+        let path = ty.path().segments().join("::");
+        let params: Vec<_> = ty
+            .type_params()
+            .iter()
+            .filter_map(|param| param.ty())
+            .map(|param| inner(param.id()))
+            .collect();
+        if params.is_empty() {
+            return path;
+        }
+        return format!("{}<{}>", path, params.join(", "));
+    }
+    match ty.type_def() {
+        TypeDef::Sequence(sequence) => format!("Vec<{}>", inner(sequence.type_param().id())),
+        TypeDef::Array(array) => format!("[{}; {}]", inner(array.type_param().id()), array.len()),
+        TypeDef::Tuple(tuple) => {
+            let fields: Vec<_> = tuple
+                .fields()
+                .iter()
+                .map(|field| inner(field.id()))
+                .collect();
+            format!("({})", fields.join(", "))
+        }
+        TypeDef::Primitive(primitive) => primitive_name(primitive).into(),
+        TypeDef::Compact(compact) => format!("Compact<{}>", inner(compact.type_param().id())),
+        TypeDef::BitSequence(bits) => format!(
+            "BitVec<{}, {}>",
+            inner(bits.bit_store_type().id()),
+            inner(bits.bit_order_type().id())
+        ),
+        TypeDef::Composite(_) | TypeDef::Variant(_) => format!("<anonymous type #{}>", id),
+    }
+}
+
+fn primitive_name(primitive: &TypeDefPrimitive) -> &'static str {
+    match primitive {
+        TypeDefPrimitive::Bool => "bool",
+        TypeDefPrimitive::Char => "char",
+        TypeDefPrimitive::Str => "String",
+        TypeDefPrimitive::U8 => "u8",
+        TypeDefPrimitive::U16 => "u16",
+        TypeDefPrimitive::U32 => "u32",
+        TypeDefPrimitive::U64 => "u64",
+        TypeDefPrimitive::U128 => "u128",
+        TypeDefPrimitive::U256 => "U256",
+        TypeDefPrimitive::I8 => "i8",
+        TypeDefPrimitive::I16 => "i16",
+        TypeDefPrimitive::I32 => "i32",
+        TypeDefPrimitive::I64 => "i64",
+        TypeDefPrimitive::I128 => "i128",
+        TypeDefPrimitive::I256 => "I256",
+    }
+}
+
+impl Metadata {
+    /// Compose the extrinsic's call field from a static call, like `compose_call_checked!`.
+    ///
+    /// The call index is resolved by name in this metadata, and the arguments are checked
+    /// against the call's field types, so a call type generated from other metadata is only
+    /// accepted if it is still compatible.
+    pub fn static_call<C: StaticCall>(&self, call: C) -> Result<([u8; 2], C), MetadataError> {
+        let call_index = self.checked_call_index(C::PALLET, C::CALL, &call.encoded_args())?;
+        Ok((call_index, call))
+    }
+
+    /// Returns the call index of `pallet::call` after checking that `args`, the SCALE encoded
+    /// call arguments, match the fields of the call in the type registry.
+    ///
+    /// Each argument must decode to exactly its field type, which catches wrong argument
+    /// counts, compact vs non-compact mixups and invalid enum variants.
+    pub fn checked_call_index(
+        &self,
+        pallet: &'static str,
+        call: &'static str,
+        args: &[Vec<u8>],
+    ) -> Result<[u8; 2], MetadataError> {
+        let pallet_index = self.pallet(pallet)?.index;
+        let variant = self.call_by_name(pallet, call)?.variant();
+        let call_name = format!("{}::{}", pallet, call);
+
+        let fields = variant.fields();
+        if fields.len() != args.len() {
+            return Err(MetadataError::CallArgumentCount(
+                call_name,
+                fields.len(),
+                args.len(),
+            ));
+        }
+
+        for (position, (field, arg)) in fields.iter().zip(args).enumerate() {
+            self.check_call_argument(field, arg).map_err(|reason| {
+                let field_name = field
+                    .name()
+                    .cloned()
+                    .unwrap_or_else(|| format!("#{}", position));
+                MetadataError::CallArgumentMismatch(call_name.clone(), field_name, reason)
+            })?;
+        }
+
+        Ok([pallet_index, variant.index()])
+    }
+
+    /// Returns a description of the mismatch if `arg` does not encode a value of the field type.
+    fn check_call_argument(&self, field: &Field<PortableForm>, arg: &[u8]) -> Result<(), String> {
+        let type_id = field.ty().id();
+        let type_name = field
+            .type_name()
+            .cloned()
+            .unwrap_or_else(|| format!("type #{}", type_id));
+
+        let mut input = arg;
+        match self.decode_value(type_id, &mut input) {
+            Ok(_) if input.is_empty() => Ok(()),
+            Ok(_) => Err(format!(
+                "{} bytes left after decoding `{}` from {} supplied bytes{}",
+                input.len(),
+                type_name,
+                arg.len(),
+                self.compact_hint(type_id)
+            )),
+            Err(Error::ValueDecoding(ValueDecodingError::VariantNotFound(enum_ty, index))) => {
+                Err(format!(
+                    "variant index {} is not defined for `{}`, valid indices are {:?}",
+                    index,
+                    type_name,
+                    self.variant_indices(enum_ty)
+                ))
+            }
+            Err(e) => Err(format!(
+                "cannot decode `{}` from {} supplied bytes: {:?}{}",
+                type_name,
+                arg.len(),
+                e,
+                self.compact_hint(type_id)
+            )),
+        }
+    }
+
+    /// Hint on compact encoding if the type is an integer or a compact integer.
+    fn compact_hint(&self, type_id: u32) -> &'static str {
+        match self.resolve_type(type_id).map(|ty| ty.type_def()) {
+            Some(TypeDef::Compact(_)) => {
+                " (the field is compact encoded, wrap the argument in `Compact`)"
+            }
+            Some(TypeDef::Primitive(
+                TypeDefPrimitive::U8
+                | TypeDefPrimitive::U16
+                | TypeDefPrimitive::U32
+                | TypeDefPrimitive::U64
+                | TypeDefPrimitive::U128,
+            )) => " (the field is not compact encoded, do not wrap the argument in `Compact`)",
+            _ => "",
+        }
+    }
+
+    fn variant_indices(&self, type_id: u32) -> Vec<u8> {
+        match self.resolve_type(type_id).map(|ty| ty.type_def()) {
+            Some(TypeDef::Variant(variant)) => {
+                variant.variants().iter().map(|v| v.index()).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use frame_metadata::{PalletCallMetadata, PalletMetadata};
+    use scale_info::{meta_type, TypeInfo};
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    struct AccountId([u8; 32]);
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum Call {
+        transfer {
+            dest: Option<AccountId>,
+            #[codec(compact)]
+            value: u128,
+        },
+        remark(Vec<u8>, (bool, [u16; 2])),
+    }
+
+    fn metadata() -> Metadata {
+        test_utils::metadata(vec![PalletMetadata {
+            calls: Some(PalletCallMetadata {
+                ty: meta_type::<Call>(),
+            }),
+            ..test_utils::pallet("Balances", 5)
+        }])
+    }
+
+    #[test]
+    fn describes_calls_by_index() {
+        let metadata = metadata();
+        assert_eq!(metadata.pallet_by_index(5).unwrap().name, "Balances");
+        assert_eq!(
+            metadata.pallet_by_index(6).unwrap_err(),
+            MetadataError::PalletIndexNotFound(6)
+        );
+
+        let transfer = metadata.call_by_index(5, 0).unwrap();
+        assert_eq!(
+            (transfer.pallet(), transfer.call(), transfer.index()),
+            ("Balances", "transfer", 0)
+        );
+        let args: Vec<_> = transfer
+            .args()
+            .iter()
+            .map(|arg| (arg.name.clone(), arg.type_path.clone()))
+            .collect();
+        assert_eq!(
+            args,
+            [
+                (
+                    Some("dest".into()),
+                    format!("Option<{}::AccountId>", module_path!())
+                ),
+                (Some("value".into()), "Compact<u128>".into()),
+            ]
+        );
+
+        let remark = metadata.call_by_index(5, 1).unwrap();
+        assert_eq!(remark.args()[0].name, None);
+        assert_eq!(remark.args()[0].type_path, "Vec<u8>");
+        assert_eq!(remark.args()[1].type_path, "(bool, [u16; 2])");
+
+        assert_eq!(
+            metadata.call_by_index(5, 2).unwrap_err(),
+            MetadataError::CallIndexNotFound(5, 2)
+        );
+        assert_eq!(metadata.calls(5).len(), 2);
+    }
+
+    fn transfer_args(dest: Vec<u8>, value: Vec<u8>) -> Vec<Vec<u8>> {
+        vec![dest, value]
+    }
+
+    #[test]
+    fn checked_call_index_accepts_matching_arguments() {
+        let dest = Some(AccountId([1; 32])).encode();
+        let value = codec::Compact(10u128).encode();
+        assert_eq!(
+            metadata().checked_call_index("Balances", "transfer", &transfer_args(dest, value)),
+            Ok([5, 0])
+        );
+        assert_eq!(
+            metadata().checked_call_index(
+                "Balances",
+                "remark",
+                &[vec![1u8].encode(), (true, [1u16, 2]).encode()]
+            ),
+            Ok([5, 1])
+        );
+    }
+
+    #[test]
+    fn checked_call_index_rejects_wrong_argument_count() {
+        let dest = Some(AccountId([1; 32])).encode();
+        assert_eq!(
+            metadata().checked_call_index("Balances", "transfer", &[dest]),
+            Err(MetadataError::CallArgumentCount(
+                "Balances::transfer".into(),
+                2,
+                1
+            ))
+        );
+        assert_eq!(
+            metadata().checked_call_index("Balances", "mint", &[]),
+            Err(MetadataError::CallNotFound("mint"))
+        );
+    }
+
+    #[test]
+    fn checked_call_index_rejects_compact_mismatch() {
+        let dest = Some(AccountId([1; 32])).encode();
+        let value = 10u128.encode();
+        match metadata().checked_call_index("Balances", "transfer", &transfer_args(dest, value)) {
+            Err(MetadataError::CallArgumentMismatch(call, field, reason)) => {
+                assert_eq!(
+                    (call.as_str(), field.as_str()),
+                    ("Balances::transfer", "value")
+                );
+                assert!(
+                    reason.contains("wrap the argument in `Compact`"),
+                    "{}",
+                    reason
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // A compact argument for a plain integer field of the tuple.
+        let flag_and_values = (true, codec::Compact(1u16), 2u16).encode();
+        match metadata().checked_call_index(
+            "Balances",
+            "remark",
+            &[vec![1u8].encode(), flag_and_values],
+        ) {
+            Err(MetadataError::CallArgumentMismatch(call, field, _)) => {
+                assert_eq!((call.as_str(), field.as_str()), ("Balances::remark", "#1"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn checked_call_index_rejects_variant_out_of_range() {
+        let value = codec::Compact(10u128).encode();
+        match metadata().checked_call_index("Balances", "transfer", &transfer_args(vec![2], value))
+        {
+            Err(MetadataError::CallArgumentMismatch(_, field, reason)) => {
+                assert_eq!(field, "dest");
+                assert!(
+                    reason.contains("variant index 2 is not defined") && reason.ends_with("[0, 1]"),
+                    "{}",
+                    reason
+                );
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    /// `Call::transfer`, like the call structs generated by `ac-codegen`.
+    #[derive(Encode)]
+    struct Transfer {
+        dest: Option<AccountId>,
+        value: codec::Compact<u128>,
+    }
+
+    impl StaticCall for Transfer {
+        const PALLET: &'static str = "Balances";
+        const CALL: &'static str = "transfer";
+
+        fn encoded_args(&self) -> Vec<Vec<u8>> {
+            vec![self.dest.encode(), self.value.encode()]
+        }
+    }
+
+    /// `Call::transfer` of metadata where `value` is not compact.
+    #[derive(Encode)]
+    struct OutdatedTransfer {
+        dest: Option<AccountId>,
+        value: u128,
+    }
+
+    impl StaticCall for OutdatedTransfer {
+        const PALLET: &'static str = "Balances";
+        const CALL: &'static str = "transfer";
+
+        fn encoded_args(&self) -> Vec<Vec<u8>> {
+            vec![self.dest.encode(), self.value.encode()]
+        }
+    }
+
+    #[test]
+    fn static_call_resolves_the_index_of_compatible_calls() {
+        let transfer = Transfer {
+            dest: None,
+            value: codec::Compact(10),
+        };
+        let (index, call) = metadata().static_call(transfer).unwrap();
+        assert_eq!(index, [5, 0]);
+        assert_eq!(call.value, codec::Compact(10));
+
+        let outdated = OutdatedTransfer {
+            dest: None,
+            value: 10,
+        };
+        match metadata().static_call(outdated) {
+            Err(MetadataError::CallArgumentMismatch(call, field, _)) => {
+                assert_eq!(
+                    (call.as_str(), field.as_str()),
+                    ("Balances::transfer", "value")
+                );
+            }
+            other => panic!("unexpected result: {:?}", other.map(|(index, _)| index)),
+        }
+    }
+}
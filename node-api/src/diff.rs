@@ -0,0 +1,650 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Compare the metadata of two runtime versions, e.g. before a runtime upgrade.
+//!
+//! Types are compared by their SCALE shape, so a renamed type or field does not count as a
+//! change, while a changed variant index or field type does.
+//!
+//! This file is **not** from subxt.
+
+use crate::metadata::Metadata;
+use frame_metadata::{
+    PalletMetadata, StorageEntryMetadata, StorageEntryModifier, StorageEntryType, StorageHasher,
+};
+use scale_info::{form::PortableForm, Field, PortableRegistry, TypeDef, Variant};
+use sp_std::collections::{btree_map::BTreeMap, btree_set::BTreeSet};
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec, vec::Vec};
+
+/// Changes from one metadata to another, see `Metadata::diff`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetadataDiff {
+    /// Pallets which were added, removed or changed, by name.
+    pub pallets: Vec<PalletDiff>,
+}
+
+impl MetadataDiff {
+    pub fn is_empty(&self) -> bool {
+        self.pallets.is_empty()
+    }
+
+    /// Whether clients built against the old metadata may fail with the new one.
+    pub fn is_breaking(&self) -> bool {
+        self.pallets.iter().any(PalletDiff::is_breaking)
+    }
+
+    /// Returns the diff of the pallet called `name`.
+    pub fn pallet(&self, name: &str) -> Option<&PalletDiff> {
+        self.pallets.iter().find(|pallet| pallet.name == name)
+    }
+}
+
+/// Changes of a pallet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PalletDiff {
+    pub name: String,
+    /// Change of the pallet itself. `Modified` with no modifications if only its items changed.
+    pub change: Change,
+    /// Changed items. Empty for added and removed pallets.
+    pub items: Vec<ItemDiff>,
+}
+
+impl PalletDiff {
+    pub fn is_breaking(&self) -> bool {
+        self.change.is_breaking() || self.items.iter().any(ItemDiff::is_breaking)
+    }
+
+    /// Returns the diff of the item of `kind` called `name`.
+    pub fn item(&self, kind: ItemKind, name: &str) -> Option<&ItemDiff> {
+        self.items
+            .iter()
+            .find(|item| item.kind == kind && item.name == name)
+    }
+}
+
+/// Kind of a pallet item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ItemKind {
+    Call,
+    Event,
+    Error,
+    Storage,
+    Constant,
+}
+
+/// Change of a pallet item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemDiff {
+    pub kind: ItemKind,
+    pub name: String,
+    pub change: Change,
+}
+
+impl ItemDiff {
+    pub fn is_breaking(&self) -> bool {
+        self.change.is_breaking()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Modified(Vec<Modification>),
+}
+
+impl Change {
+    /// Removals and modifications other than constant values are breaking, additions are not.
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            Change::Added => false,
+            Change::Removed => true,
+            Change::Modified(modifications) => modifications.iter().any(Modification::is_breaking),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Modification {
+    /// Pallet, call, event or error index.
+    Index { old: u8, new: u8 },
+    /// Shape of the call arguments, event or error fields, storage key or value or constant
+    /// type.
+    Type,
+    /// Storage hashers. A plain storage value has none.
+    Hashers {
+        old: Vec<StorageHasher>,
+        new: Vec<StorageHasher>,
+    },
+    /// Storage modifier, which decides whether a missing value decodes as the default.
+    Modifier {
+        old: StorageEntryModifier,
+        new: StorageEntryModifier,
+    },
+    /// Constant value.
+    Value,
+    /// Default value of a storage entry, which a missing value decodes as.
+    Default,
+}
+
+impl Modification {
+    pub fn is_breaking(&self) -> bool {
+        !matches!(self, Modification::Value | Modification::Default)
+    }
+}
+
+impl Metadata {
+    /// Changes from this metadata to `new`, e.g. the metadata of the runtime after an upgrade.
+    pub fn diff(&self, new: &Metadata) -> MetadataDiff {
+        let old_pallets = pallets_by_name(&self.metadata.pallets);
+        let new_pallets = pallets_by_name(&new.metadata.pallets);
+        let types = TypeComparer {
+            old: &self.metadata.types,
+            new: &new.metadata.types,
+        };
+
+        let mut pallets = Vec::new();
+        for (name, old) in &old_pallets {
+            match new_pallets.get(name) {
+                None => pallets.push(PalletDiff {
+                    name: name.clone(),
+                    change: Change::Removed,
+                    items: Vec::new(),
+                }),
+                Some(new) => {
+                    let mut modifications = Vec::new();
+                    if old.index != new.index {
+                        modifications.push(Modification::Index {
+                            old: old.index,
+                            new: new.index,
+                        });
+                    }
+                    let items = types.pallet_items(old, new);
+                    if !modifications.is_empty() || !items.is_empty() {
+                        pallets.push(PalletDiff {
+                            name: name.clone(),
+                            change: Change::Modified(modifications),
+                            items,
+                        });
+                    }
+                }
+            }
+        }
+        for name in new_pallets.keys() {
+            if !old_pallets.contains_key(name) {
+                pallets.push(PalletDiff {
+                    name: name.clone(),
+                    change: Change::Added,
+                    items: Vec::new(),
+                });
+            }
+        }
+        pallets.sort_by(|a, b| a.name.cmp(&b.name));
+        MetadataDiff { pallets }
+    }
+}
+
+fn pallets_by_name(
+    pallets: &[PalletMetadata<PortableForm>],
+) -> BTreeMap<String, &PalletMetadata<PortableForm>> {
+    pallets
+        .iter()
+        .map(|pallet| (pallet.name.clone(), pallet))
+        .collect()
+}
+
+/// Compares items and types of the old and new metadata.
+struct TypeComparer<'a> {
+    old: &'a PortableRegistry,
+    new: &'a PortableRegistry,
+}
+
+impl<'a> TypeComparer<'a> {
+    fn pallet_items(
+        &self,
+        old: &PalletMetadata<PortableForm>,
+        new: &PalletMetadata<PortableForm>,
+    ) -> Vec<ItemDiff> {
+        let mut items = Vec::new();
+        items.extend(self.variant_items(
+            ItemKind::Call,
+            old.calls.as_ref().map(|calls| calls.ty.id()),
+            new.calls.as_ref().map(|calls| calls.ty.id()),
+        ));
+        items.extend(self.variant_items(
+            ItemKind::Event,
+            old.event.as_ref().map(|event| event.ty.id()),
+            new.event.as_ref().map(|event| event.ty.id()),
+        ));
+        items.extend(self.variant_items(
+            ItemKind::Error,
+            old.error.as_ref().map(|error| error.ty.id()),
+            new.error.as_ref().map(|error| error.ty.id()),
+        ));
+
+        let old_storage = storage_by_name(old);
+        let new_storage = storage_by_name(new);
+        items.extend(diff_by_name(
+            ItemKind::Storage,
+            &old_storage,
+            &new_storage,
+            |old, new| self.storage_modifications(old, new),
+        ));
+
+        let old_constants = old
+            .constants
+            .iter()
+            .map(|constant| (constant.name.clone(), constant))
+            .collect();
+        let new_constants = new
+            .constants
+            .iter()
+            .map(|constant| (constant.name.clone(), constant))
+            .collect();
+        items.extend(diff_by_name(
+            ItemKind::Constant,
+            &old_constants,
+            &new_constants,
+            |old, new| {
+                if !self.same_type(old.ty.id(), new.ty.id()) {
+                    vec![Modification::Type]
+                } else if old.value != new.value {
+                    vec![Modification::Value]
+                } else {
+                    Vec::new()
+                }
+            },
+        ));
+        items
+    }
+
+    /// Diff of the variants of the call, event or error enums of a pallet.
+    fn variant_items(&self, kind: ItemKind, old: Option<u32>, new: Option<u32>) -> Vec<ItemDiff> {
+        let old_variants = variants_by_name(self.old, old);
+        let new_variants = variants_by_name(self.new, new);
+        diff_by_name(kind, &old_variants, &new_variants, |old, new| {
+            let mut modifications = Vec::new();
+            if old.index() != new.index() {
+                modifications.push(Modification::Index {
+                    old: old.index(),
+                    new: new.index(),
+                });
+            }
+            if !self.same_fields(old.fields(), new.fields(), &mut BTreeSet::new()) {
+                modifications.push(Modification::Type);
+            }
+            modifications
+        })
+    }
+
+    fn storage_modifications(
+        &self,
+        old: &StorageEntryMetadata<PortableForm>,
+        new: &StorageEntryMetadata<PortableForm>,
+    ) -> Vec<Modification> {
+        let mut modifications = Vec::new();
+        if old.modifier != new.modifier {
+            modifications.push(Modification::Modifier {
+                old: old.modifier.clone(),
+                new: new.modifier.clone(),
+            });
+        }
+        let (old_hashers, old_key, old_value) = storage_parts(&old.ty);
+        let (new_hashers, new_key, new_value) = storage_parts(&new.ty);
+        if old_hashers != new_hashers {
+            modifications.push(Modification::Hashers {
+                old: old_hashers.to_vec(),
+                new: new_hashers.to_vec(),
+            });
+        }
+        let same_key = match (old_key, new_key) {
+            (Some(old), Some(new)) => self.same_type(old, new),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same_key || !self.same_type(old_value, new_value) {
+            modifications.push(Modification::Type);
+        } else if old.default != new.default {
+            modifications.push(Modification::Default);
+        }
+        modifications
+    }
+
+    fn same_type(&self, old: u32, new: u32) -> bool {
+        self.same_type_assuming(old, new, &mut BTreeSet::new())
+    }
+
+    /// Whether the types encode the same. Pairs in `assumed` are being compared further up,
+    /// which terminates the comparison of recursive types.
+    fn same_type_assuming(&self, old: u32, new: u32, assumed: &mut BTreeSet<(u32, u32)>) -> bool {
+        if !assumed.insert((old, new)) {
+            return true;
+        }
+        let same = match (self.old.resolve(old), self.new.resolve(new)) {
+            (Some(old), Some(new)) => self.same_type_def(old.type_def(), new.type_def(), assumed),
+            _ => false,
+        };
+        if !same {
+            assumed.remove(&(old, new));
+        }
+        same
+    }
+
+    fn same_type_def(
+        &self,
+        old: &TypeDef<PortableForm>,
+        new: &TypeDef<PortableForm>,
+        assumed: &mut BTreeSet<(u32, u32)>,
+    ) -> bool {
+        match (old, new) {
+            (TypeDef::Composite(old), TypeDef::Composite(new)) => {
+                self.same_fields(old.fields(), new.fields(), assumed)
+            }
+            (TypeDef::Variant(old), TypeDef::Variant(new)) => {
+                old.variants().len() == new.variants().len()
+                    && old.variants().iter().all(|old| {
+                        new.variants().iter().any(|new| {
+                            old.index() == new.index()
+                                && self.same_fields(old.fields(), new.fields(), assumed)
+                        })
+                    })
+            }
+            (TypeDef::Sequence(old), TypeDef::Sequence(new)) => {
+                self.same_type_assuming(old.type_param().id(), new.type_param().id(), assumed)
+            }
+            (TypeDef::Array(old), TypeDef::Array(new)) => {
+                old.len() == new.len()
+                    && self.same_type_assuming(
+                        old.type_param().id(),
+                        new.type_param().id(),
+                        assumed,
+                    )
+            }
+            (TypeDef::Tuple(old), TypeDef::Tuple(new)) => {
+                old.fields().len() == new.fields().len()
+                    && old
+                        .fields()
+                        .iter()
+                        .zip(new.fields())
+                        .all(|(old, new)| self.same_type_assuming(old.id(), new.id(), assumed))
+            }
+            (TypeDef::Primitive(old), TypeDef::Primitive(new)) => old == new,
+            (TypeDef::Compact(old), TypeDef::Compact(new)) => {
+                self.same_type_assuming(old.type_param().id(), new.type_param().id(), assumed)
+            }
+            (TypeDef::BitSequence(old), TypeDef::BitSequence(new)) => {
+                // The order types are empty markers, only their name tells them apart.
+                let order_name = |registry: &PortableRegistry, id: u32| {
+                    registry.resolve(id).map(|ty| ty.path().segments().to_vec())
+                };
+                order_name(self.old, old.bit_order_type().id())
+                    == order_name(self.new, new.bit_order_type().id())
+                    && self.same_type_assuming(
+                        old.bit_store_type().id(),
+                        new.bit_store_type().id(),
+                        assumed,
+                    )
+            }
+            _ => false,
+        }
+    }
+
+    fn same_fields(
+        &self,
+        old: &[Field<PortableForm>],
+        new: &[Field<PortableForm>],
+        assumed: &mut BTreeSet<(u32, u32)>,
+    ) -> bool {
+        old.len() == new.len()
+            && old
+                .iter()
+                .zip(new)
+                .all(|(old, new)| self.same_type_assuming(old.ty().id(), new.ty().id(), assumed))
+    }
+}
+
+fn variants_by_name(
+    registry: &PortableRegistry,
+    ty: Option<u32>,
+) -> BTreeMap<String, &Variant<PortableForm>> {
+    match ty
+        .and_then(|ty| registry.resolve(ty))
+        .map(|ty| ty.type_def())
+    {
+        Some(TypeDef::Variant(variant)) => variant
+            .variants()
+            .iter()
+            .map(|variant| (variant.name().clone(), variant))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn storage_by_name(
+    pallet: &PalletMetadata<PortableForm>,
+) -> BTreeMap<String, &StorageEntryMetadata<PortableForm>> {
+    pallet
+        .storage
+        .iter()
+        .flat_map(|storage| storage.entries.iter())
+        .map(|entry| (entry.name.clone(), entry))
+        .collect()
+}
+
+/// Hashers, key type and value type of a storage entry.
+fn storage_parts(ty: &StorageEntryType<PortableForm>) -> (&[StorageHasher], Option<u32>, u32) {
+    match ty {
+        StorageEntryType::Plain(value) => (&[], None, value.id()),
+        StorageEntryType::Map {
+            hashers,
+            key,
+            value,
+        } => (hashers, Some(key.id()), value.id()),
+    }
+}
+
+/// Added and removed items, and the ones `modifications` reports changes for.
+fn diff_by_name<V>(
+    kind: ItemKind,
+    old: &BTreeMap<String, V>,
+    new: &BTreeMap<String, V>,
+    modifications: impl Fn(&V, &V) -> Vec<Modification>,
+) -> Vec<ItemDiff> {
+    let mut items = Vec::new();
+    for (name, old_item) in old {
+        let change = match new.get(name) {
+            None => Change::Removed,
+            Some(new_item) => {
+                let modifications = modifications(old_item, new_item);
+                if modifications.is_empty() {
+                    continue;
+                }
+                Change::Modified(modifications)
+            }
+        };
+        items.push(ItemDiff {
+            kind,
+            name: name.clone(),
+            change,
+        });
+    }
+    items.extend(
+        new.keys()
+            .filter(|name| !old.contains_key(*name))
+            .map(|name| ItemDiff {
+                kind,
+                name: name.clone(),
+                change: Change::Added,
+            }),
+    );
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use frame_metadata::{
+        PalletCallMetadata, PalletConstantMetadata, PalletEventMetadata, PalletStorageMetadata,
+    };
+    use scale_info::{meta_type, MetaType, TypeInfo};
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum OldCall {
+        transfer { dest: [u8; 32], value: u64 },
+        remark { data: Vec<u8> },
+        burn { value: u64 },
+    }
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum NewCall {
+        remark { data: Vec<u8> },
+        transfer { dest: [u8; 32], value: u128 },
+        burn { value: u64 },
+        mint { value: u64 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Tree {
+        Leaf(u32),
+        Node(Vec<Tree>),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum RenamedTree {
+        Leaf(u32),
+        Node(Vec<RenamedTree>),
+    }
+
+    fn metadata(
+        call: MetaType,
+        event: MetaType,
+        hasher: StorageHasher,
+        deposit: u64,
+        extra_pallet: bool,
+    ) -> Metadata {
+        let balances = PalletMetadata {
+            name: "Balances",
+            storage: Some(PalletStorageMetadata {
+                prefix: "Balances",
+                entries: vec![StorageEntryMetadata {
+                    name: "Account",
+                    modifier: StorageEntryModifier::Default,
+                    ty: StorageEntryType::Map {
+                        hashers: vec![hasher],
+                        key: meta_type::<[u8; 32]>(),
+                        value: meta_type::<u64>(),
+                    },
+                    default: deposit.to_le_bytes().to_vec(),
+                    docs: vec![],
+                }],
+            }),
+            calls: Some(PalletCallMetadata { ty: call }),
+            event: Some(PalletEventMetadata { ty: event }),
+            constants: vec![PalletConstantMetadata {
+                name: "ExistentialDeposit",
+                ty: meta_type::<u64>(),
+                value: deposit.to_le_bytes().to_vec(),
+                docs: vec![],
+            }],
+            error: None,
+            index: 5,
+        };
+        let mut pallets = vec![balances];
+        if extra_pallet {
+            pallets.push(test_utils::pallet("Sudo", 6));
+        }
+        test_utils::metadata(pallets)
+    }
+
+    #[test]
+    fn identical_metadata_has_no_diff() {
+        let old = metadata(
+            meta_type::<OldCall>(),
+            meta_type::<Tree>(),
+            StorageHasher::Blake2_128Concat,
+            1,
+            true,
+        );
+        // Renamed types of the same shape are no change.
+        let new = metadata(
+            meta_type::<OldCall>(),
+            meta_type::<RenamedTree>(),
+            StorageHasher::Blake2_128Concat,
+            1,
+            true,
+        );
+        assert!(old.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn reports_item_changes_and_breaking_flag() {
+        let old = metadata(
+            meta_type::<OldCall>(),
+            meta_type::<Tree>(),
+            StorageHasher::Blake2_128Concat,
+            1,
+            true,
+        );
+        let new = metadata(
+            meta_type::<NewCall>(),
+            meta_type::<Tree>(),
+            StorageHasher::Twox64Concat,
+            2,
+            false,
+        );
+        let diff = old.diff(&new);
+        assert!(diff.is_breaking());
+        assert_eq!(diff.pallet("Sudo").unwrap().change, Change::Removed);
+
+        let balances = diff.pallet("Balances").unwrap();
+        assert_eq!(balances.change, Change::Modified(vec![]));
+        assert_eq!(
+            balances.item(ItemKind::Call, "transfer").unwrap().change,
+            Change::Modified(vec![
+                Modification::Index { old: 0, new: 1 },
+                Modification::Type
+            ])
+        );
+        assert_eq!(
+            balances.item(ItemKind::Call, "remark").unwrap().change,
+            Change::Modified(vec![Modification::Index { old: 1, new: 0 }])
+        );
+        assert!(balances.item(ItemKind::Call, "burn").is_none());
+        let mint = balances.item(ItemKind::Call, "mint").unwrap();
+        assert_eq!(mint.change, Change::Added);
+        assert!(!mint.is_breaking());
+        assert_eq!(
+            balances.item(ItemKind::Storage, "Account").unwrap().change,
+            Change::Modified(vec![
+                Modification::Hashers {
+                    old: vec![StorageHasher::Blake2_128Concat],
+                    new: vec![StorageHasher::Twox64Concat],
+                },
+                Modification::Default
+            ])
+        );
+        assert!(!Modification::Default.is_breaking());
+        let deposit = balances
+            .item(ItemKind::Constant, "ExistentialDeposit")
+            .unwrap();
+        assert_eq!(deposit.change, Change::Modified(vec![Modification::Value]));
+        assert!(!deposit.is_breaking());
+    }
+}
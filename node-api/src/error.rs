@@ -21,16 +21,23 @@
 
 use crate::{
     events::EventsDecodingError,
+    extrinsics::ExtrinsicDecodingError,
     metadata::{InvalidMetadataError, Metadata, MetadataError},
+    value::ValueDecodingError,
 };
-use codec::{Decode, Encode};
+use codec::{Decode, Encode, Input};
 use derive_more::From;
+use scale_info::TypeDef;
 use sp_core::crypto::SecretStringError;
-use sp_runtime::{transaction_validity::TransactionValidityError, DispatchError, ModuleError};
+use sp_runtime::{
+    transaction_validity::TransactionValidityError, ArithmeticError, DispatchError, ModuleError,
+    TokenError, TransactionalError,
+};
 
 #[cfg(not(feature = "std"))]
 use alloc::{
     string::{String, ToString},
+    vec,
     vec::Vec,
 };
 
@@ -53,6 +60,10 @@ pub enum Error {
     Runtime(RuntimeError),
     /// Events decoding error.
     EventsDecoding(EventsDecodingError),
+    /// Extrinsic decoding error.
+    ExtrinsicDecoding(ExtrinsicDecodingError),
+    /// Value decoding error.
+    ValueDecoding(ValueDecodingError),
     /// Other error.
     Other(String),
 }
@@ -72,6 +83,12 @@ pub enum RuntimeError {
     BadOrigin,
     /// Cannot lookup some information required to validate the transaction.
     CannotLookup,
+    /// An arithmetic error.
+    Arithmetic(ArithmeticError),
+    /// An error to do with tokens.
+    Token(TokenError),
+    /// An error with a transactional layer.
+    Transactional(TransactionalError),
     /// Other error.
     Other(String),
 }
@@ -85,11 +102,21 @@ impl RuntimeError {
                 error,
                 message: _,
             }) => {
-                let error = metadata.error(index, error[0])?;
+                let error_metadata = metadata.error(index, error[0])?;
+                let (path, description) = match error_variant_path(metadata, index, &error) {
+                    Some((path, docs)) => (path, docs),
+                    // The error type could not be walked, e.g. because `error` was truncated
+                    // to the 4 bytes of a `ModuleError`.
+                    None => (
+                        vec![error_metadata.error().to_string()],
+                        error_metadata.description().to_vec(),
+                    ),
+                };
                 Ok(Self::Module(PalletError {
-                    pallet: error.pallet().to_string(),
-                    error: error.error().to_string(),
-                    description: error.description().to_vec(),
+                    pallet: error_metadata.pallet().to_string(),
+                    error: error_metadata.error().to_string(),
+                    path,
+                    description,
                 }))
             }
             DispatchError::BadOrigin => Ok(Self::BadOrigin),
@@ -97,10 +124,10 @@ impl RuntimeError {
             DispatchError::ConsumerRemaining => Ok(Self::ConsumerRemaining),
             DispatchError::TooManyConsumers => Ok(Self::TooManyConsumers),
             DispatchError::NoProviders => Ok(Self::NoProviders),
-            DispatchError::Arithmetic(_math_error) => Ok(Self::Other("math_error".into())),
-            DispatchError::Token(_token_error) => Ok(Self::Other("token error".into())),
-            DispatchError::Transactional(_transactional_error) => {
-                Ok(Self::Other("transactional error".into()))
+            DispatchError::Arithmetic(arithmetic_error) => Ok(Self::Arithmetic(arithmetic_error)),
+            DispatchError::Token(token_error) => Ok(Self::Token(token_error)),
+            DispatchError::Transactional(transactional_error) => {
+                Ok(Self::Transactional(transactional_error))
             }
             DispatchError::Other(msg) => Ok(Self::Other(msg.to_string())),
         }
@@ -114,6 +141,128 @@ pub struct PalletError {
     pub pallet: String,
     /// The actual error code.
     pub error: String,
-    /// The error description.
+    /// The names of the error variant and of the variants nested in it, e.g.
+    /// `["Execution", "TooExpensive"]` for an error wrapping an XCM error. Starts with `error`.
+    pub path: Vec<String>,
+    /// The error description, of the innermost variant of `path`.
     pub description: Vec<String>,
 }
+
+/// Walk the error type of the pallet with `pallet_index` along the encoded `error`, following
+/// variants with a single field into nested error enums. Returns the variant names and the docs
+/// of the innermost variant.
+///
+/// This is not from subxt.
+fn error_variant_path(
+    metadata: &Metadata,
+    pallet_index: u8,
+    error: &[u8],
+) -> Option<(Vec<String>, Vec<String>)> {
+    let mut type_id = metadata
+        .runtime_metadata()
+        .pallets
+        .iter()
+        .find(|pallet| pallet.index == pallet_index)?
+        .error
+        .as_ref()?
+        .ty
+        .id();
+    let mut input = error;
+    let mut path = Vec::new();
+    let mut docs = Vec::new();
+    loop {
+        match metadata.resolve_type(type_id)?.type_def() {
+            TypeDef::Variant(variant_type) => {
+                let index = input.read_byte().ok()?;
+                let variant = variant_type
+                    .variants()
+                    .iter()
+                    .find(|variant| variant.index() == index)?;
+                path.push(variant.name().to_string());
+                docs = variant.docs().to_vec();
+                match variant.fields() {
+                    [field] => type_id = field.ty().id(),
+                    _ => break,
+                }
+            }
+            // Wrappers of a nested error.
+            TypeDef::Composite(composite) if composite.fields().len() == 1 => {
+                type_id = composite.fields()[0].ty().id()
+            }
+            _ => break,
+        }
+    }
+    Some((path, docs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use frame_metadata::{PalletErrorMetadata, PalletMetadata};
+    use scale_info::{meta_type, TypeInfo};
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum XcmError {
+        Overflow,
+        TooExpensive,
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum TestError {
+        Flat,
+        Nested(XcmError),
+    }
+
+    fn metadata() -> Metadata {
+        test_utils::metadata(vec![PalletMetadata {
+            error: Some(PalletErrorMetadata {
+                ty: meta_type::<TestError>(),
+            }),
+            ..test_utils::pallet("Test", 7)
+        }])
+    }
+
+    fn module_error(error: [u8; 4]) -> RuntimeError {
+        let dispatch_error = DispatchError::Module(ModuleError {
+            index: 7,
+            error,
+            message: None,
+        });
+        RuntimeError::from_dispatch(&metadata(), dispatch_error).unwrap()
+    }
+
+    #[test]
+    fn module_error_without_fields_has_single_element_path() {
+        match module_error([0, 0, 0, 0]) {
+            RuntimeError::Module(e) => {
+                assert_eq!((e.pallet.as_str(), e.error.as_str()), ("Test", "Flat"));
+                assert_eq!(e.path, vec!["Flat"]);
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn nested_module_error_is_followed() {
+        match module_error([1, 1, 0, 0]) {
+            RuntimeError::Module(e) => {
+                assert_eq!(e.error, "Nested");
+                assert_eq!(e.path, vec!["Nested", "TooExpensive"]);
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn arithmetic_error_is_kept() {
+        let error = RuntimeError::from_dispatch(
+            &metadata(),
+            DispatchError::Arithmetic(ArithmeticError::Overflow),
+        )
+        .unwrap();
+        assert_eq!(error, RuntimeError::Arithmetic(ArithmeticError::Overflow));
+    }
+}
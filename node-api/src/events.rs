@@ -22,13 +22,15 @@
 use crate::{
     error::{Error, RuntimeError},
     metadata::{EventMetadata, Metadata, MetadataError},
+    value::{Composite, ValueDecodingError},
     Phase,
 };
 use ac_primitives::Hash;
 use codec::{Codec, Compact, Decode, Encode, Input};
-use scale_info::{prelude::format, TypeDef, TypeDefPrimitive};
+use scale_info::{TypeDef, TypeDefPrimitive};
+use serde::Serialize;
 use sp_core::Bytes;
-use sp_std::marker::PhantomData;
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData};
 
 #[cfg(not(feature = "std"))]
 use alloc::{
@@ -51,6 +53,71 @@ pub struct RawEvent {
     pub data: Bytes,
 }
 
+impl RawEvent {
+    /// Decode the event data into self-describing values, named after the fields of the event
+    /// variant in the metadata.
+    ///
+    /// This is not from subxt.
+    pub fn decode_fields(&self, metadata: &Metadata) -> Result<Composite, Error> {
+        let event_metadata = metadata.event(self.pallet_index, self.variant_index)?;
+        metadata.decode_fields(event_metadata.variant().fields(), &mut &self.data[..])
+    }
+
+    /// Decode the event data as `E`, or `None` if this is not an `E` event.
+    ///
+    /// This is not from subxt.
+    pub fn as_event<E: StaticEvent>(&self) -> Result<Option<E>, codec::Error> {
+        if !E::is_event(&self.pallet, &self.variant) {
+            return Ok(None);
+        }
+        E::decode(&mut &self.data[..]).map(Some)
+    }
+
+    /// Decode the event data into a [`DynamicEvent`], see [`RawEvent::decode_fields`].
+    pub fn to_dynamic(&self, metadata: &Metadata) -> Result<DynamicEvent, Error> {
+        Ok(DynamicEvent {
+            pallet: self.pallet.clone(),
+            pallet_index: self.pallet_index,
+            variant: self.variant.clone(),
+            variant_index: self.variant_index,
+            fields: self.decode_fields(metadata)?,
+        })
+    }
+}
+
+/// Event with its data decoded into self-describing values, which needs no Rust type of the
+/// event and serializes to e.g. JSON.
+///
+/// This is not from subxt.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DynamicEvent {
+    /// The name of the pallet from whence the Event originated.
+    pub pallet: String,
+    /// The index of the pallet from whence the Event originated.
+    pub pallet_index: u8,
+    /// The name of the pallet Event variant.
+    pub variant: String,
+    /// The index of the pallet Event variant.
+    pub variant_index: u8,
+    /// The event fields.
+    pub fields: Composite,
+}
+
+/// An event with a static Rust type, identified by its pallet and variant names.
+///
+/// This is not from subxt.
+pub trait StaticEvent: Decode {
+    /// Name of the pallet emitting the event.
+    const PALLET: &'static str;
+    /// Name of the event variant.
+    const EVENT: &'static str;
+
+    /// Whether the event of `pallet` named `event` is this one.
+    fn is_event(pallet: &str, event: &str) -> bool {
+        Self::PALLET == pallet && Self::EVENT == event
+    }
+}
+
 /// Events decoder.
 ///
 /// In subxt, this was generic over a `Config` type, but it's sole usage was to derive the
@@ -72,6 +139,19 @@ impl EventsDecoder {
 
     /// Decode events.
     pub fn decode_events(&self, input: &mut &[u8]) -> Result<Vec<(Phase, Raw)>, Error> {
+        Ok(self
+            .decode_event_records(input)?
+            .into_iter()
+            .map(|record| (record.phase, record.event))
+            .collect())
+    }
+
+    /// Decode events together with their topics.
+    ///
+    /// Like in `decode_events`, a `System::ExtrinsicFailed` event is returned as [`Raw::Error`].
+    ///
+    /// This is not from subxt.
+    pub fn decode_event_records(&self, input: &mut &[u8]) -> Result<EventRecords, Error> {
         let compact_len = <Compact<u32>>::decode(input)?;
         let len = compact_len.0 as usize;
         log::debug!("decoding {} events", len);
@@ -94,36 +174,180 @@ impl EventsDecoder {
 
             let mut event_data = Vec::<u8>::new();
             let mut event_errors = Vec::<RuntimeError>::new();
-            let result =
-                self.decode_raw_event(event_metadata, input, &mut event_data, &mut event_errors);
-            let raw = match result {
-                Ok(()) => {
-                    log::debug!("raw bytes: {}", hex::encode(&event_data),);
+            self.decode_raw_event(event_metadata, input, &mut event_data, &mut event_errors)?;
+            log::debug!("raw bytes: {}", hex::encode(&event_data),);
+
+            // topics come after the event data in EventRecord
+            let topics = Vec::<Hash>::decode(input)?;
+            log::debug!("topics: {:?}", topics);
 
+            if event_errors.is_empty() {
+                let event = RawEvent {
+                    pallet: event_metadata.pallet().to_string(),
+                    pallet_index,
+                    variant: event_metadata.event().to_string(),
+                    variant_index,
+                    data: event_data.into(),
+                };
+                r.push(EventRecord {
+                    phase: phase.clone(),
+                    event: Raw::Event(event),
+                    topics: topics.clone(),
+                });
+            }
+
+            for err in event_errors {
+                r.push(EventRecord {
+                    phase: phase.clone(),
+                    event: Raw::Error(err),
+                    topics: topics.clone(),
+                });
+            }
+        }
+        Ok(EventRecords(r))
+    }
+
+    /// Decode events without giving up on the first record that cannot be decoded.
+    ///
+    /// A record that fails to decode is reported in [`LenientEvents::undecodable`]. Decoding
+    /// continues with the next record if the length of the failed one can still be determined
+    /// from the metadata, e.g. if only resolving its `DispatchError` failed. Otherwise, e.g. for
+    /// an event unknown to the metadata, the remaining records are counted in
+    /// [`LenientEvents::not_decoded`].
+    ///
+    /// This is not from subxt.
+    pub fn decode_events_lenient(&self, input: &mut &[u8]) -> Result<LenientEvents, Error> {
+        let len = <Compact<u32>>::decode(input)?.0;
+        log::debug!("decoding {} events leniently", len);
+
+        let mut decoded = LenientEvents::default();
+        for index in 0..len {
+            if let Err(undecodable) = self.decode_record_lenient(index, input, &mut decoded) {
+                log::warn!("could not decode event record: {:?}", undecodable);
+                let can_continue = undecodable.skipped;
+                decoded.undecodable.push(undecodable);
+                if !can_continue {
+                    decoded.not_decoded = len - index - 1;
+                    break;
+                }
+            }
+        }
+        Ok(decoded)
+    }
+
+    /// Decode the event record at position `index` into `decoded`. On failure, `input` is
+    /// advanced past the record if its length could be determined nevertheless.
+    fn decode_record_lenient(
+        &self,
+        index: u32,
+        input: &mut &[u8],
+        decoded: &mut LenientEvents,
+    ) -> Result<(), UndecodableEvent> {
+        let undecodable = |phase: Option<Phase>, event_index, error, skipped| UndecodableEvent {
+            index,
+            phase,
+            event_index,
+            error,
+            skipped,
+        };
+
+        let phase = Phase::decode(input).map_err(|e| undecodable(None, None, e.into(), false))?;
+        let event_index = <(u8, u8)>::decode(input)
+            .map_err(|e| undecodable(Some(phase.clone()), None, e.into(), false))?;
+        let event_metadata = self
+            .metadata
+            .event(event_index.0, event_index.1)
+            .map_err(|e| undecodable(Some(phase.clone()), Some(event_index), e.into(), false))?;
+
+        let mut attempt = *input;
+        let mut event_data = Vec::<u8>::new();
+        let mut event_errors = Vec::<RuntimeError>::new();
+        let result = self
+            .decode_raw_event(
+                event_metadata,
+                &mut attempt,
+                &mut event_data,
+                &mut event_errors,
+            )
+            .and_then(|()| Ok(Vec::<Hash>::decode(&mut attempt)?));
+
+        match result {
+            Ok(topics) => {
+                *input = attempt;
+                if event_errors.is_empty() {
                     let event = RawEvent {
                         pallet: event_metadata.pallet().to_string(),
-                        pallet_index,
+                        pallet_index: event_index.0,
                         variant: event_metadata.event().to_string(),
-                        variant_index,
+                        variant_index: event_index.1,
                         data: event_data.into(),
                     };
+                    decoded.records.0.push(EventRecord {
+                        phase: phase.clone(),
+                        event: Raw::Event(event),
+                        topics: topics.clone(),
+                    });
+                }
+                for err in event_errors {
+                    decoded.records.0.push(EventRecord {
+                        phase: phase.clone(),
+                        event: Raw::Error(err),
+                        topics: topics.clone(),
+                    });
+                }
+                Ok(())
+            }
+            Err(error) => {
+                // Determine the length of the record with the value decoder, which does not
+                // need to resolve dispatch errors.
+                let mut skip = *input;
+                let skipped = self
+                    .metadata
+                    .decode_fields(event_metadata.variant().fields(), &mut skip)
+                    .and_then(|_| Ok(Vec::<Hash>::decode(&mut skip)?))
+                    .is_ok();
+                if skipped {
+                    *input = skip;
+                }
+                Err(undecodable(Some(phase), Some(event_index), error, skipped))
+            }
+        }
+    }
 
-                    // topics come after the event data in EventRecord
-                    let topics = Vec::<Hash>::decode(input)?;
-                    log::debug!("topics: {:?}", topics);
+    /// Decode events with their data decoded into self-describing values. In contrast to
+    /// `decode_events`, a `System::ExtrinsicFailed` event is returned like any other event,
+    /// with the `DispatchError` as value.
+    ///
+    /// This is not from subxt.
+    pub fn decode_events_dynamic(
+        &self,
+        input: &mut &[u8],
+    ) -> Result<Vec<(Phase, DynamicEvent)>, Error> {
+        let compact_len = <Compact<u32>>::decode(input)?;
+        let len = compact_len.0 as usize;
+        log::debug!("decoding {} events", len);
 
-                    Raw::Event(event)
-                }
-                Err(err) => return Err(err),
-            };
+        let mut r = Vec::new();
+        for _ in 0..len {
+            let phase = Phase::decode(input)?;
+            let pallet_index = input.read_byte()?;
+            let variant_index = input.read_byte()?;
+            let event_metadata = self.metadata.event(pallet_index, variant_index)?;
 
-            if event_errors.is_empty() {
-                r.push((phase.clone(), raw));
-            }
+            let fields = self
+                .metadata
+                .decode_fields(event_metadata.variant().fields(), input)?;
+            let event = DynamicEvent {
+                pallet: event_metadata.pallet().to_string(),
+                pallet_index,
+                variant: event_metadata.event().to_string(),
+                variant_index,
+                fields,
+            };
 
-            for err in event_errors {
-                r.push((phase.clone(), Raw::Error(err)));
-            }
+            // topics come after the event data in EventRecord
+            let _topics = Vec::<Hash>::decode(input)?;
+            r.push((phase, event));
         }
         Ok(r)
     }
@@ -192,8 +416,9 @@ impl EventsDecoder {
                 variant_index.encode_to(output);
                 let variant = variant
                     .variants()
-                    .get(variant_index as usize)
-                    .ok_or_else(|| Error::Other(format!("Variant {} not found", variant_index)))?;
+                    .iter()
+                    .find(|v| v.index() == variant_index)
+                    .ok_or(ValueDecodingError::VariantNotFound(type_id, variant_index))?;
                 for field in variant.fields() {
                     self.decode_type(field.ty().id(), input, output)?;
                 }
@@ -222,7 +447,11 @@ impl EventsDecoder {
             TypeDef::Primitive(primitive) => match primitive {
                 TypeDefPrimitive::Bool => decode_raw::<bool>(input, output),
                 TypeDefPrimitive::Char => {
-                    Err(EventsDecodingError::UnsupportedPrimitive(TypeDefPrimitive::Char).into())
+                    // Encoded as the `u32` of its unicode scalar value.
+                    let code = u32::decode(input)?;
+                    char::from_u32(code).ok_or(EventsDecodingError::InvalidChar(code))?;
+                    code.encode_to(output);
+                    Ok(())
                 }
                 TypeDefPrimitive::Str => decode_raw::<String>(input, output),
                 TypeDefPrimitive::U8 => decode_raw::<u8>(input, output),
@@ -230,65 +459,235 @@ impl EventsDecoder {
                 TypeDefPrimitive::U32 => decode_raw::<u32>(input, output),
                 TypeDefPrimitive::U64 => decode_raw::<u64>(input, output),
                 TypeDefPrimitive::U128 => decode_raw::<u128>(input, output),
-                TypeDefPrimitive::U256 => {
-                    Err(EventsDecodingError::UnsupportedPrimitive(TypeDefPrimitive::U256).into())
-                }
+                TypeDefPrimitive::U256 => decode_raw::<[u8; 32]>(input, output),
                 TypeDefPrimitive::I8 => decode_raw::<i8>(input, output),
                 TypeDefPrimitive::I16 => decode_raw::<i16>(input, output),
                 TypeDefPrimitive::I32 => decode_raw::<i32>(input, output),
                 TypeDefPrimitive::I64 => decode_raw::<i64>(input, output),
                 TypeDefPrimitive::I128 => decode_raw::<i128>(input, output),
-                TypeDefPrimitive::I256 => {
-                    Err(EventsDecodingError::UnsupportedPrimitive(TypeDefPrimitive::I256).into())
-                }
+                TypeDefPrimitive::I256 => decode_raw::<[u8; 32]>(input, output),
             },
-            TypeDef::Compact(_compact) => {
-                let inner = self
+            TypeDef::Compact(compact) => {
+                self.decode_compact(compact.type_param().id(), input, output)
+            }
+            TypeDef::BitSequence(bit_seq) => {
+                let store_bits = self
                     .metadata
-                    .resolve_type(type_id)
-                    .ok_or(MetadataError::TypeNotFound(type_id))?;
-                let mut decode_compact_primitive = |primitive: &TypeDefPrimitive| match primitive {
-                    TypeDefPrimitive::U8 => decode_raw::<Compact<u8>>(input, output),
-                    TypeDefPrimitive::U16 => decode_raw::<Compact<u16>>(input, output),
-                    TypeDefPrimitive::U32 => decode_raw::<Compact<u32>>(input, output),
-                    TypeDefPrimitive::U64 => decode_raw::<Compact<u64>>(input, output),
-                    TypeDefPrimitive::U128 => decode_raw::<Compact<u128>>(input, output),
-                    prim => Err(EventsDecodingError::InvalidCompactPrimitive(prim.clone()).into()),
-                };
-                match inner.type_def() {
-                    TypeDef::Primitive(primitive) => decode_compact_primitive(primitive),
-                    TypeDef::Composite(composite) => match composite.fields() {
-                        [field] => {
-                            let field_ty = self
-                                .metadata
-                                .resolve_type(field.ty().id())
-                                .ok_or_else(|| MetadataError::TypeNotFound(field.ty().id()))?;
-                            if let TypeDef::Primitive(primitive) = field_ty.type_def() {
-                                decode_compact_primitive(primitive)
-                            } else {
-                                Err(EventsDecodingError::InvalidCompactType(
-                                    "Composite type must have a single primitive field".into(),
-                                )
-                                .into())
-                            }
-                        }
-                        _ => Err(EventsDecodingError::InvalidCompactType(
-                            "Composite type must have a single field".into(),
-                        )
-                        .into()),
-                    },
-                    _ => Err(EventsDecodingError::InvalidCompactType(
-                        "Compact type must be a primitive or a composite type".into(),
-                    )
-                    .into()),
+                    .bit_store_width(bit_seq.bit_store_type().id())?;
+                // The order does not change the length, but must be a known one.
+                self.metadata
+                    .bit_order_is_lsb0(bit_seq.bit_order_type().id())?;
+
+                let bits = <Compact<u32>>::decode(input)?;
+                bits.encode_to(output);
+                let store_elements = (bits.0 as usize + store_bits - 1) / store_bits;
+                let len = store_elements * store_bits / 8;
+                if input.len() < len {
+                    return Err(codec::Error::from("Not enough data for the bit sequence").into());
                 }
+                output.extend_from_slice(&input[..len]);
+                *input = &input[len..];
+                Ok(())
             }
-            TypeDef::BitSequence(_bitseq) => {
-                // decode_raw::<bitvec::BitVec>
-                unimplemented!("BitVec decoding for events not implemented yet")
-            }
         }
     }
+
+    /// Decode a compact encoded value of the inner type `type_id`: an unsigned integer or a
+    /// (possibly nested) wrapper with a single such field.
+    ///
+    /// This is not from subxt.
+    fn decode_compact(
+        &self,
+        type_id: u32,
+        input: &mut &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let ty = self
+            .metadata
+            .resolve_type(type_id)
+            .ok_or(MetadataError::TypeNotFound(type_id))?;
+
+        fn decode_raw<T: Codec>(input: &mut &[u8], output: &mut Vec<u8>) -> Result<(), Error> {
+            let decoded = T::decode(input)?;
+            decoded.encode_to(output);
+            Ok(())
+        }
+
+        match ty.type_def() {
+            TypeDef::Primitive(primitive) => match primitive {
+                TypeDefPrimitive::U8 => decode_raw::<Compact<u8>>(input, output),
+                TypeDefPrimitive::U16 => decode_raw::<Compact<u16>>(input, output),
+                TypeDefPrimitive::U32 => decode_raw::<Compact<u32>>(input, output),
+                TypeDefPrimitive::U64 => decode_raw::<Compact<u64>>(input, output),
+                TypeDefPrimitive::U128 => decode_raw::<Compact<u128>>(input, output),
+                prim => Err(EventsDecodingError::InvalidCompactPrimitive(prim.clone()).into()),
+            },
+            TypeDef::Composite(composite) => match composite.fields() {
+                // `Compact<()>` is encoded as nothing.
+                [] => Ok(()),
+                [field] => self.decode_compact(field.ty().id(), input, output),
+                _ => Err(EventsDecodingError::InvalidCompactType(
+                    "Composite type must have a single field".into(),
+                )
+                .into()),
+            },
+            TypeDef::Tuple(tuple) => match tuple.fields() {
+                [] => Ok(()),
+                [field] => self.decode_compact(field.id(), input, output),
+                _ => Err(EventsDecodingError::InvalidCompactType(
+                    "Tuple type must have a single field".into(),
+                )
+                .into()),
+            },
+            _ => Err(EventsDecodingError::InvalidCompactType(
+                "Compact type must be a primitive or a composite type".into(),
+            )
+            .into()),
+        }
+    }
+}
+
+/// A decoded event with the phase it was emitted in and its topics.
+///
+/// This is not from subxt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventRecord {
+    /// The phase of the block it happened in.
+    pub phase: Phase,
+    /// The event itself.
+    pub event: Raw,
+    /// The list of the topics this event has.
+    pub topics: Vec<Hash>,
+}
+
+/// The event records of a block, in the order they were emitted.
+///
+/// This is not from subxt.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventRecords(pub Vec<EventRecord>);
+
+impl EventRecords {
+    /// Iterate over all records.
+    pub fn iter(&self) -> impl Iterator<Item = &EventRecord> {
+        self.0.iter()
+    }
+
+    /// Records emitted in `phase`.
+    pub fn in_phase<'a>(&'a self, phase: &'a Phase) -> impl Iterator<Item = &'a EventRecord> {
+        self.0.iter().filter(move |record| &record.phase == phase)
+    }
+
+    /// Records emitted while applying the extrinsic at position `index` in the block.
+    pub fn for_extrinsic(&self, index: u32) -> impl Iterator<Item = &EventRecord> {
+        self.0
+            .iter()
+            .filter(move |record| record.phase == Phase::ApplyExtrinsic(index))
+    }
+
+    /// Records emitted while initializing the block.
+    pub fn initialization(&self) -> impl Iterator<Item = &EventRecord> {
+        self.0
+            .iter()
+            .filter(|record| record.phase == Phase::Initialization)
+    }
+
+    /// Records emitted while finalizing the block.
+    pub fn finalization(&self) -> impl Iterator<Item = &EventRecord> {
+        self.0
+            .iter()
+            .filter(|record| record.phase == Phase::Finalization)
+    }
+
+    /// Records grouped by phase. Within a phase, records keep their order.
+    pub fn group_by_phase(&self) -> BTreeMap<Phase, Vec<&EventRecord>> {
+        let mut groups = BTreeMap::<Phase, Vec<&EventRecord>>::new();
+        for record in self.0.iter() {
+            groups.entry(record.phase.clone()).or_default().push(record);
+        }
+        groups
+    }
+
+    /// Records having `topic` among their topics.
+    pub fn with_topic<'a>(&'a self, topic: &'a Hash) -> impl Iterator<Item = &'a EventRecord> {
+        self.0
+            .iter()
+            .filter(move |record| record.topics.contains(topic))
+    }
+
+    /// Decode the first `E` event, if there is any.
+    pub fn find_first<E: StaticEvent>(&self) -> Result<Option<E>, Error> {
+        Ok(self.find::<E>().next().transpose()?.map(|(_, event)| event))
+    }
+
+    /// Decode all `E` events, in the order they were emitted.
+    pub fn find_all<E: StaticEvent>(&self) -> Result<Vec<E>, Error> {
+        self.find::<E>()
+            .map(|result| result.map(|(_, event)| event))
+            .collect()
+    }
+
+    /// Iterate over the `E` events, decoded, along with their records.
+    pub fn find<E: StaticEvent>(&self) -> impl Iterator<Item = Result<(&EventRecord, E), Error>> {
+        self.0.iter().filter_map(|record| match &record.event {
+            Raw::Event(raw) => raw
+                .as_event::<E>()
+                .map_err(Error::from)
+                .transpose()
+                .map(|event| event.map(|event| (record, event))),
+            Raw::Error(_) => None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl IntoIterator for EventRecords {
+    type Item = EventRecord;
+    type IntoIter = sp_std::vec::IntoIter<EventRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl From<EventRecords> for Vec<EventRecord> {
+    fn from(records: EventRecords) -> Self {
+        records.0
+    }
+}
+
+/// Result of [`EventsDecoder::decode_events_lenient`].
+///
+/// This is not from subxt.
+#[derive(Debug, Default)]
+pub struct LenientEvents {
+    /// Successfully decoded records with their topics, as returned by `decode_event_records`.
+    pub records: EventRecords,
+    /// Records that could not be decoded.
+    pub undecodable: Vec<UndecodableEvent>,
+    /// Number of records following an undecodable one that could not be skipped.
+    pub not_decoded: u32,
+}
+
+/// An event record that could not be decoded.
+#[derive(Debug)]
+pub struct UndecodableEvent {
+    /// Position of the record in the block's events.
+    pub index: u32,
+    /// Phase of the record, if it could be decoded.
+    pub phase: Option<Phase>,
+    /// Pallet and variant index of the event, if they could be decoded.
+    pub event_index: Option<(u8, u8)>,
+    /// Why decoding failed.
+    pub error: Error,
+    /// Whether the record could be skipped to continue with the next one.
+    pub skipped: bool,
 }
 
 /// Raw event or error event
@@ -302,9 +701,346 @@ pub enum Raw {
 
 #[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Encode, Decode)]
 pub enum EventsDecodingError {
-    /// Unsupported primitive type
-    UnsupportedPrimitive(TypeDefPrimitive),
     /// Invalid compact type, must be an unsigned int.
     InvalidCompactPrimitive(TypeDefPrimitive),
     InvalidCompactType(String),
+    /// Not a valid unicode scalar value.
+    InvalidChar(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use bitvec::{
+        order::{Lsb0, Msb0},
+        vec::BitVec,
+    };
+    use frame_metadata::{PalletEventMetadata, PalletMetadata};
+    use scale_info::{meta_type, MetaType, Type, TypeInfo};
+    use sp_runtime::{DispatchError, ModuleError};
+
+    /// Has the type info of the `U256` primitive, which no rust type of the dependencies has.
+    struct U256Primitive;
+
+    impl TypeInfo for U256Primitive {
+        type Identity = Self;
+
+        fn type_info() -> Type {
+            TypeDefPrimitive::U256.into()
+        }
+    }
+
+    #[derive(TypeInfo)]
+    struct Wrapper(u64);
+
+    #[derive(TypeInfo)]
+    struct NestedWrapper(Wrapper);
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Event {
+        Bits(BitVec<u8, Lsb0>),
+        WideBits(BitVec<u32, Msb0>),
+        Char(char),
+        Big(U256Primitive),
+        CompactWrapper(#[codec(compact)] Wrapper),
+        CompactNestedWrapper(#[codec(compact)] NestedWrapper, u8),
+        Gapped(GappedEnum, u8),
+    }
+
+    /// Variant indices differing from the positions, like pallet calls after a removal.
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum GappedEnum {
+        #[codec(index = 0)]
+        First,
+        #[codec(index = 5)]
+        Sixth(u16),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum SystemEvent {
+        ExtrinsicFailed { dispatch_error: DispatchError },
+    }
+
+    fn pallet(name: &'static str, index: u8, event: MetaType) -> PalletMetadata {
+        PalletMetadata {
+            event: Some(PalletEventMetadata { ty: event }),
+            ..test_utils::pallet(name, index)
+        }
+    }
+
+    fn decoder() -> EventsDecoder {
+        let pallets = vec![
+            pallet("Test", 0, meta_type::<Event>()),
+            pallet("System", 1, meta_type::<SystemEvent>()),
+        ];
+        EventsDecoder::new(test_utils::metadata(pallets))
+    }
+
+    /// Encoded `Vec<EventRecord>` with a single event of the test pallet.
+    fn single_event(variant_index: u8, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Compact(1u32).encode();
+        Phase::ApplyExtrinsic(1).encode_to(&mut bytes);
+        bytes.extend_from_slice(&[0, variant_index]);
+        bytes.extend_from_slice(data);
+        Vec::<Hash>::new().encode_to(&mut bytes);
+        bytes
+    }
+
+    fn assert_decodes_to_itself(variant_index: u8, data: &[u8]) {
+        let input = single_event(variant_index, data);
+        let events = decoder().decode_events(&mut input.as_slice()).unwrap();
+        match &events[..] {
+            [(Phase::ApplyExtrinsic(1), Raw::Event(event))] => {
+                assert_eq!(event.variant_index, variant_index);
+                assert_eq!(&event.data[..], data);
+            }
+            events => panic!("unexpected events: {:?}", events),
+        }
+    }
+
+    #[test]
+    fn decodes_bit_sequence_with_u8_store() {
+        // 9 bits need two store elements.
+        let data = [Compact(9u32).encode(), vec![0b0000_1101, 0b0000_0001]].concat();
+        assert_decodes_to_itself(0, &data);
+    }
+
+    #[test]
+    fn decodes_bit_sequence_with_u32_store() {
+        // 33 bits need two store elements of 4 bytes.
+        let data = [Compact(33u32).encode(), vec![0xff; 8]].concat();
+        assert_decodes_to_itself(1, &data);
+    }
+
+    #[test]
+    fn decodes_char() {
+        assert_decodes_to_itself(2, &('λ' as u32).encode());
+    }
+
+    #[test]
+    fn rejects_invalid_char() {
+        let input = single_event(2, &0xD800u32.encode());
+        let result = decoder().decode_events(&mut input.as_slice());
+        assert!(matches!(
+            result,
+            Err(Error::EventsDecoding(EventsDecodingError::InvalidChar(
+                0xD800
+            )))
+        ));
+    }
+
+    #[test]
+    fn decodes_u256() {
+        assert_decodes_to_itself(3, &[0xab; 32]);
+    }
+
+    #[test]
+    fn decodes_variants_by_their_codec_index() {
+        assert_decodes_to_itself(6, &[5, 0x34, 0x12, 7]);
+        assert_decodes_to_itself(6, &[0, 7]);
+
+        let input = single_event(6, &[1, 7]);
+        let result = decoder().decode_events(&mut input.as_slice());
+        assert!(
+            matches!(
+                result,
+                Err(Error::ValueDecoding(ValueDecodingError::VariantNotFound(
+                    _,
+                    1
+                )))
+            ),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn lenient_decoding_skips_broken_records_of_known_length() {
+        // The module error of an unknown pallet cannot be resolved, but its length is known.
+        let unknown_module_error = DispatchError::Module(ModuleError {
+            index: 9,
+            error: [0; 4],
+            message: None,
+        });
+        let mut input = Compact(3u32).encode();
+        for (event_index, data) in [
+            ((0u8, 3u8), vec![0xab; 32]),
+            ((1, 0), unknown_module_error.encode()),
+            ((0, 4), Compact(5u64).encode()),
+        ] {
+            Phase::Finalization.encode_to(&mut input);
+            event_index.encode_to(&mut input);
+            input.extend_from_slice(&data);
+            Vec::<Hash>::new().encode_to(&mut input);
+        }
+
+        let decoded = decoder()
+            .decode_events_lenient(&mut input.as_slice())
+            .unwrap();
+
+        let variants: Vec<_> = decoded
+            .records
+            .iter()
+            .map(|record| match &record.event {
+                Raw::Event(raw) => raw.variant_index,
+                Raw::Error(e) => panic!("Unexpected error {:?}", e),
+            })
+            .collect();
+        assert_eq!(variants, vec![3, 4]);
+        assert_eq!(decoded.undecodable.len(), 1);
+        assert_eq!(decoded.undecodable[0].index, 1);
+        assert_eq!(decoded.undecodable[0].event_index, Some((1, 0)));
+        assert!(decoded.undecodable[0].skipped);
+        assert_eq!(decoded.not_decoded, 0);
+    }
+
+    #[test]
+    fn lenient_decoding_stops_at_unknown_events() {
+        let mut input = Compact(3u32).encode();
+        for variant_index in [3u8, 42, 3] {
+            Phase::Finalization.encode_to(&mut input);
+            input.extend_from_slice(&[0, variant_index]);
+            input.extend_from_slice(&[0xab; 32]);
+            Vec::<Hash>::new().encode_to(&mut input);
+        }
+
+        let decoded = decoder()
+            .decode_events_lenient(&mut input.as_slice())
+            .unwrap();
+
+        assert_eq!(decoded.records.len(), 1);
+        assert_eq!(decoded.undecodable[0].event_index, Some((0, 42)));
+        assert!(!decoded.undecodable[0].skipped);
+        assert_eq!(decoded.not_decoded, 1);
+    }
+
+    #[test]
+    fn event_records_keep_topics_and_group_by_phase() {
+        let topic = Hash::repeat_byte(1);
+        let mut input = Compact(4u32).encode();
+        for (phase, topics) in [
+            (Phase::Initialization, vec![]),
+            (Phase::ApplyExtrinsic(1), vec![topic]),
+            (Phase::ApplyExtrinsic(0), vec![]),
+            (Phase::ApplyExtrinsic(1), vec![Hash::repeat_byte(2), topic]),
+        ] {
+            phase.encode_to(&mut input);
+            input.extend_from_slice(&[0, 4]);
+            Compact(1u64).encode_to(&mut input);
+            topics.encode_to(&mut input);
+        }
+
+        let records = decoder()
+            .decode_event_records(&mut input.as_slice())
+            .unwrap();
+
+        assert_eq!(records.len(), 4);
+        assert_eq!(records.for_extrinsic(1).count(), 2);
+        assert_eq!(records.initialization().count(), 1);
+        assert_eq!(records.finalization().count(), 0);
+        let groups = records.group_by_phase();
+        assert_eq!(
+            groups.keys().cloned().collect::<Vec<_>>(),
+            vec![
+                Phase::ApplyExtrinsic(0),
+                Phase::ApplyExtrinsic(1),
+                Phase::Initialization
+            ]
+        );
+        assert_eq!(groups[&Phase::ApplyExtrinsic(1)][1].topics.len(), 2);
+        assert_eq!(records.with_topic(&topic).count(), 2);
+        assert_eq!(records.with_topic(&Hash::repeat_byte(2)).count(), 1);
+    }
+
+    #[test]
+    fn dynamic_decoding_fails_on_lengths_beyond_the_input() {
+        let input = Compact(u32::MAX).encode();
+
+        assert!(decoder()
+            .decode_events_dynamic(&mut input.as_slice())
+            .is_err());
+    }
+
+    #[test]
+    fn dynamic_decoding_fails_on_bit_counts_beyond_the_input() {
+        let input = single_event(0, &Compact(u32::MAX).encode());
+
+        assert!(decoder()
+            .decode_events_dynamic(&mut input.as_slice())
+            .is_err());
+    }
+
+    #[test]
+    fn lenient_decoding_keeps_the_topics() {
+        let mut input = Compact(2u32).encode();
+        for topics in [vec![Hash::repeat_byte(1)], vec![]] {
+            Phase::Finalization.encode_to(&mut input);
+            input.extend_from_slice(&[0, 4]);
+            Compact(1u64).encode_to(&mut input);
+            topics.encode_to(&mut input);
+        }
+
+        let decoded = decoder()
+            .decode_events_lenient(&mut input.as_slice())
+            .unwrap();
+
+        assert_eq!(
+            decoded.records,
+            decoder()
+                .decode_event_records(&mut input.as_slice())
+                .unwrap()
+        );
+        assert_eq!(decoded.records.0[0].topics, vec![Hash::repeat_byte(1)]);
+    }
+
+    #[derive(Decode, Debug, PartialEq)]
+    struct CompactWrapperEvent(#[codec(compact)] u64);
+
+    impl StaticEvent for CompactWrapperEvent {
+        const PALLET: &'static str = "Test";
+        const EVENT: &'static str = "CompactWrapper";
+    }
+
+    #[test]
+    fn finds_static_events() {
+        let mut input = Compact(3u32).encode();
+        for (variant_index, data) in [
+            (4u8, Compact(1u64).encode()),
+            (2, ('a' as u32).encode()),
+            (4, Compact(2u64).encode()),
+        ] {
+            Phase::ApplyExtrinsic(0).encode_to(&mut input);
+            input.extend_from_slice(&[0, variant_index]);
+            input.extend_from_slice(&data);
+            Vec::<Hash>::new().encode_to(&mut input);
+        }
+
+        let records = decoder()
+            .decode_event_records(&mut input.as_slice())
+            .unwrap();
+
+        assert_eq!(
+            records.find_first::<CompactWrapperEvent>().unwrap(),
+            Some(CompactWrapperEvent(1))
+        );
+        assert_eq!(
+            records.find_all::<CompactWrapperEvent>().unwrap(),
+            vec![CompactWrapperEvent(1), CompactWrapperEvent(2)]
+        );
+    }
+
+    #[test]
+    fn decodes_compact_wrapper() {
+        assert_decodes_to_itself(4, &Compact(1_000_000u64).encode());
+    }
+
+    #[test]
+    fn decodes_compact_nested_wrapper_followed_by_other_fields() {
+        let data = [Compact(u64::MAX).encode(), vec![7]].concat();
+        assert_decodes_to_itself(5, &data);
+    }
 }
@@ -0,0 +1,374 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Metadata driven decoding of opaque extrinsics.
+//!
+//! This file is **not** from subxt.
+
+use crate::{
+    error::Error,
+    metadata::{Metadata, MetadataError},
+    value::{Composite, Value},
+};
+use codec::{Compact, Decode};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// The only extrinsic format version supported.
+const EXTRINSIC_VERSION: u8 = 4;
+
+/// An extrinsic decoded into named calls and arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedExtrinsic {
+    /// Signature part, `None` for unsigned extrinsics.
+    pub signature: Option<ExtrinsicSignature>,
+    /// The name of the pallet the call belongs to.
+    pub pallet: String,
+    /// The index of the pallet the call belongs to.
+    pub pallet_index: u8,
+    /// The name of the call.
+    pub call: String,
+    /// The index of the call.
+    pub call_index: u8,
+    /// The call arguments, named if the call has named fields.
+    pub args: Composite,
+}
+
+/// The signature part of a signed extrinsic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtrinsicSignature {
+    /// The address of the signer.
+    pub address: Value,
+    /// The SCALE encoded address of the signer, e.g. to decode it as `GenericAddress`.
+    pub encoded_address: Vec<u8>,
+    /// The signature.
+    pub signature: Value,
+    /// The signed extensions, identified by name, in the order given by the metadata.
+    pub extensions: Vec<(String, Value)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd)]
+pub enum ExtrinsicDecodingError {
+    /// Only V4 extrinsics can be decoded.
+    UnsupportedVersion(u8),
+    /// The metadata extrinsic type lacks a type parameter (`Address` or `Signature`).
+    MissingTypeParameter(&'static str),
+    /// Bytes left after decoding the call. The length prefix does not match the content.
+    TrailingBytes(usize),
+}
+
+/// Extrinsics decoder.
+///
+/// Decodes extrinsics of any runtime based on the `scale_info` type registry in the metadata.
+#[derive(Clone, Debug)]
+pub struct ExtrinsicDecoder {
+    metadata: Metadata,
+}
+
+impl ExtrinsicDecoder {
+    /// Creates a new `ExtrinsicDecoder`.
+    pub fn new(metadata: Metadata) -> Self {
+        Self { metadata }
+    }
+
+    /// Decode a length prefixed extrinsic, as contained in a block.
+    pub fn decode_extrinsic(&self, input: &mut &[u8]) -> Result<DecodedExtrinsic, Error> {
+        let len = <Compact<u32>>::decode(input)?.0 as usize;
+        if input.len() < len {
+            return Err(codec::Error::from("Not enough data to fill extrinsic").into());
+        }
+        let (mut xt, rest) = input.split_at(len);
+        *input = rest;
+
+        let version = u8::decode(&mut xt)?;
+        let is_signed = version & 0b1000_0000 != 0;
+        let version = version & 0b0111_1111;
+        if version != EXTRINSIC_VERSION {
+            return Err(ExtrinsicDecodingError::UnsupportedVersion(version).into());
+        }
+
+        let signature = if is_signed {
+            Some(self.decode_signature(&mut xt)?)
+        } else {
+            None
+        };
+
+        let pallet_index = u8::decode(&mut xt)?;
+        let call_index = u8::decode(&mut xt)?;
+        let call = self.metadata.call_by_index(pallet_index, call_index)?;
+        log::debug!(
+            "decoding call '{}::{}' of extrinsic",
+            call.pallet(),
+            call.call()
+        );
+        let args = self
+            .metadata
+            .decode_fields(call.variant().fields(), &mut xt)?;
+
+        if !xt.is_empty() {
+            return Err(ExtrinsicDecodingError::TrailingBytes(xt.len()).into());
+        }
+
+        Ok(DecodedExtrinsic {
+            signature,
+            pallet: call.pallet().to_string(),
+            pallet_index,
+            call: call.call().to_string(),
+            call_index,
+            args,
+        })
+    }
+
+    /// Decode all extrinsics of a block, as returned by `Encode` on an opaque extrinsic.
+    pub fn decode_extrinsics<'a, I>(&self, extrinsics: I) -> Result<Vec<DecodedExtrinsic>, Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        extrinsics
+            .into_iter()
+            .map(|mut xt| self.decode_extrinsic(&mut xt))
+            .collect()
+    }
+
+    fn decode_signature(&self, input: &mut &[u8]) -> Result<ExtrinsicSignature, Error> {
+        let address_ty = self.extrinsic_type_parameter("Address")?;
+        let signature_ty = self.extrinsic_type_parameter("Signature")?;
+
+        let address_start = *input;
+        let address = self.metadata.decode_value(address_ty, input)?;
+        let encoded_address = address_start[..address_start.len() - input.len()].to_vec();
+
+        let signature = self.metadata.decode_value(signature_ty, input)?;
+
+        let extensions = self
+            .metadata
+            .runtime_metadata()
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|extension| {
+                self.metadata
+                    .decode_value(extension.ty.id(), input)
+                    .map(|value| (extension.identifier.clone(), value))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ExtrinsicSignature {
+            address,
+            encoded_address,
+            signature,
+            extensions,
+        })
+    }
+
+    /// Type id of a generic parameter of the runtime's `UncheckedExtrinsic` type.
+    fn extrinsic_type_parameter(&self, name: &'static str) -> Result<u32, Error> {
+        // V15 names the types directly.
+        if let Some(types) = &self.metadata.extrinsic_types {
+            match name {
+                "Address" => return Ok(types.address),
+                "Signature" => return Ok(types.signature),
+                _ => {}
+            }
+        }
+        let extrinsic_ty = self.metadata.runtime_metadata().extrinsic.ty.id();
+        let ty = self
+            .metadata
+            .resolve_type(extrinsic_ty)
+            .ok_or(MetadataError::TypeNotFound(extrinsic_ty))?;
+        ty.type_params()
+            .iter()
+            .find(|param| param.name() == name)
+            .and_then(|param| param.ty())
+            .map(|ty| ty.id())
+            .ok_or_else(|| ExtrinsicDecodingError::MissingTypeParameter(name).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_utils, value::Primitive};
+    use codec::Encode;
+    use frame_metadata::{
+        ExtrinsicMetadata, PalletCallMetadata, PalletMetadata, SignedExtensionMetadata,
+    };
+    use scale_info::{meta_type, TypeInfo};
+    use sp_std::marker::PhantomData;
+
+    /// Stand-in for the runtime's `UncheckedExtrinsic`, only its type parameters are used.
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct UncheckedExtrinsic<Address, Call, Signature, Extra>(
+        PhantomData<(Address, Call, Signature, Extra)>,
+    );
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum Call {
+        remark(Vec<u8>),
+        transfer {
+            dest: u32,
+            #[codec(compact)]
+            value: u128,
+        },
+    }
+
+    fn decoder() -> ExtrinsicDecoder {
+        let pallet = PalletMetadata {
+            calls: Some(PalletCallMetadata {
+                ty: meta_type::<Call>(),
+            }),
+            ..test_utils::pallet("Balances", 5)
+        };
+        let extrinsic = ExtrinsicMetadata {
+            ty: meta_type::<UncheckedExtrinsic<u32, Call, [u8; 4], ()>>(),
+            version: 4,
+            signed_extensions: vec![SignedExtensionMetadata {
+                identifier: "CheckNonce",
+                ty: meta_type::<Compact<u32>>(),
+                additional_signed: meta_type::<()>(),
+            }],
+        };
+        ExtrinsicDecoder::new(test_utils::metadata_with_extrinsic(vec![pallet], extrinsic))
+    }
+
+    /// Length prefixed extrinsic of the version byte followed by `body`.
+    fn extrinsic(version: u8, body: &[u8]) -> Vec<u8> {
+        let mut xt = vec![version];
+        xt.extend_from_slice(body);
+        xt.encode()
+    }
+
+    fn transfer_call() -> Vec<u8> {
+        let mut call = vec![5, 1];
+        7u32.encode_to(&mut call);
+        Compact(1000u128).encode_to(&mut call);
+        call
+    }
+
+    fn transfer_args() -> Composite {
+        Composite::Named(vec![
+            ("dest".into(), Value::Primitive(Primitive::U128(7))),
+            ("value".into(), Value::Primitive(Primitive::U128(1000))),
+        ])
+    }
+
+    #[test]
+    fn decodes_unsigned_extrinsic() {
+        let input = extrinsic(4, &transfer_call());
+        let xt = decoder().decode_extrinsic(&mut input.as_slice()).unwrap();
+        assert_eq!(
+            xt,
+            DecodedExtrinsic {
+                signature: None,
+                pallet: "Balances".into(),
+                pallet_index: 5,
+                call: "transfer".into(),
+                call_index: 1,
+                args: transfer_args(),
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_signed_extrinsic() {
+        let mut body = 42u32.encode();
+        body.extend_from_slice(&[1, 2, 3, 4]);
+        Compact(3u32).encode_to(&mut body);
+        body.extend(transfer_call());
+        let input = extrinsic(0b1000_0100, &body);
+
+        let xt = decoder().decode_extrinsic(&mut input.as_slice()).unwrap();
+        let signature = xt.signature.unwrap();
+        assert_eq!(signature.address, Value::Primitive(Primitive::U128(42)));
+        assert_eq!(signature.encoded_address, 42u32.encode());
+        assert_eq!(
+            signature.signature,
+            Value::Sequence(
+                (1..=4)
+                    .map(|b| Value::Primitive(Primitive::U128(b)))
+                    .collect()
+            )
+        );
+        assert_eq!(
+            signature.extensions,
+            vec![(
+                "CheckNonce".to_string(),
+                Value::Primitive(Primitive::U128(3))
+            )]
+        );
+        assert_eq!(
+            (xt.pallet.as_str(), xt.call.as_str()),
+            ("Balances", "transfer")
+        );
+        assert_eq!(xt.args, transfer_args());
+    }
+
+    #[test]
+    fn decodes_consecutive_extrinsics() {
+        let first = extrinsic(4, &[5, 0, 0]);
+        let second = extrinsic(4, &transfer_call());
+        let xts = decoder()
+            .decode_extrinsics([first.as_slice(), second.as_slice()])
+            .unwrap();
+        assert_eq!(xts[0].call, "remark");
+        assert_eq!(
+            xts[0].args,
+            Composite::Unnamed(vec![Value::Sequence(vec![])])
+        );
+        assert_eq!(xts[1].call, "transfer");
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut call = transfer_call();
+        call.extend_from_slice(&[0, 0]);
+        let input = extrinsic(4, &call);
+        assert!(matches!(
+            decoder().decode_extrinsic(&mut input.as_slice()),
+            Err(Error::ExtrinsicDecoding(
+                ExtrinsicDecodingError::TrailingBytes(2)
+            ))
+        ));
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let input = extrinsic(3, &transfer_call());
+        assert!(matches!(
+            decoder().decode_extrinsic(&mut input.as_slice()),
+            Err(Error::ExtrinsicDecoding(
+                ExtrinsicDecodingError::UnsupportedVersion(3)
+            ))
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_call() {
+        let input = extrinsic(4, &[5, 2]);
+        assert!(matches!(
+            decoder().decode_extrinsic(&mut input.as_slice()),
+            Err(Error::Metadata(MetadataError::CallIndexNotFound(5, 2)))
+        ));
+        let input = extrinsic(4, &[6, 0]);
+        assert!(matches!(
+            decoder().decode_extrinsic(&mut input.as_slice()),
+            Err(Error::Metadata(MetadataError::CallIndexNotFound(6, 0)))
+        ));
+    }
+}
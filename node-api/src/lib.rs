@@ -22,15 +22,26 @@ use alloc::{borrow::ToOwned, vec::Vec};
 
 use codec::{Decode, Encode};
 
+pub use diff::*;
 pub use error::*;
 pub use events::*;
+pub use extrinsics::*;
 pub use metadata::*;
 pub use storage::*;
+pub use v15::*;
+pub use value::*;
 
+pub mod calls;
+pub mod diff;
 pub mod error;
 pub mod events;
+pub mod extrinsics;
 pub mod metadata;
 pub mod storage;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod v15;
+pub mod value;
 
 #[cfg(feature = "std")]
 mod print_metadata;
@@ -47,7 +58,7 @@ impl codec::Encode for Encoded {
 }
 
 /// A phase of a block's execution.
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Encode, Decode)]
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, serde::Serialize)]
 pub enum Phase {
     /// Applying an extrinsic.
     ApplyExtrinsic(u32),
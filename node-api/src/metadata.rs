@@ -19,11 +19,16 @@
 //!
 //! This file is mostly subxt.
 
-use crate::{storage::GetStorage, Encoded};
+use crate::{
+    calls::CallMetadata,
+    storage::{GetStorage, StaticStorageAddress},
+    v15::{CustomMetadata, ExtrinsicTypes, OuterEnums, RuntimeApiMetadata, VersionedMetadata},
+    Encoded,
+};
 use codec::{Decode, Encode, Error as CodecError};
 use frame_metadata::{
-    PalletConstantMetadata, RuntimeMetadata, RuntimeMetadataLastVersion, RuntimeMetadataPrefixed,
-    StorageEntryMetadata, META_RESERVED,
+    PalletConstantMetadata, RuntimeMetadataLastVersion, RuntimeMetadataPrefixed,
+    StorageEntryMetadata, StorageEntryType, META_RESERVED,
 };
 use scale_info::{form::PortableForm, Type, Variant};
 use sp_core::storage::StorageKey;
@@ -32,7 +37,7 @@ use sp_core::storage::StorageKey;
 use serde::Serialize;
 
 // We use `BTreeMap` because we can't use `HashMap` in `no_std`.
-use sp_std::collections::btree_map::BTreeMap;
+use sp_std::{collections::btree_map::BTreeMap, marker::PhantomData};
 
 #[cfg(not(feature = "std"))]
 use alloc::{
@@ -50,6 +55,8 @@ pub enum MetadataError {
     PalletIndexNotFound(u8),
     /// Call is not in metadata.
     CallNotFound(&'static str),
+    /// Call is not in metadata: (pallet index, call index).
+    CallIndexNotFound(u8, u8),
     /// Event is not in metadata.
     EventNotFound(u8, u8),
     /// Error is not in metadata.
@@ -68,15 +75,31 @@ pub enum MetadataError {
     ConstantNotFound(&'static str),
     /// Type is missing from type registry.
     TypeNotFound(u32),
+    /// Number of call arguments does not match the call: (call, expected, supplied).
+    CallArgumentCount(String, usize, usize),
+    /// Call argument does not match its field type: (call, field, reason).
+    CallArgumentMismatch(String, String, String),
 }
 
 /// Runtime metadata.
 #[derive(Clone, Debug, Encode, Decode)]
 pub struct Metadata {
+    /// The metadata, in its V14 layout for newer versions.
     pub metadata: RuntimeMetadataLastVersion,
     pub pallets: BTreeMap<String, PalletMetadata>,
     pub events: BTreeMap<(u8, u8), EventMetadata>,
     pub errors: BTreeMap<(u8, u8), ErrorMetadata>,
+    pub calls: BTreeMap<(u8, u8), CallMetadata>,
+    /// Version of the metadata this was built from.
+    pub version: u32,
+    /// Runtime apis, available from V15.
+    pub apis: Vec<RuntimeApiMetadata>,
+    /// Outer enums of the runtime, available from V15.
+    pub outer_enums: Option<OuterEnums>,
+    /// Types of the extrinsic parts, available from V15.
+    pub extrinsic_types: Option<ExtrinsicTypes>,
+    /// Custom values, available from V15.
+    pub custom: CustomMetadata,
 }
 
 impl Metadata {
@@ -87,6 +110,48 @@ impl Metadata {
             .ok_or_else(|| MetadataError::PalletNotFound(name.to_string()))
     }
 
+    /// Returns the pallet at the given index.
+    pub fn pallet_by_index(&self, pallet_index: u8) -> Result<&PalletMetadata, MetadataError> {
+        self.pallets
+            .values()
+            .find(|pallet| pallet.index == pallet_index)
+            .ok_or(MetadataError::PalletIndexNotFound(pallet_index))
+    }
+
+    /// Returns the metadata for the call at the given pallet and call indices, with the
+    /// names and types of its arguments.
+    pub fn call_by_index(
+        &self,
+        pallet_index: u8,
+        call_index: u8,
+    ) -> Result<&CallMetadata, MetadataError> {
+        self.calls
+            .get(&(pallet_index, call_index))
+            .ok_or(MetadataError::CallIndexNotFound(pallet_index, call_index))
+    }
+
+    /// Returns the metadata for the call `pallet::call`, see `call_by_index`.
+    pub fn call_by_name(
+        &self,
+        pallet: &'static str,
+        call: &'static str,
+    ) -> Result<&CallMetadata, MetadataError> {
+        let pallet = self.pallet(pallet)?;
+        let call_index = pallet
+            .calls
+            .get(call)
+            .ok_or(MetadataError::CallNotFound(call))?;
+        self.call_by_index(pallet.index, *call_index)
+    }
+
+    /// Returns the metadata for all calls of a given pallet
+    pub fn calls(&self, pallet_index: u8) -> Vec<&CallMetadata> {
+        self.calls
+            .range((pallet_index, 0)..=(pallet_index, u8::MAX))
+            .map(|(_k, v)| v)
+            .collect()
+    }
+
     /// Returns the metadata for the event at the given pallet and event indices.
     pub fn event(
         &self,
@@ -143,6 +208,35 @@ impl Metadata {
         &self.metadata
     }
 
+    /// Returns the descriptions of the runtime apis. Empty before V15.
+    pub fn runtime_apis(&self) -> &[RuntimeApiMetadata] {
+        &self.apis
+    }
+
+    /// Returns the runtime api called `name`, e.g. `Core`.
+    pub fn runtime_api(&self, name: &str) -> Option<&RuntimeApiMetadata> {
+        self.apis.iter().find(|api| api.name == name)
+    }
+
+    /// Returns the type id and SCALE encoded value of the custom value called `name`.
+    pub fn custom_value(&self, name: &str) -> Option<(u32, &[u8])> {
+        self.custom
+            .map
+            .get(name)
+            .map(|custom| (custom.ty.id(), custom.value.as_slice()))
+    }
+
+    /// Identifiers of the signed extensions of the runtime, in the order they are encoded in
+    /// an extrinsic.
+    pub fn signed_extension_identifiers(&self) -> Vec<String> {
+        self.metadata
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|extension| extension.identifier.clone())
+            .collect()
+    }
+
     #[cfg(feature = "std")]
     pub fn pretty_format(metadata: &RuntimeMetadataPrefixed) -> Option<String> {
         let buf = Vec::new();
@@ -196,6 +290,24 @@ impl PalletMetadata {
     }
 }
 
+/// Address of a constant with a static Rust type, e.g. as generated by `ac-codegen`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StaticConstantAddress<V> {
+    pub pallet: &'static str,
+    pub constant: &'static str,
+    _marker: PhantomData<V>,
+}
+
+impl<V> StaticConstantAddress<V> {
+    pub fn new(pallet: &'static str, constant: &'static str) -> Self {
+        Self {
+            pallet,
+            constant,
+            _marker: PhantomData,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Encode, Decode)]
 pub struct EventMetadata {
     pub pallet: String,
@@ -252,18 +364,36 @@ pub enum InvalidMetadataError {
     MissingType(u32),
     /// Type was not variant/enum type.
     TypeDefNotVariant(u32),
+    /// The `UncheckedExtrinsic` type, which the V14 view of V15 metadata refers to, could not
+    /// be added to its type registry.
+    MissingExtrinsicType,
 }
 
 impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
     type Error = InvalidMetadataError;
 
     fn try_from(metadata: RuntimeMetadataPrefixed) -> Result<Self, Self::Error> {
-        if metadata.0 != META_RESERVED {
+        Metadata::try_from(VersionedMetadata::try_from(metadata)?)
+    }
+}
+
+impl TryFrom<VersionedMetadata> for Metadata {
+    type Error = InvalidMetadataError;
+
+    fn try_from(metadata: VersionedMetadata) -> Result<Self, Self::Error> {
+        if metadata.prefix() != META_RESERVED {
             return Err(InvalidMetadataError::InvalidPrefix);
         }
-        let metadata = match metadata.1 {
-            RuntimeMetadata::V14(meta) => meta,
-            _ => return Err(InvalidMetadataError::InvalidVersion),
+        let version = metadata.version();
+        let (metadata, apis, outer_enums, extrinsic_types, custom) = match metadata {
+            VersionedMetadata::V14(_, meta) => (meta, Vec::new(), None, None, Default::default()),
+            VersionedMetadata::V15(_, meta) => (
+                meta.to_v14()?,
+                meta.apis.clone(),
+                Some(meta.outer_enums.clone()),
+                Some(meta.extrinsic_types()),
+                meta.custom,
+            ),
         };
 
         let get_type_def_variant = |type_id: u32| {
@@ -317,6 +447,18 @@ impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
             })
             .collect::<Result<_, _>>()?;
 
+        let types = &metadata.types;
+        let pallet_calls = metadata
+            .pallets
+            .iter()
+            .filter_map(|pallet| {
+                pallet.calls.as_ref().map(|call| {
+                    let type_def_variant = get_type_def_variant(call.ty.id())?;
+                    Ok((pallet, type_def_variant))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
         let pallet_events = metadata
             .pallets
             .iter()
@@ -367,11 +509,27 @@ impl TryFrom<RuntimeMetadataPrefixed> for Metadata {
             })
             .collect();
 
+        let calls = pallet_calls
+            .iter()
+            .flat_map(|(pallet, type_def_variant)| {
+                type_def_variant.variants().iter().map(move |var| {
+                    let key = (pallet.index, var.index());
+                    (key, CallMetadata::new(&pallet.name, var, types))
+                })
+            })
+            .collect();
+
         Ok(Self {
             metadata,
             pallets,
             events,
             errors,
+            calls,
+            version,
+            apis,
+            outer_enums,
+            extrinsic_types,
+            custom,
         })
     }
 }
@@ -415,6 +573,28 @@ impl Metadata {
             .get_map_prefix(storage_prefix)
     }
 
+    /// Storage key of a static storage address, with the map keys hashed by the hashers of
+    /// the entry in this metadata.
+    pub fn static_storage_key<V>(
+        &self,
+        address: &StaticStorageAddress<V>,
+    ) -> Result<StorageKey, MetadataError> {
+        let entry = self.pallet(address.pallet)?.storage(address.entry)?;
+        match &entry.ty {
+            StorageEntryType::Plain(_) => address.key(&[]),
+            StorageEntryType::Map { hashers, .. } => address.key(hashers),
+        }
+    }
+
+    /// Decode the value of a static constant address from this metadata.
+    pub fn static_constant<V: Decode>(
+        &self,
+        address: &StaticConstantAddress<V>,
+    ) -> Result<V, MetadataError> {
+        let constant = self.pallet(address.pallet)?.constant(address.constant)?;
+        V::decode(&mut constant.value.as_slice()).map_err(MetadataError::ConstantValueError)
+    }
+
     pub fn storage_double_map_key<K: Encode, Q: Encode>(
         &self,
         storage_prefix: &'static str,
@@ -429,3 +609,94 @@ impl Metadata {
             .key(first, second))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use frame_metadata::{
+        PalletMetadata, PalletStorageMetadata, StorageEntryModifier, StorageHasher,
+    };
+    use scale_info::meta_type;
+
+    fn metadata() -> Metadata {
+        let entry = |name, ty| StorageEntryMetadata {
+            name,
+            modifier: StorageEntryModifier::Default,
+            ty,
+            default: vec![0; 16],
+            docs: vec![],
+        };
+        test_utils::metadata(vec![PalletMetadata {
+            storage: Some(PalletStorageMetadata {
+                prefix: "Balances",
+                entries: vec![
+                    entry(
+                        "Account",
+                        StorageEntryType::Map {
+                            hashers: vec![StorageHasher::Blake2_128Concat],
+                            key: meta_type::<[u8; 32]>(),
+                            value: meta_type::<u128>(),
+                        },
+                    ),
+                    entry(
+                        "TotalIssuance",
+                        StorageEntryType::Plain(meta_type::<u128>()),
+                    ),
+                ],
+            }),
+            constants: vec![PalletConstantMetadata {
+                name: "ExistentialDeposit",
+                ty: meta_type::<u128>(),
+                value: 500u128.encode(),
+                docs: vec![],
+            }],
+            ..test_utils::pallet("Balances", 5)
+        }])
+    }
+
+    #[test]
+    fn static_storage_key_hashes_the_keys_with_the_hashers_of_the_entry() {
+        let metadata = metadata();
+        let account =
+            StaticStorageAddress::<u128>::new("Balances", "Account", vec![[1u8; 32].encode()]);
+        assert_eq!(
+            metadata.static_storage_key(&account),
+            metadata.storage_map_key("Balances", "Account", [1u8; 32])
+        );
+        let issuance = StaticStorageAddress::<u128>::new("Balances", "TotalIssuance", vec![]);
+        assert_eq!(
+            metadata.static_storage_key(&issuance),
+            metadata.storage_value_key("Balances", "TotalIssuance")
+        );
+
+        let unkeyed = StaticStorageAddress::<u128>::new("Balances", "Account", vec![]);
+        assert_eq!(
+            metadata.static_storage_key(&unkeyed),
+            Err(MetadataError::StorageTypeError)
+        );
+        let missing = StaticStorageAddress::<u128>::new("Balances", "Locks", vec![]);
+        assert_eq!(
+            metadata.static_storage_key(&missing),
+            Err(MetadataError::StorageNotFound("Locks"))
+        );
+    }
+
+    #[test]
+    fn static_constant_decodes_the_value_of_the_metadata() {
+        let metadata = metadata();
+        let deposit = StaticConstantAddress::<u128>::new("Balances", "ExistentialDeposit");
+        assert_eq!(metadata.static_constant(&deposit), Ok(500));
+
+        let too_wide = StaticConstantAddress::<[u8; 32]>::new("Balances", "ExistentialDeposit");
+        assert!(matches!(
+            metadata.static_constant(&too_wide),
+            Err(MetadataError::ConstantValueError(_))
+        ));
+        let missing = StaticConstantAddress::<u128>::new("Balances", "MaxLocks");
+        assert_eq!(
+            metadata.static_constant(&missing),
+            Err(MetadataError::ConstantNotFound("MaxLocks"))
+        );
+    }
+}
@@ -76,6 +76,42 @@ impl<K: Encode, Q: Encode> StorageDoubleMap<K, Q> {
     }
 }
 
+/// Address of a storage entry with a static Rust value type, e.g. as generated by `ac-codegen`.
+///
+/// Holds the SCALE encoded map keys; they are hashed with the hashers of the entry in the live
+/// metadata, see `Metadata::static_storage_key`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StaticStorageAddress<V> {
+    pub pallet: &'static str,
+    pub entry: &'static str,
+    pub keys: Vec<Vec<u8>>,
+    _marker: PhantomData<V>,
+}
+
+impl<V> StaticStorageAddress<V> {
+    pub fn new(pallet: &'static str, entry: &'static str, keys: Vec<Vec<u8>>) -> Self {
+        Self {
+            pallet,
+            entry,
+            keys,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Storage key of the entry, with `keys` hashed by `hashers`.
+    pub(crate) fn key(&self, hashers: &[StorageHasher]) -> Result<StorageKey, MetadataError> {
+        if hashers.len() != self.keys.len() {
+            return Err(MetadataError::StorageTypeError);
+        }
+        let mut bytes = sp_core::twox_128(self.pallet.as_bytes()).to_vec();
+        bytes.extend(&sp_core::twox_128(self.entry.as_bytes())[..]);
+        for (key, hasher) in self.keys.iter().zip(hashers) {
+            bytes.extend(hash_bytes(key, hasher));
+        }
+        Ok(StorageKey(bytes))
+    }
+}
+
 /// trait to extract the storage based on the [`StorageEntryMetadata`].
 pub trait GetStorage {
     fn get_double_map<K: Encode, Q: Encode>(
@@ -179,25 +215,27 @@ impl GetStorage for StorageEntryMetadata<PortableForm> {
 
 /// generates the key's hash depending on the StorageHasher selected
 fn key_hash<K: Encode>(key: &K, hasher: &StorageHasher) -> Vec<u8> {
-    let encoded_key = key.encode();
+    hash_bytes(&key.encode(), hasher)
+}
+
+fn hash_bytes(encoded_key: &[u8], hasher: &StorageHasher) -> Vec<u8> {
     match hasher {
         StorageHasher::Identity => encoded_key.to_vec(),
-        StorageHasher::Blake2_128 => sp_core::blake2_128(&encoded_key).to_vec(),
+        StorageHasher::Blake2_128 => sp_core::blake2_128(encoded_key).to_vec(),
         StorageHasher::Blake2_128Concat => {
             // copied from substrate Blake2_128Concat::hash since StorageHasher is not public
-            let x: &[u8] = encoded_key.as_slice();
-            sp_core::blake2_128(x)
+            sp_core::blake2_128(encoded_key)
                 .iter()
-                .chain(x.iter())
+                .chain(encoded_key.iter())
                 .cloned()
                 .collect::<Vec<_>>()
         }
-        StorageHasher::Blake2_256 => sp_core::blake2_256(&encoded_key).to_vec(),
-        StorageHasher::Twox128 => sp_core::twox_128(&encoded_key).to_vec(),
-        StorageHasher::Twox256 => sp_core::twox_256(&encoded_key).to_vec(),
-        StorageHasher::Twox64Concat => sp_core::twox_64(&encoded_key)
+        StorageHasher::Blake2_256 => sp_core::blake2_256(encoded_key).to_vec(),
+        StorageHasher::Twox128 => sp_core::twox_128(encoded_key).to_vec(),
+        StorageHasher::Twox256 => sp_core::twox_256(encoded_key).to_vec(),
+        StorageHasher::Twox64Concat => sp_core::twox_64(encoded_key)
             .iter()
-            .chain(&encoded_key)
+            .chain(encoded_key)
             .cloned()
             .collect(),
     }
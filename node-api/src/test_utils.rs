@@ -0,0 +1,81 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Synthetic runtime metadata for tests, also available to other crates with the
+//! `test-utils` feature.
+//!
+//! This file is **not** from subxt.
+
+use crate::metadata::Metadata;
+use frame_metadata::{
+    ExtrinsicMetadata, PalletMetadata, RuntimeMetadataLastVersion, RuntimeMetadataPrefixed,
+};
+use scale_info::meta_type;
+use sp_std::convert::TryFrom;
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+/// A pallet without any items, to be filled in with the struct update syntax.
+pub fn pallet(name: &'static str, index: u8) -> PalletMetadata {
+    PalletMetadata {
+        name,
+        storage: None,
+        calls: None,
+        event: None,
+        constants: vec![],
+        error: None,
+        index,
+    }
+}
+
+/// V4 extrinsic metadata with a unit extrinsic type and no signed extensions.
+pub fn extrinsic() -> ExtrinsicMetadata {
+    ExtrinsicMetadata {
+        ty: meta_type::<()>(),
+        version: 4,
+        signed_extensions: vec![],
+    }
+}
+
+/// Runtime metadata with `pallets` and the extrinsic of `extrinsic()`.
+pub fn runtime_metadata(pallets: Vec<PalletMetadata>) -> RuntimeMetadataLastVersion {
+    runtime_metadata_with_extrinsic(pallets, extrinsic())
+}
+
+/// Runtime metadata with `pallets` and `extrinsic`.
+pub fn runtime_metadata_with_extrinsic(
+    pallets: Vec<PalletMetadata>,
+    extrinsic: ExtrinsicMetadata,
+) -> RuntimeMetadataLastVersion {
+    RuntimeMetadataLastVersion::new(pallets, extrinsic, meta_type::<()>())
+}
+
+/// `runtime_metadata` as returned by `state_getMetadata`.
+pub fn prefixed_metadata(pallets: Vec<PalletMetadata>) -> RuntimeMetadataPrefixed {
+    RuntimeMetadataPrefixed::from(runtime_metadata(pallets))
+}
+
+/// `Metadata` of `runtime_metadata`.
+pub fn metadata(pallets: Vec<PalletMetadata>) -> Metadata {
+    Metadata::try_from(prefixed_metadata(pallets)).unwrap()
+}
+
+/// `Metadata` of `runtime_metadata_with_extrinsic`.
+pub fn metadata_with_extrinsic(
+    pallets: Vec<PalletMetadata>,
+    extrinsic: ExtrinsicMetadata,
+) -> Metadata {
+    let metadata = runtime_metadata_with_extrinsic(pallets, extrinsic);
+    Metadata::try_from(RuntimeMetadataPrefixed::from(metadata)).unwrap()
+}
@@ -0,0 +1,551 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! V15 runtime metadata, as returned by the `Metadata_metadata_at_version` runtime api.
+//!
+//! The `frame-metadata` version we depend on only knows about V14, hence the V15 layout is
+//! mirrored here. The pallet items are the same as in V14 and reuse its types.
+//!
+//! This file is **not** from subxt.
+
+use crate::metadata::InvalidMetadataError;
+use codec::{Compact, Decode, Encode, Error as CodecError, Input, Output};
+use frame_metadata::{
+    ExtrinsicMetadata, PalletCallMetadata, PalletConstantMetadata, PalletErrorMetadata,
+    PalletEventMetadata, PalletMetadata, PalletStorageMetadata, RuntimeMetadata,
+    RuntimeMetadataLastVersion, RuntimeMetadataPrefixed, SignedExtensionMetadata,
+};
+use scale_info::{
+    form::{Form, PortableForm},
+    PortableRegistry, Type, TypeDef, TypeDefPrimitive,
+};
+use sp_std::collections::btree_map::BTreeMap;
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec, vec::Vec};
+
+/// Reference to a type of the metadata type registry.
+pub type TypeRef = <PortableForm as Form>::Type;
+
+/// Metadata versions this crate can handle, oldest first.
+pub const SUPPORTED_METADATA_VERSIONS: [u32; 2] = [14, 15];
+
+/// Path of the extrinsic type V14 refers to, generic over the types of the extrinsic parts.
+const UNCHECKED_EXTRINSIC_PATH: [&str; 4] = [
+    "sp_runtime",
+    "generic",
+    "unchecked_extrinsic",
+    "UncheckedExtrinsic",
+];
+
+/// V15 runtime metadata.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RuntimeMetadataV15 {
+    pub types: PortableRegistry,
+    pub pallets: Vec<PalletMetadataV15>,
+    pub extrinsic: ExtrinsicMetadataV15,
+    /// Type of the `Runtime`.
+    pub ty: TypeRef,
+    /// Descriptions of the runtime apis.
+    pub apis: Vec<RuntimeApiMetadata>,
+    pub outer_enums: OuterEnums,
+    /// Custom values of the chain, not interpreted by the runtime.
+    pub custom: CustomMetadata,
+}
+
+/// Pallet metadata of V15, which adds the pallet docs to V14.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PalletMetadataV15 {
+    pub name: String,
+    pub storage: Option<PalletStorageMetadata<PortableForm>>,
+    pub calls: Option<PalletCallMetadata<PortableForm>>,
+    pub event: Option<PalletEventMetadata<PortableForm>>,
+    pub constants: Vec<PalletConstantMetadata<PortableForm>>,
+    pub error: Option<PalletErrorMetadata<PortableForm>>,
+    pub index: u8,
+    pub docs: Vec<String>,
+}
+
+/// Extrinsic metadata of V15, which names the types of the extrinsic parts instead of the
+/// `UncheckedExtrinsic` type.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ExtrinsicMetadataV15 {
+    pub version: u8,
+    pub address_ty: TypeRef,
+    pub call_ty: TypeRef,
+    pub signature_ty: TypeRef,
+    pub extra_ty: TypeRef,
+    pub signed_extensions: Vec<SignedExtensionMetadata<PortableForm>>,
+}
+
+/// Types of the parts of an extrinsic.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ExtrinsicTypes {
+    pub address: u32,
+    pub call: u32,
+    pub signature: u32,
+    pub extra: u32,
+}
+
+/// A runtime api, e.g. `Core`, and its methods.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RuntimeApiMetadata {
+    pub name: String,
+    pub methods: Vec<RuntimeApiMethodMetadata>,
+    pub docs: Vec<String>,
+}
+
+impl RuntimeApiMetadata {
+    /// Returns the method called `name`.
+    pub fn method(&self, name: &str) -> Option<&RuntimeApiMethodMetadata> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
+
+/// A method of a runtime api. It is called as `<api>_<method>`, e.g. `Core_version`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RuntimeApiMethodMetadata {
+    pub name: String,
+    pub inputs: Vec<RuntimeApiMethodParamMetadata>,
+    pub output: TypeRef,
+    pub docs: Vec<String>,
+}
+
+/// A parameter of a runtime api method.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct RuntimeApiMethodParamMetadata {
+    pub name: String,
+    pub ty: TypeRef,
+}
+
+/// The outer enums of the runtime, aggregating the enums of all pallets.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct OuterEnums {
+    /// `RuntimeCall`.
+    pub call_enum_ty: TypeRef,
+    /// `RuntimeEvent`.
+    pub event_enum_ty: TypeRef,
+    /// `RuntimeError`.
+    pub error_enum_ty: TypeRef,
+}
+
+/// Custom values of the metadata, by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Encode, Decode)]
+pub struct CustomMetadata {
+    pub map: BTreeMap<String, CustomValueMetadata>,
+}
+
+/// A SCALE encoded custom value and its type.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct CustomValueMetadata {
+    pub ty: TypeRef,
+    pub value: Vec<u8>,
+}
+
+/// Prefixed runtime metadata of a version we support.
+///
+/// Encodes like `RuntimeMetadataPrefixed`: the magic number followed by the version and the
+/// metadata.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VersionedMetadata {
+    V14(u32, RuntimeMetadataLastVersion),
+    V15(u32, RuntimeMetadataV15),
+}
+
+impl VersionedMetadata {
+    /// The metadata version.
+    pub fn version(&self) -> u32 {
+        match self {
+            VersionedMetadata::V14(..) => 14,
+            VersionedMetadata::V15(..) => 15,
+        }
+    }
+
+    /// The magic number prefix.
+    pub fn prefix(&self) -> u32 {
+        match self {
+            VersionedMetadata::V14(prefix, _) | VersionedMetadata::V15(prefix, _) => *prefix,
+        }
+    }
+}
+
+impl Encode for VersionedMetadata {
+    fn encode_to<O: Output + ?Sized>(&self, dest: &mut O) {
+        self.prefix().encode_to(dest);
+        (self.version() as u8).encode_to(dest);
+        match self {
+            VersionedMetadata::V14(_, metadata) => metadata.encode_to(dest),
+            VersionedMetadata::V15(_, metadata) => metadata.encode_to(dest),
+        }
+    }
+}
+
+impl Decode for VersionedMetadata {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let prefix = u32::decode(input)?;
+        match u8::decode(input)? {
+            14 => Ok(VersionedMetadata::V14(
+                prefix,
+                RuntimeMetadataLastVersion::decode(input)?,
+            )),
+            15 => Ok(VersionedMetadata::V15(
+                prefix,
+                RuntimeMetadataV15::decode(input)?,
+            )),
+            _ => Err("Unsupported metadata version".into()),
+        }
+    }
+}
+
+impl TryFrom<RuntimeMetadataPrefixed> for VersionedMetadata {
+    type Error = InvalidMetadataError;
+
+    fn try_from(metadata: RuntimeMetadataPrefixed) -> Result<Self, Self::Error> {
+        match metadata.1 {
+            RuntimeMetadata::V14(v14) => Ok(VersionedMetadata::V14(metadata.0, v14)),
+            _ => Err(InvalidMetadataError::InvalidVersion),
+        }
+    }
+}
+
+/// SCALE layout of a `scale_info::PortableType`, to add types to a `PortableRegistry`, which
+/// scale-info only supports while building one.
+#[derive(Encode)]
+struct PortableTypeLayout {
+    #[codec(compact)]
+    id: u32,
+    path: Vec<&'static str>,
+    /// Names and types of the type parameters.
+    type_params: Vec<(&'static str, Option<Compact<u32>>)>,
+    type_def: TypeDefLayout,
+    docs: Vec<&'static str>,
+}
+
+/// SCALE layout of the `scale_info::TypeDef` variants added by `to_v14`.
+#[derive(Encode)]
+enum TypeDefLayout {
+    /// Fields with their names, types, type names and docs.
+    #[codec(index = 0)]
+    Composite(
+        Vec<(
+            Option<&'static str>,
+            Compact<u32>,
+            Option<&'static str>,
+            Vec<&'static str>,
+        )>,
+    ),
+    #[codec(index = 2)]
+    Sequence(Compact<u32>),
+    #[codec(index = 5)]
+    Primitive(TypeDefPrimitive),
+}
+
+impl RuntimeMetadataV15 {
+    /// The V14 view of this metadata, which the rest of the crate works with.
+    ///
+    /// V14 references the `UncheckedExtrinsic` type, which V15 replaced with the types of its
+    /// parts. The registry type of that path with these parts as type parameters is used, or
+    /// added to the registry if the runtime doesn't use it anywhere else.
+    pub fn to_v14(&self) -> Result<RuntimeMetadataLastVersion, InvalidMetadataError> {
+        let pallets = self
+            .pallets
+            .iter()
+            .map(|pallet| PalletMetadata {
+                name: pallet.name.clone(),
+                storage: pallet.storage.clone(),
+                calls: pallet.calls.clone(),
+                event: pallet.event.clone(),
+                constants: pallet.constants.clone(),
+                error: pallet.error.clone(),
+                index: pallet.index,
+            })
+            .collect();
+        let (types, extrinsic_ty) = self.types_with_unchecked_extrinsic()?;
+        Ok(RuntimeMetadataLastVersion {
+            types,
+            pallets,
+            extrinsic: ExtrinsicMetadata {
+                ty: extrinsic_ty.into(),
+                version: self.extrinsic.version,
+                signed_extensions: self.extrinsic.signed_extensions.clone(),
+            },
+            ty: self.ty,
+        })
+    }
+
+    /// The type registry including the `UncheckedExtrinsic` type of the extrinsic parts, and
+    /// the id of that type.
+    fn types_with_unchecked_extrinsic(
+        &self,
+    ) -> Result<(PortableRegistry, u32), InvalidMetadataError> {
+        let parts = self.extrinsic_types();
+        let params = [parts.address, parts.call, parts.signature, parts.extra];
+        let unchecked_extrinsic = self.find_type(|ty| {
+            ty.path().segments() == UNCHECKED_EXTRINSIC_PATH
+                && ty
+                    .type_params()
+                    .iter()
+                    .map(|param| param.ty().map(|ty| ty.id()))
+                    .eq(params.iter().map(|&id| Some(id)))
+        });
+        if let Some(id) = unchecked_extrinsic {
+            return Ok((self.types.clone(), id));
+        }
+
+        // Like the one of `sp_runtime`, the added type wraps the encoded extrinsic.
+        let mut added = Vec::new();
+        let mut add = |type_def, path: &[&'static str], type_params| {
+            let id = (self.types.types().len() + added.len()) as u32;
+            added.push(PortableTypeLayout {
+                id,
+                path: path.to_vec(),
+                type_params,
+                type_def,
+                docs: Vec::new(),
+            });
+            id
+        };
+        let u8_ty = self
+            .find_type(|ty| matches!(ty.type_def(), TypeDef::Primitive(TypeDefPrimitive::U8)))
+            .unwrap_or_else(|| {
+                let u8_def = TypeDefLayout::Primitive(TypeDefPrimitive::U8);
+                add(u8_def, &[], Vec::new())
+            });
+        let bytes_ty = self
+            .find_type(|ty| match ty.type_def() {
+                TypeDef::Sequence(sequence) => sequence.type_param().id() == u8_ty,
+                _ => false,
+            })
+            .unwrap_or_else(|| add(TypeDefLayout::Sequence(u8_ty.into()), &[], Vec::new()));
+        let type_params = ["Address", "Call", "Signature", "Extra"]
+            .into_iter()
+            .zip(params)
+            .map(|(name, id)| (name, Some(Compact(id))))
+            .collect();
+        let field = (None, bytes_ty.into(), None, Vec::new());
+        let unchecked_extrinsic = add(
+            TypeDefLayout::Composite(vec![field]),
+            &UNCHECKED_EXTRINSIC_PATH,
+            type_params,
+        );
+
+        let mut encoded = Compact((self.types.types().len() + added.len()) as u32).encode();
+        for ty in self.types.types() {
+            ty.encode_to(&mut encoded);
+        }
+        for ty in added {
+            ty.encode_to(&mut encoded);
+        }
+        let types = PortableRegistry::decode(&mut encoded.as_slice())
+            .map_err(|_| InvalidMetadataError::MissingExtrinsicType)?;
+        Ok((types, unchecked_extrinsic))
+    }
+
+    /// Id of the first registry type `matches` holds for.
+    fn find_type(&self, matches: impl Fn(&Type<PortableForm>) -> bool) -> Option<u32> {
+        self.types
+            .types()
+            .iter()
+            .find(|ty| matches(ty.ty()))
+            .map(|ty| ty.id())
+    }
+
+    /// The types of the extrinsic parts.
+    pub fn extrinsic_types(&self) -> ExtrinsicTypes {
+        ExtrinsicTypes {
+            address: self.extrinsic.address_ty.id(),
+            call: self.extrinsic.call_ty.id(),
+            signature: self.extrinsic.signature_ty.id(),
+            extra: self.extrinsic.extra_ty.id(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Metadata;
+    use frame_metadata::META_RESERVED;
+    use scale_info::{
+        build::Fields, meta_type, IntoPortable, Path, Registry, TypeInfo, TypeParameter,
+    };
+    use sp_std::convert::TryFrom;
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Event {
+        Remarked(u32),
+    }
+
+    /// Same ident as the extrinsic type, but a different path.
+    #[derive(TypeInfo)]
+    struct UncheckedExtrinsic;
+
+    /// The `UncheckedExtrinsic` of `sp_runtime` with the extrinsic parts of `metadata_v15_with`.
+    struct RuntimeUncheckedExtrinsic;
+
+    impl TypeInfo for RuntimeUncheckedExtrinsic {
+        type Identity = Self;
+
+        fn type_info() -> Type {
+            Type::builder()
+                .path(Path::from_segments(UNCHECKED_EXTRINSIC_PATH).unwrap())
+                .type_params(vec![
+                    TypeParameter::new("Address", Some(meta_type::<u32>())),
+                    TypeParameter::new("Call", Some(meta_type::<()>())),
+                    TypeParameter::new("Signature", Some(meta_type::<u32>())),
+                    TypeParameter::new("Extra", Some(meta_type::<()>())),
+                ])
+                .composite(Fields::unnamed().field(|f| f.ty::<Vec<u8>>()))
+        }
+    }
+
+    fn metadata_v15() -> RuntimeMetadataV15 {
+        metadata_v15_with(true)
+    }
+
+    /// V15 metadata, with the `UncheckedExtrinsic` type of `sp_runtime` in the registry if
+    /// `unchecked_extrinsic`.
+    fn metadata_v15_with(unchecked_extrinsic: bool) -> RuntimeMetadataV15 {
+        let mut registry = Registry::new();
+        registry.register_type(&meta_type::<UncheckedExtrinsic>());
+        if unchecked_extrinsic {
+            registry.register_type(&meta_type::<RuntimeUncheckedExtrinsic>());
+        }
+        let event = PalletEventMetadata {
+            ty: meta_type::<Event>(),
+        }
+        .into_portable(&mut registry);
+        let u32_ty = registry.register_type(&meta_type::<u32>());
+        let version_ty = registry.register_type(&meta_type::<(u32, u32)>());
+        let unit_ty = registry.register_type(&meta_type::<()>());
+        RuntimeMetadataV15 {
+            types: registry.into(),
+            pallets: vec![PalletMetadataV15 {
+                name: "System".into(),
+                storage: None,
+                calls: None,
+                event: Some(event),
+                constants: vec![],
+                error: None,
+                index: 0,
+                docs: vec!["The system pallet.".into()],
+            }],
+            extrinsic: ExtrinsicMetadataV15 {
+                version: 4,
+                address_ty: u32_ty,
+                call_ty: unit_ty,
+                signature_ty: u32_ty,
+                extra_ty: unit_ty,
+                signed_extensions: vec![],
+            },
+            ty: unit_ty,
+            apis: vec![RuntimeApiMetadata {
+                name: "Core".into(),
+                methods: vec![RuntimeApiMethodMetadata {
+                    name: "version".into(),
+                    inputs: vec![RuntimeApiMethodParamMetadata {
+                        name: "at".into(),
+                        ty: u32_ty,
+                    }],
+                    output: version_ty,
+                    docs: vec![],
+                }],
+                docs: vec![],
+            }],
+            outer_enums: OuterEnums {
+                call_enum_ty: unit_ty,
+                event_enum_ty: unit_ty,
+                error_enum_ty: unit_ty,
+            },
+            custom: CustomMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn decodes_v15_and_converts_to_metadata() {
+        let encoded = VersionedMetadata::V15(META_RESERVED, metadata_v15()).encode();
+        // Magic number followed by the version byte, like `RuntimeMetadataPrefixed`.
+        assert_eq!(encoded[4], 15);
+
+        let versioned = VersionedMetadata::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(versioned.version(), 15);
+
+        let metadata = Metadata::try_from(versioned).unwrap();
+        assert_eq!(metadata.version, 15);
+        assert_eq!(metadata.event(0, 0).unwrap().event(), "Remarked");
+        let version = metadata.runtime_api("Core").unwrap().method("version");
+        assert_eq!(version.unwrap().inputs[0].name, "at");
+        assert!(metadata.outer_enums.is_some());
+        assert!(metadata.extrinsic_types.is_some());
+    }
+
+    #[test]
+    fn decodes_v14_prefixed_metadata() {
+        let metadata = metadata_v15();
+        let v14 = metadata.to_v14().unwrap();
+        assert_eq!(v14.types, metadata.types);
+        let extrinsic_ty = v14.types.resolve(v14.extrinsic.ty.id()).unwrap();
+        assert_eq!(extrinsic_ty.path().segments(), UNCHECKED_EXTRINSIC_PATH);
+        let encoded = RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V14(v14)).encode();
+
+        let versioned = VersionedMetadata::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(versioned.version(), 14);
+        assert_eq!(versioned.encode(), encoded);
+
+        let metadata = Metadata::try_from(versioned).unwrap();
+        assert_eq!(metadata.version, 14);
+        assert!(metadata.runtime_apis().is_empty());
+        assert!(metadata.outer_enums.is_none());
+    }
+
+    #[test]
+    fn adds_the_unchecked_extrinsic_type_for_the_v14_view() {
+        let metadata = metadata_v15_with(false);
+        let v14 = metadata.to_v14().unwrap();
+
+        let types = metadata.types.types();
+        assert_eq!(&v14.types.types()[..types.len()], types);
+        let extrinsic_ty = v14.types.resolve(v14.extrinsic.ty.id()).unwrap();
+        assert_eq!(extrinsic_ty.path().segments(), UNCHECKED_EXTRINSIC_PATH);
+        let params: Vec<_> = extrinsic_ty
+            .type_params()
+            .iter()
+            .map(|param| (param.name().as_str(), param.ty().unwrap().id()))
+            .collect();
+        let parts = metadata.extrinsic_types();
+        assert_eq!(
+            params,
+            vec![
+                ("Address", parts.address),
+                ("Call", parts.call),
+                ("Signature", parts.signature),
+                ("Extra", parts.extra)
+            ]
+        );
+        let bytes_ty = match extrinsic_ty.type_def() {
+            TypeDef::Composite(composite) => composite.fields()[0].ty().id(),
+            def => panic!("Unexpected type {:?}", def),
+        };
+        let u8_ty = match v14.types.resolve(bytes_ty).unwrap().type_def() {
+            TypeDef::Sequence(sequence) => sequence.type_param().id(),
+            def => panic!("Unexpected type {:?}", def),
+        };
+        assert_eq!(
+            v14.types.resolve(u8_ty).unwrap().type_def(),
+            &TypeDef::Primitive(TypeDefPrimitive::U8)
+        );
+
+        assert!(Metadata::try_from(VersionedMetadata::V15(META_RESERVED, metadata)).is_ok());
+    }
+}
@@ -0,0 +1,533 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Self-describing values decoded with the help of the metadata type registry.
+//!
+//! This file is **not** from subxt.
+
+use crate::{
+    error::Error,
+    metadata::{Metadata, MetadataError},
+};
+use codec::{Compact, Decode, Encode, Input, Output};
+use scale_info::{form::PortableForm, Field, TypeDef, TypeDefPrimitive};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+/// A value decoded from SCALE bytes, described by a type of the metadata registry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// A struct, tuple or tuple struct.
+    Composite(Composite),
+    /// An enum variant.
+    Variant(VariantValue),
+    /// A vector or a fixed size array.
+    Sequence(Vec<Value>),
+    /// A bit sequence, with the bits in the order defined by the type.
+    BitSequence(Vec<bool>),
+    /// A primitive value.
+    Primitive(Primitive),
+}
+
+/// The fields of a composite type or an enum variant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Composite {
+    /// Fields of a struct or struct-like variant.
+    Named(Vec<(String, Value)>),
+    /// Fields of a tuple, tuple struct or tuple-like variant.
+    Unnamed(Vec<Value>),
+}
+
+/// An enum variant with its fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariantValue {
+    /// Name of the variant.
+    pub name: String,
+    /// Index of the variant.
+    pub index: u8,
+    /// Fields of the variant.
+    pub fields: Composite,
+}
+
+/// A primitive value. Unsigned and signed integers are widened to 128 bit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Primitive {
+    Bool(bool),
+    Char(char),
+    Str(String),
+    U128(u128),
+    I128(i128),
+    /// Little endian bytes of an unsigned 256 bit integer.
+    U256([u8; 32]),
+    /// Little endian bytes of a signed 256 bit integer.
+    I256([u8; 32]),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Ord, PartialOrd)]
+pub enum ValueDecodingError {
+    /// Enum variant index is not part of the type.
+    VariantNotFound(u32, u8),
+    /// Compact encoding is only defined for unsigned integers and wrappers of them.
+    InvalidCompactType(u32),
+    /// Not a valid unicode scalar value.
+    InvalidChar(u32),
+    /// Bit store type must be an unsigned integer up to 64 bit.
+    InvalidBitStoreType(u32),
+    /// Bit order type must be either `Lsb0` or `Msb0`.
+    InvalidBitOrderType(u32),
+}
+
+impl Composite {
+    /// Get the field values, regardless of whether they are named.
+    pub fn values(&self) -> Vec<&Value> {
+        match self {
+            Composite::Named(fields) => fields.iter().map(|(_, value)| value).collect(),
+            Composite::Unnamed(fields) => fields.iter().collect(),
+        }
+    }
+
+    /// Get a named field by its name.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match self {
+            Composite::Named(fields) => fields
+                .iter()
+                .find(|(field_name, _)| field_name == name)
+                .map(|(_, value)| value),
+            Composite::Unnamed(_) => None,
+        }
+    }
+
+    /// Number of fields.
+    pub fn len(&self) -> usize {
+        match self {
+            Composite::Named(fields) => fields.len(),
+            Composite::Unnamed(fields) => fields.len(),
+        }
+    }
+
+    /// True if there are no fields.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Value {
+    /// If this is a sequence of `u8` values, possibly wrapped in single field composites
+    /// (e.g. `AccountId32`), return the bytes.
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            Value::Sequence(values) => values
+                .iter()
+                .map(|value| match value {
+                    Value::Primitive(Primitive::U128(byte)) => u8::try_from(*byte).ok(),
+                    _ => None,
+                })
+                .collect(),
+            Value::Composite(composite) if composite.len() == 1 => composite.values()[0].as_bytes(),
+            _ => None,
+        }
+    }
+}
+
+/// A bit sequence with a static store type, e.g. as generated by `ac-codegen`. Holds the store
+/// elements as encoded; interpreting them in the bit order of the type is left to the user.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RawBitSequence<Store> {
+    /// Number of bits.
+    pub bits: u32,
+    /// The elements storing the bits.
+    pub store: Vec<Store>,
+}
+
+impl<Store: Encode> Encode for RawBitSequence<Store> {
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        Compact(self.bits).encode_to(dest);
+        for element in self.store.iter() {
+            element.encode_to(dest);
+        }
+    }
+}
+
+impl<Store: Decode> Decode for RawBitSequence<Store> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let bits = <Compact<u32>>::decode(input)?.0;
+        let store_bits = (core::mem::size_of::<Store>() * 8) as u32;
+        if store_bits == 0 {
+            return Err("bit store type must not be zero sized".into());
+        }
+        let store = (0..bits / store_bits + u32::from(bits % store_bits != 0))
+            .map(|_| Store::decode(input))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { bits, store })
+    }
+}
+
+/// Values serialize into plain JSON-like data: named fields into maps, unnamed fields, sequences
+/// and bit sequences into arrays, variants into their name or a single entry map from their
+/// name to their fields, and 256 bit integers into big endian hex strings.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Composite(composite) => composite.serialize(serializer),
+            Value::Variant(variant) => variant.serialize(serializer),
+            Value::Sequence(values) => serializer.collect_seq(values),
+            Value::BitSequence(bits) => serializer.collect_seq(bits),
+            Value::Primitive(primitive) => primitive.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Composite {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Composite::Named(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (name, value) in fields {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            }
+            Composite::Unnamed(values) => serializer.collect_seq(values),
+        }
+    }
+}
+
+impl Serialize for VariantValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.fields.is_empty() {
+            return serializer.serialize_str(&self.name);
+        }
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(&self.name, &self.fields)?;
+        map.end()
+    }
+}
+
+impl Serialize for Primitive {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Primitive::Bool(b) => serializer.serialize_bool(*b),
+            Primitive::Char(c) => serializer.serialize_char(*c),
+            Primitive::Str(s) => serializer.serialize_str(s),
+            Primitive::U128(n) => serializer.serialize_u128(*n),
+            Primitive::I128(n) => serializer.serialize_i128(*n),
+            Primitive::U256(le_bytes) | Primitive::I256(le_bytes) => {
+                let mut be_bytes = *le_bytes;
+                be_bytes.reverse();
+                serializer.serialize_str(&format!("0x{}", hex::encode(be_bytes)))
+            }
+        }
+    }
+}
+
+impl Metadata {
+    /// Decode a value of type `type_id` from `input`, consuming exactly the bytes of the value.
+    pub fn decode_value(&self, type_id: u32, input: &mut &[u8]) -> Result<Value, Error> {
+        let ty = self
+            .resolve_type(type_id)
+            .ok_or(MetadataError::TypeNotFound(type_id))?;
+
+        match ty.type_def() {
+            TypeDef::Composite(composite) => self
+                .decode_fields(composite.fields(), input)
+                .map(Value::Composite),
+            TypeDef::Variant(variant) => {
+                let index = u8::decode(input)?;
+                let variant = variant
+                    .variants()
+                    .iter()
+                    .find(|v| v.index() == index)
+                    .ok_or(ValueDecodingError::VariantNotFound(type_id, index))?;
+                let fields = self.decode_fields(variant.fields(), input)?;
+                Ok(Value::Variant(VariantValue {
+                    name: variant.name().to_string(),
+                    index,
+                    fields,
+                }))
+            }
+            TypeDef::Sequence(seq) => {
+                let len = <Compact<u32>>::decode(input)?;
+                (0..len.0)
+                    .map(|_| self.decode_value(seq.type_param().id(), input))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Sequence)
+            }
+            TypeDef::Array(arr) => (0..arr.len())
+                .map(|_| self.decode_value(arr.type_param().id(), input))
+                .collect::<Result<_, _>>()
+                .map(Value::Sequence),
+            TypeDef::Tuple(tuple) => tuple
+                .fields()
+                .iter()
+                .map(|field| self.decode_value(field.id(), input))
+                .collect::<Result<_, _>>()
+                .map(|values| Value::Composite(Composite::Unnamed(values))),
+            TypeDef::Primitive(primitive) => {
+                decode_primitive(primitive, input).map(Value::Primitive)
+            }
+            TypeDef::Compact(compact) => self.decode_compact(compact.type_param().id(), input),
+            TypeDef::BitSequence(bit_seq) => self
+                .decode_bit_sequence(
+                    bit_seq.bit_store_type().id(),
+                    bit_seq.bit_order_type().id(),
+                    input,
+                )
+                .map(Value::BitSequence),
+        }
+    }
+
+    pub(crate) fn decode_fields(
+        &self,
+        fields: &[Field<PortableForm>],
+        input: &mut &[u8],
+    ) -> Result<Composite, Error> {
+        let is_named = fields.iter().any(|field| field.name().is_some());
+        if is_named {
+            fields
+                .iter()
+                .map(|field| {
+                    let name = field.name().cloned().unwrap_or_default();
+                    self.decode_value(field.ty().id(), input)
+                        .map(|value| (name, value))
+                })
+                .collect::<Result<_, _>>()
+                .map(Composite::Named)
+        } else {
+            fields
+                .iter()
+                .map(|field| self.decode_value(field.ty().id(), input))
+                .collect::<Result<_, _>>()
+                .map(Composite::Unnamed)
+        }
+    }
+
+    /// Decode a compact value of the inner type `type_id`. Compact encoding is supported for
+    /// unsigned integers and for composites wrapping a single compact-able field.
+    fn decode_compact(&self, type_id: u32, input: &mut &[u8]) -> Result<Value, Error> {
+        let ty = self
+            .resolve_type(type_id)
+            .ok_or(MetadataError::TypeNotFound(type_id))?;
+
+        match ty.type_def() {
+            TypeDef::Primitive(primitive) => {
+                let value = match primitive {
+                    TypeDefPrimitive::U8 => <Compact<u8>>::decode(input)?.0 as u128,
+                    TypeDefPrimitive::U16 => <Compact<u16>>::decode(input)?.0 as u128,
+                    TypeDefPrimitive::U32 => <Compact<u32>>::decode(input)?.0 as u128,
+                    TypeDefPrimitive::U64 => <Compact<u64>>::decode(input)?.0 as u128,
+                    TypeDefPrimitive::U128 => <Compact<u128>>::decode(input)?.0,
+                    _ => return Err(ValueDecodingError::InvalidCompactType(type_id).into()),
+                };
+                Ok(Value::Primitive(Primitive::U128(value)))
+            }
+            TypeDef::Composite(composite) => match composite.fields() {
+                [field] => {
+                    let inner = self.decode_compact(field.ty().id(), input)?;
+                    let fields = match field.name() {
+                        Some(name) => Composite::Named(vec![(name.clone(), inner)]),
+                        None => Composite::Unnamed(vec![inner]),
+                    };
+                    Ok(Value::Composite(fields))
+                }
+                _ => Err(ValueDecodingError::InvalidCompactType(type_id).into()),
+            },
+            TypeDef::Tuple(tuple) => match tuple.fields() {
+                [field] => self
+                    .decode_compact(field.id(), input)
+                    .map(|inner| Value::Composite(Composite::Unnamed(vec![inner]))),
+                _ => Err(ValueDecodingError::InvalidCompactType(type_id).into()),
+            },
+            _ => Err(ValueDecodingError::InvalidCompactType(type_id).into()),
+        }
+    }
+
+    /// Decode a `BitVec<Store, Order>`: the number of bits as `Compact<u32>` followed by
+    /// the store elements needed to hold these bits.
+    fn decode_bit_sequence(
+        &self,
+        store_type_id: u32,
+        order_type_id: u32,
+        input: &mut &[u8],
+    ) -> Result<Vec<bool>, Error> {
+        let store_bits = self.bit_store_width(store_type_id)?;
+        let is_lsb0 = self.bit_order_is_lsb0(order_type_id)?;
+
+        let bits = <Compact<u32>>::decode(input)?.0 as usize;
+        let elements = (bits + store_bits - 1) / store_bits;
+        // The bit count comes from the input, check it before allocating for it.
+        if elements * (store_bits / 8) > input.len() {
+            return Err(codec::Error::from("Not enough data to fill the bit sequence").into());
+        }
+
+        let mut decoded = Vec::with_capacity(bits);
+        for _ in 0..elements {
+            let element = match store_bits {
+                8 => u8::decode(input)? as u64,
+                16 => u16::decode(input)? as u64,
+                32 => u32::decode(input)? as u64,
+                _ => u64::decode(input)?,
+            };
+            let remaining = (bits - decoded.len()).min(store_bits);
+            for i in 0..remaining {
+                let shift = if is_lsb0 { i } else { store_bits - 1 - i };
+                decoded.push((element >> shift) & 1 == 1);
+            }
+        }
+        Ok(decoded)
+    }
+
+    pub(crate) fn bit_store_width(&self, store_type_id: u32) -> Result<usize, Error> {
+        let store = self
+            .resolve_type(store_type_id)
+            .ok_or(MetadataError::TypeNotFound(store_type_id))?;
+        match store.type_def() {
+            TypeDef::Primitive(TypeDefPrimitive::U8) => Ok(8),
+            TypeDef::Primitive(TypeDefPrimitive::U16) => Ok(16),
+            TypeDef::Primitive(TypeDefPrimitive::U32) => Ok(32),
+            TypeDef::Primitive(TypeDefPrimitive::U64) => Ok(64),
+            _ => Err(ValueDecodingError::InvalidBitStoreType(store_type_id).into()),
+        }
+    }
+
+    pub(crate) fn bit_order_is_lsb0(&self, order_type_id: u32) -> Result<bool, Error> {
+        let order = self
+            .resolve_type(order_type_id)
+            .ok_or(MetadataError::TypeNotFound(order_type_id))?;
+        match order.path().ident().as_deref() {
+            Some("Lsb0") => Ok(true),
+            Some("Msb0") => Ok(false),
+            _ => Err(ValueDecodingError::InvalidBitOrderType(order_type_id).into()),
+        }
+    }
+}
+
+fn decode_primitive(primitive: &TypeDefPrimitive, input: &mut &[u8]) -> Result<Primitive, Error> {
+    let value = match primitive {
+        TypeDefPrimitive::Bool => Primitive::Bool(bool::decode(input)?),
+        TypeDefPrimitive::Char => {
+            let code = u32::decode(input)?;
+            Primitive::Char(char::from_u32(code).ok_or(ValueDecodingError::InvalidChar(code))?)
+        }
+        TypeDefPrimitive::Str => Primitive::Str(String::decode(input)?),
+        TypeDefPrimitive::U8 => Primitive::U128(u8::decode(input)? as u128),
+        TypeDefPrimitive::U16 => Primitive::U128(u16::decode(input)? as u128),
+        TypeDefPrimitive::U32 => Primitive::U128(u32::decode(input)? as u128),
+        TypeDefPrimitive::U64 => Primitive::U128(u64::decode(input)? as u128),
+        TypeDefPrimitive::U128 => Primitive::U128(u128::decode(input)?),
+        TypeDefPrimitive::U256 => Primitive::U256(<[u8; 32]>::decode(input)?),
+        TypeDefPrimitive::I8 => Primitive::I128(i8::decode(input)? as i128),
+        TypeDefPrimitive::I16 => Primitive::I128(i16::decode(input)? as i128),
+        TypeDefPrimitive::I32 => Primitive::I128(i32::decode(input)? as i128),
+        TypeDefPrimitive::I64 => Primitive::I128(i64::decode(input)? as i128),
+        TypeDefPrimitive::I128 => Primitive::I128(i128::decode(input)?),
+        TypeDefPrimitive::I256 => Primitive::I256(<[u8; 32]>::decode(input)?),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_serialize_into_plain_json() {
+        let value = Value::Composite(Composite::Named(vec![
+            (
+                "who".to_string(),
+                Value::Sequence(vec![Value::Primitive(Primitive::U128(1)); 2]),
+            ),
+            (
+                "status".to_string(),
+                Value::Variant(VariantValue {
+                    name: "Free".to_string(),
+                    index: 0,
+                    fields: Composite::Unnamed(vec![]),
+                }),
+            ),
+            (
+                "result".to_string(),
+                Value::Variant(VariantValue {
+                    name: "Err".to_string(),
+                    index: 1,
+                    fields: Composite::Unnamed(vec![Value::Primitive(Primitive::Str(
+                        "oops".to_string(),
+                    ))]),
+                }),
+            ),
+            (
+                "big".to_string(),
+                Value::Primitive(Primitive::U256({
+                    let mut bytes = [0u8; 32];
+                    bytes[0] = 0xff;
+                    bytes
+                })),
+            ),
+        ]));
+
+        let json = serde_json::to_value(&value).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "who": [1, 1],
+                "status": "Free",
+                "result": { "Err": ["oops"] },
+                "big": format!("0x{}ff", "00".repeat(31)),
+            })
+        );
+    }
+
+    #[test]
+    fn raw_bit_sequences_round_trip() {
+        // 9 bits need two `u8` store elements.
+        let encoded = [Compact(9u32).encode(), vec![0xff, 0x01]].concat();
+        let bits = RawBitSequence::<u8>::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(
+            bits,
+            RawBitSequence {
+                bits: 9,
+                store: vec![0xff, 0x01]
+            }
+        );
+        assert_eq!(bits.encode(), encoded);
+
+        // 33 bits need two `u32` store elements.
+        let wide = RawBitSequence {
+            bits: 33,
+            store: vec![u32::MAX, 1],
+        };
+        let encoded = wide.encode();
+        assert_eq!(encoded.len(), 1 + 2 * 4);
+        assert_eq!(
+            RawBitSequence::<u32>::decode(&mut encoded.as_slice()),
+            Ok(wide)
+        );
+
+        let empty = RawBitSequence::<u8>::default();
+        assert_eq!(empty.encode(), vec![0]);
+        assert_eq!(RawBitSequence::<u8>::decode(&mut &[0u8][..]), Ok(empty));
+    }
+
+    #[test]
+    fn raw_bit_sequences_reject_missing_or_zero_sized_store_elements() {
+        let truncated = [Compact(9u32).encode(), vec![0xff]].concat();
+        assert!(RawBitSequence::<u8>::decode(&mut truncated.as_slice()).is_err());
+        assert!(RawBitSequence::<()>::decode(&mut Compact(1u32).encode().as_slice()).is_err());
+    }
+}
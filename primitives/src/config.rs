@@ -0,0 +1,163 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+
+*/
+
+//! The types of a chain the api client needs to know, bundled in the [`Config`] trait.
+
+use crate::{
+    AccountData, AccountId, AccountId20, AssetTipExtrinsicParams, EthereumSignature,
+    ExtrinsicParams, GenericAddress, PlainTipExtrinsicParams,
+};
+use codec::{Codec, HasCompact};
+use sp_core::H256;
+use sp_runtime::{
+    generic,
+    traits::{
+        AtLeast32BitUnsigned, BlakeTwo256, Hash as HashT, Header as HeaderT,
+        MaybeSerializeDeserialize, Member,
+    },
+    MultiSignature,
+};
+
+/// Runtime types of a chain, mirroring the respective types of its `frame_system::Config`.
+///
+/// `Api` is generic over this trait. Use one of the presets or implement it for chains with
+/// differing types, e.g. `u64` block numbers or a custom `AccountData`.
+pub trait Config: 'static {
+    /// Account index (aka nonce) type.
+    type Index: Member + Copy + Default + Codec + HasCompact + MaybeSerializeDeserialize;
+
+    /// Block number type.
+    type BlockNumber: Member
+        + Copy
+        + Default
+        + Codec
+        + AtLeast32BitUnsigned
+        + MaybeSerializeDeserialize;
+
+    /// Block hash type.
+    type Hash: Member
+        + Copy
+        + Default
+        + Ord
+        + core::hash::Hash
+        + AsRef<[u8]>
+        + Codec
+        + MaybeSerializeDeserialize;
+
+    /// Hashing algorithm of block headers and extrinsics.
+    type Hasher: HashT<Output = Self::Hash>;
+
+    /// Account id type.
+    type AccountId: Member + Codec + MaybeSerializeDeserialize;
+
+    /// Address type a signed extrinsic carries.
+    type Address: Member + Codec + From<Self::AccountId>;
+
+    /// Signature type a signed extrinsic carries.
+    type Signature: Member + Codec;
+
+    /// Block header type.
+    type Header: Member
+        + HeaderT<Number = Self::BlockNumber, Hash = Self::Hash, Hashing = Self::Hasher>
+        + MaybeSerializeDeserialize;
+
+    /// Data stored in `frame_system::AccountInfo`, usually `pallet_balances::AccountData`.
+    type AccountData: Member + Codec;
+
+    /// Signed extensions the chain expects.
+    type ExtrinsicParams: ExtrinsicParams<Index = Self::Index, Hash = Self::Hash>;
+}
+
+/// Builder of the extrinsic params of a config, see [`ExtrinsicParams::OtherParams`].
+pub type ExtrinsicParamsBuilderOf<T> =
+    <<T as Config>::ExtrinsicParams as ExtrinsicParams>::OtherParams;
+
+/// Signed extra of the extrinsics of a config, see [`ExtrinsicParams::SignedExtra`].
+pub type SignedExtraOf<T> = <<T as Config>::ExtrinsicParams as ExtrinsicParams>::SignedExtra;
+
+/// `frame_system::AccountInfo` of a config.
+pub type AccountInfoOf<T> = crate::AccountInfoGen<<T as Config>::Index, <T as Config>::AccountData>;
+
+/// Config of the substrate node-template and other chains with the default types of
+/// `frame_system` and `pallet_balances`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NodeTemplateConfig {}
+
+impl Config for NodeTemplateConfig {
+    type Index = u32;
+    type BlockNumber = u32;
+    type Hash = H256;
+    type Hasher = BlakeTwo256;
+    type AccountId = AccountId;
+    type Address = GenericAddress;
+    type Signature = MultiSignature;
+    type Header = generic::Header<Self::BlockNumber, Self::Hasher>;
+    type AccountData = AccountData;
+    type ExtrinsicParams = PlainTipExtrinsicParams;
+}
+
+/// Config of Polkadot, Kusama and other relay chains of the Polkadot family.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PolkadotConfig {}
+
+impl Config for PolkadotConfig {
+    type Index = u32;
+    type BlockNumber = u32;
+    type Hash = H256;
+    type Hasher = BlakeTwo256;
+    type AccountId = AccountId;
+    type Address = GenericAddress;
+    type Signature = MultiSignature;
+    type Header = generic::Header<Self::BlockNumber, Self::Hasher>;
+    type AccountData = AccountData;
+    type ExtrinsicParams = PlainTipExtrinsicParams;
+}
+
+/// Config of DB3 nodes, which charge transaction fees with `ChargeAssetTxPayment`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Db3Config {}
+
+impl Config for Db3Config {
+    type Index = u32;
+    type BlockNumber = u32;
+    type Hash = H256;
+    type Hasher = BlakeTwo256;
+    type AccountId = AccountId;
+    type Address = GenericAddress;
+    type Signature = MultiSignature;
+    type Header = generic::Header<Self::BlockNumber, Self::Hasher>;
+    type AccountData = AccountData;
+    type ExtrinsicParams = AssetTipExtrinsicParams;
+}
+
+/// Config of Ethereum compatible chains based on Frontier, e.g. Moonbeam, with 20 byte accounts
+/// and ECDSA signatures over keccak-256 hashes. Sign with an [`crate::EthereumPair`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EthereumConfig {}
+
+impl Config for EthereumConfig {
+    type Index = u32;
+    type BlockNumber = u32;
+    type Hash = H256;
+    type Hasher = BlakeTwo256;
+    type AccountId = AccountId20;
+    type Address = AccountId20;
+    type Signature = EthereumSignature;
+    type Header = generic::Header<Self::BlockNumber, Self::Hasher>;
+    type AccountData = AccountData;
+    type ExtrinsicParams = PlainTipExtrinsicParams;
+}
@@ -0,0 +1,251 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+
+*/
+
+//! Account, signature and signer types of Ethereum compatible (Frontier based) chains, which
+//! use 20 byte accounts derived from ECDSA keys instead of `AccountId32`.
+
+extern crate alloc;
+
+use crate::Signer;
+use alloc::string::String;
+use codec::{Decode, Encode};
+use core::{fmt, str::FromStr};
+use sp_core::{ecdsa, hashing::keccak_256, H160};
+use sp_runtime::traits::{IdentifyAccount, Lazy, Verify};
+
+/// 20 byte account id of Ethereum compatible chains: the last 20 bytes of the keccak-256 hash
+/// of the uncompressed ECDSA public key.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Encode, Decode)]
+pub struct AccountId20(pub [u8; 20]);
+
+impl AccountId20 {
+    /// Derive the account of an ECDSA public key.
+    ///
+    /// Returns `None` if `public` is not a valid compressed secp256k1 point.
+    pub fn from_public(public: &ecdsa::Public) -> Option<Self> {
+        let public = libsecp256k1::PublicKey::parse_compressed(&public.0).ok()?;
+        // Skip the `0x04` tag of the uncompressed serialization.
+        let hash = keccak_256(&public.serialize()[1..]);
+        let mut account = [0u8; 20];
+        account.copy_from_slice(&hash[12..]);
+        Some(Self(account))
+    }
+
+    /// Mixed case hex representation with the EIP-55 checksum, prefixed with `0x`.
+    pub fn to_checksum(&self) -> String {
+        let lower = hex::encode(self.0);
+        let hash = keccak_256(lower.as_bytes());
+        let mut checksummed = String::with_capacity(42);
+        checksummed.push_str("0x");
+        for (i, c) in lower.chars().enumerate() {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                checksummed.push(c.to_ascii_uppercase());
+            } else {
+                checksummed.push(c);
+            }
+        }
+        checksummed
+    }
+}
+
+impl From<[u8; 20]> for AccountId20 {
+    fn from(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<H160> for AccountId20 {
+    fn from(h: H160) -> Self {
+        Self(h.0)
+    }
+}
+
+impl From<AccountId20> for H160 {
+    fn from(account: AccountId20) -> Self {
+        H160(account.0)
+    }
+}
+
+impl AsRef<[u8]> for AccountId20 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for AccountId20 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_checksum())
+    }
+}
+
+impl fmt::Debug for AccountId20 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_checksum())
+    }
+}
+
+impl FromStr for AccountId20 {
+    type Err = hex::FromHexError;
+
+    /// Parse a hex string, with or without `0x` prefix. The checksum is not verified.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut account = [0u8; 20];
+        hex::decode_to_slice(s.trim_start_matches("0x"), &mut account)?;
+        Ok(Self(account))
+    }
+}
+
+#[cfg(feature = "std")]
+impl serde::Serialize for AccountId20 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_checksum())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> serde::Deserialize<'de> for AccountId20 {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Public key of an Ethereum account, identifying it by its [`AccountId20`].
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct EthereumSigner(pub ecdsa::Public);
+
+impl From<ecdsa::Public> for EthereumSigner {
+    fn from(public: ecdsa::Public) -> Self {
+        Self(public)
+    }
+}
+
+impl IdentifyAccount for EthereumSigner {
+    type AccountId = AccountId20;
+
+    /// Panics if the public key is not a valid secp256k1 point, which a key of an
+    /// `ecdsa::Pair` always is.
+    fn into_account(self) -> AccountId20 {
+        AccountId20::from_public(&self.0).expect("ecdsa public key is a valid point; qed")
+    }
+}
+
+/// Signature of Ethereum compatible chains: an ECDSA signature over the keccak-256 hash of the
+/// payload, from which the signing account is recovered.
+#[derive(Clone, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct EthereumSignature(pub ecdsa::Signature);
+
+impl From<ecdsa::Signature> for EthereumSignature {
+    fn from(signature: ecdsa::Signature) -> Self {
+        Self(signature)
+    }
+}
+
+impl Verify for EthereumSignature {
+    type Signer = EthereumSigner;
+
+    fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &AccountId20) -> bool {
+        self.0
+            .recover_prehashed(&keccak_256(msg.get()))
+            .and_then(|public| AccountId20::from_public(&public))
+            .map_or(false, |account| &account == signer)
+    }
+}
+
+/// ECDSA key pair signing for an [`AccountId20`].
+///
+/// A plain `ecdsa::Pair` signs for the `AccountId32` of its public key, hence the wrapper.
+#[derive(Clone)]
+pub struct EthereumPair(ecdsa::Pair);
+
+impl EthereumPair {
+    pub fn new(pair: ecdsa::Pair) -> Self {
+        Self(pair)
+    }
+
+    /// The wrapped key pair.
+    pub fn pair(&self) -> &ecdsa::Pair {
+        &self.0
+    }
+
+    pub fn public(&self) -> ecdsa::Public {
+        sp_core::Pair::public(&self.0)
+    }
+}
+
+impl From<ecdsa::Pair> for EthereumPair {
+    fn from(pair: ecdsa::Pair) -> Self {
+        Self(pair)
+    }
+}
+
+impl Signer for EthereumPair {
+    type AccountId = AccountId20;
+    type Address = AccountId20;
+    type Signature = EthereumSignature;
+
+    fn account_id(&self) -> AccountId20 {
+        EthereumSigner(self.public()).into_account()
+    }
+
+    fn sign(&self, payload: &[u8]) -> EthereumSignature {
+        EthereumSignature(self.0.sign_prehashed(&keccak_256(payload)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::Pair;
+
+    // Well known development account `Alith` of Moonbeam.
+    const ALITH_SEED: &str = "5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133";
+    const ALITH: &str = "0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566cac";
+
+    fn alith() -> EthereumPair {
+        let seed = hex::decode(ALITH_SEED).unwrap();
+        ecdsa::Pair::from_seed_slice(&seed).unwrap().into()
+    }
+
+    #[test]
+    fn account_is_derived_from_the_keccak_hash_of_the_public_key() {
+        assert_eq!(alith().account_id(), ALITH.parse().unwrap());
+    }
+
+    #[test]
+    fn display_uses_the_eip55_checksum() {
+        let account: AccountId20 = ALITH.to_lowercase().parse().unwrap();
+        assert_eq!(account.to_string(), ALITH);
+    }
+
+    #[test]
+    fn signature_verifies_for_the_signing_account_only() {
+        let signer = alith();
+        let signature = Signer::sign(&signer, b"payload");
+
+        assert!(signature.verify(&b"payload"[..], &signer.account_id()));
+        assert!(!signature.verify(&b"other payload"[..], &signer.account_id()));
+        assert!(!signature.verify(&b"payload"[..], &AccountId20::default()));
+    }
+
+    #[test]
+    fn signer_address_encodes_as_the_raw_account() {
+        let signer = alith();
+        assert_eq!(signer.address().encode(), signer.account_id().0.to_vec());
+    }
+}
@@ -1,4 +1,8 @@
-use codec::{Decode, Encode};
+extern crate alloc;
+
+use crate::SignedExtensionError;
+use alloc::string::String;
+use codec::{Decode, Encode, HasCompact};
 use core::marker::PhantomData;
 use sp_core::H256;
 use sp_runtime::traits::Hash;
@@ -8,34 +12,40 @@ use sp_std::prelude::*;
 /// Default SignedExtra.
 /// Simple generic extra mirroring the SignedExtra currently used in extrinsics.
 #[derive(Decode, Encode, Copy, Clone, Eq, PartialEq, Debug)]
-pub struct SubstrateDefaultSignedExtra<Tip> {
+pub struct SubstrateDefaultSignedExtra<Tip, Index = u32> {
     pub era: Era,
     #[codec(compact)]
-    pub nonce: u32,
+    pub nonce: Index,
     pub tip: Tip,
 }
 
-impl<Tip> SubstrateDefaultSignedExtra<Tip> {
-    pub fn new(era: Era, nonce: u32, tip: Tip) -> Self {
+impl<Tip, Index> SubstrateDefaultSignedExtra<Tip, Index> {
+    pub fn new(era: Era, nonce: Index, tip: Tip) -> Self {
         Self { era, nonce, tip }
     }
 }
 
 /// Default AdditionalSigned fields of the respective SignedExtra fields.
 /// The Order is (CheckNonZeroSender, CheckSpecVersion, CheckTxVersion, CheckGenesis, Check::Era, CheckNonce, CheckWeight, transactionPayment::ChargeTransactionPayment).
-pub type SubstrateDefaultAdditionalSigned = ((), u32, u32, H256, H256, (), (), ());
+pub type SubstrateDefaultAdditionalSigned<Hash = H256> = ((), u32, u32, Hash, Hash, (), (), ());
 
 /// This trait allows you to configure the "signed extra" and
 /// "additional" parameters that are signed and used in transactions.
 /// see [`BaseExtrinsicParams`] for an implementation that is compatible with
 /// a Polkadot node.
 pub trait ExtrinsicParams {
+    /// Account index (aka nonce) type of the chain.
+    type Index;
+
+    /// Block hash type of the chain.
+    type Hash;
+
     /// These parameters can be provided to the constructor along with
     /// some default parameters in order to help construct your [`ExtrinsicParams`] object.
     type OtherParams: Default + Clone;
 
     /// SignedExtra format of the node.
-    type SignedExtra: Copy + Encode;
+    type SignedExtra: Clone + Encode;
 
     /// Additional Signed format of the node
     type AdditionalSigned: Encode;
@@ -44,8 +54,8 @@ pub trait ExtrinsicParams {
     fn new(
         spec_version: u32,
         transaction_version: u32,
-        nonce: u32,
-        genesis_hash: H256,
+        nonce: Self::Index,
+        genesis_hash: Self::Hash,
         other_params: Self::OtherParams,
     ) -> Self;
 
@@ -57,6 +67,17 @@ pub trait ExtrinsicParams {
     /// taken into account when signing it, meaning the client and node must agree
     /// on their values.
     fn additional_signed(&self) -> Self::AdditionalSigned;
+
+    /// Adapt `other_params` to the signed extensions the runtime lists in its metadata, in
+    /// the order they are encoded. Called before [`ExtrinsicParams::new`] by the api.
+    ///
+    /// Params with a fixed set of signed extensions ignore them, which is the default.
+    fn with_signed_extensions(
+        other_params: Self::OtherParams,
+        _identifiers: &[String],
+    ) -> Result<Self::OtherParams, SignedExtensionError> {
+        Ok(other_params)
+    }
 }
 
 /// A struct representing the signed extra and additional parameters required
@@ -77,27 +98,27 @@ pub type PlainTipExtrinsicParamsBuilder = BaseExtrinsicParamsBuilder<PlainTip>;
 /// extrinsics that can be sent to a node with the same signed extra and additional
 /// parameters as a Polkadot/Substrate node.
 #[derive(Decode, Encode, Clone, Eq, PartialEq, Debug)]
-pub struct BaseExtrinsicParams<Tip> {
+pub struct BaseExtrinsicParams<Tip, Index = u32, Hash = H256> {
     era: Era,
-    nonce: u32,
+    nonce: Index,
     tip: Tip,
     spec_version: u32,
     transaction_version: u32,
-    genesis_hash: H256,
-    mortality_checkpoint: H256,
+    genesis_hash: Hash,
+    mortality_checkpoint: Hash,
     marker: PhantomData<()>,
 }
 
 /// This builder allows you to provide the parameters that can be configured in order to
 /// construct a [`BaseExtrinsicParams`] value.
 #[derive(Decode, Encode, Copy, Clone, Eq, PartialEq, Debug)]
-pub struct BaseExtrinsicParamsBuilder<Tip> {
+pub struct BaseExtrinsicParamsBuilder<Tip, Hash = H256> {
     era: Era,
-    mortality_checkpoint: Option<H256>,
+    mortality_checkpoint: Option<Hash>,
     tip: Tip,
 }
 
-impl<Tip: Default> BaseExtrinsicParamsBuilder<Tip> {
+impl<Tip: Default, Hash> BaseExtrinsicParamsBuilder<Tip, Hash> {
     /// Instantiate the default set of [`BaseExtrinsicParamsBuilder`]
     pub fn new() -> Self {
         Self::default()
@@ -108,7 +129,7 @@ impl<Tip: Default> BaseExtrinsicParamsBuilder<Tip> {
     /// of time). The second argument is the block hash after which the transaction
     /// becomes valid, and must align with the era phase (see the [`Era::Mortal`] docs
     /// for more detail on that).
-    pub fn era(mut self, era: Era, checkpoint: H256) -> Self {
+    pub fn era(mut self, era: Era, checkpoint: Hash) -> Self {
         self.era = era;
         self.mortality_checkpoint = Some(checkpoint);
         self
@@ -122,7 +143,7 @@ impl<Tip: Default> BaseExtrinsicParamsBuilder<Tip> {
     }
 }
 
-impl<Tip: Default> Default for BaseExtrinsicParamsBuilder<Tip> {
+impl<Tip: Default, Hash> Default for BaseExtrinsicParamsBuilder<Tip, Hash> {
     fn default() -> Self {
         Self {
             era: Era::Immortal,
@@ -132,20 +153,24 @@ impl<Tip: Default> Default for BaseExtrinsicParamsBuilder<Tip> {
     }
 }
 
-impl<Tip: Encode> ExtrinsicParams for BaseExtrinsicParams<Tip>
+impl<Tip, Index, Hash> ExtrinsicParams for BaseExtrinsicParams<Tip, Index, Hash>
 where
     u128: From<Tip>,
-    Tip: Copy + Default,
+    Tip: Encode + Copy + Default,
+    Index: HasCompact + Copy,
+    Hash: Encode + Copy,
 {
-    type OtherParams = BaseExtrinsicParamsBuilder<Tip>;
-    type SignedExtra = SubstrateDefaultSignedExtra<Tip>;
-    type AdditionalSigned = SubstrateDefaultAdditionalSigned;
+    type Index = Index;
+    type Hash = Hash;
+    type OtherParams = BaseExtrinsicParamsBuilder<Tip, Hash>;
+    type SignedExtra = SubstrateDefaultSignedExtra<Tip, Index>;
+    type AdditionalSigned = SubstrateDefaultAdditionalSigned<Hash>;
 
     fn new(
         spec_version: u32,
         transaction_version: u32,
-        nonce: u32,
-        genesis_hash: H256,
+        nonce: Index,
+        genesis_hash: Hash,
         other_params: Self::OtherParams,
     ) -> Self {
         BaseExtrinsicParams {
@@ -34,21 +34,30 @@ pub type CallIndex = [u8; 2];
 
 /// Mirrors the currently used Extrinsic format (V4) from substrate. Has less traits and methods though.
 /// The SingedExtra used does not need to implement SingedExtension here.
+/// Address and signature default to the types of a default substrate node.
 #[derive(Clone, Eq, PartialEq)]
-pub struct UncheckedExtrinsicV4<Call, SignedExtra> {
-    pub signature: Option<(GenericAddress, MultiSignature, SignedExtra)>,
+pub struct UncheckedExtrinsicV4<
+    Call,
+    SignedExtra,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> {
+    pub signature: Option<(Address, Signature, SignedExtra)>,
     pub function: Call,
 }
 
-impl<Call, SignedExtra> UncheckedExtrinsicV4<Call, SignedExtra>
+impl<Call, SignedExtra, Address, Signature>
+    UncheckedExtrinsicV4<Call, SignedExtra, Address, Signature>
 where
     Call: Encode,
     SignedExtra: Encode,
+    Address: Encode,
+    Signature: Encode,
 {
     pub fn new_signed(
         function: Call,
-        signed: GenericAddress,
-        signature: MultiSignature,
+        signed: Address,
+        signature: Signature,
         extra: SignedExtra,
     ) -> Self {
         UncheckedExtrinsicV4 {
@@ -64,10 +73,12 @@ where
     }
 }
 
-impl<Call, SignedExtra> fmt::Debug for UncheckedExtrinsicV4<Call, SignedExtra>
+impl<Call, SignedExtra, Address, Signature> fmt::Debug
+    for UncheckedExtrinsicV4<Call, SignedExtra, Address, Signature>
 where
     Call: fmt::Debug,
     SignedExtra: fmt::Debug,
+    Address: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -81,10 +92,13 @@ where
 
 const V4: u8 = 4;
 
-impl<Call, SignedExtra> Encode for UncheckedExtrinsicV4<Call, SignedExtra>
+impl<Call, SignedExtra, Address, Signature> Encode
+    for UncheckedExtrinsicV4<Call, SignedExtra, Address, Signature>
 where
     Call: Encode,
     SignedExtra: Encode,
+    Address: Encode,
+    Signature: Encode,
 {
     fn encode(&self) -> Vec<u8> {
         encode_with_vec_prefix::<Self, _>(|v| {
@@ -102,10 +116,13 @@ where
     }
 }
 
-impl<Call, SignedExtra> Decode for UncheckedExtrinsicV4<Call, SignedExtra>
+impl<Call, SignedExtra, Address, Signature> Decode
+    for UncheckedExtrinsicV4<Call, SignedExtra, Address, Signature>
 where
     Call: Decode + Encode,
     SignedExtra: Decode + Encode,
+    Address: Decode + Encode,
+    Signature: Decode + Encode,
 {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
         // This is a little more complicated than usual since the binary format must be compatible
@@ -176,7 +193,7 @@ mod tests {
         let tx_params =
             PlainTipExtrinsicParamsBuilder::new().era(Era::mortal(8, 0), Hash::from([0u8; 32]));
 
-        let default_extra = BaseExtrinsicParams::new(0, 0, 0, Hash::from([0u8; 32]), tx_params);
+        let default_extra = BaseExtrinsicParams::new(0, 0, 0u32, Hash::from([0u8; 32]), tx_params);
         let xt = UncheckedExtrinsicV4::new_signed(
             vec![1, 1, 1],
             account.into(),
@@ -1,19 +1,27 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Decode, Encode};
+use sp_runtime::{generic, traits::BlakeTwo256, OpaqueExtrinsic};
 
+pub use config::*;
+pub use ethereum::*;
 pub use extrinsic_params::*;
 pub use extrinsics::*;
+pub use signed_extensions::*;
+pub use signer::*;
 
+pub mod config;
+pub mod ethereum;
 pub mod extrinsic_params;
 pub mod extrinsics;
+pub mod signed_extensions;
+pub mod signer;
 
 /// The block number type used in this runtime.
 pub type BlockNumber = u64;
 /// The timestamp moment type used in this runtime.
 pub type Moment = u64;
-/// Index of a transaction.
-//fixme: make generic
+/// Index of a transaction of the preset [`Config`]s. Other chains set `Config::Index`.
 pub type Index = u32;
 
 pub type Hash = sp_core::H256;
@@ -21,6 +29,13 @@ pub type Hash = sp_core::H256;
 //fixme: make generic
 pub type Balance = u128;
 
+/// Header of a chain with `u32` block numbers and `BlakeTwo256` hashing, as used by most
+/// substrate based chains.
+pub type OpaqueHeader = generic::Header<u32, BlakeTwo256>;
+/// Block whose extrinsics are kept as opaque bytes, so it can be fetched without knowing the
+/// runtime's concrete extrinsic type.
+pub type OpaqueBlock = generic::Block<OpaqueHeader, OpaqueExtrinsic>;
+
 /// Redefinition from `pallet-balances`. Currently, pallets break `no_std` builds, see:
 /// https://github.com/paritytech/substrate/issues/8891
 #[derive(Clone, Eq, PartialEq, Default, Debug, Encode, Decode)]
@@ -0,0 +1,461 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+
+*/
+
+//! [`ExtrinsicParams`] driven by the signed extensions a runtime announces in its metadata.
+//!
+//! In contrast to [`BaseExtrinsicParams`](crate::BaseExtrinsicParams), which is fixed to the
+//! signed extensions of a default substrate node, [`DynamicExtrinsicParams`] encodes whatever
+//! extensions the metadata lists, in the order listed. Extensions unknown to this crate can be
+//! supported by registering a custom encoder.
+
+extern crate alloc;
+
+use crate::ExtrinsicParams;
+use alloc::{string::String, sync::Arc, vec::Vec};
+use codec::{Compact, Encode};
+use sp_core::H256;
+use sp_runtime::generic::Era;
+use sp_std::{collections::btree_map::BTreeMap, fmt, marker::PhantomData};
+
+/// Encoder of a signed extension. Returns the SCALE encoded `(extra, additional_signed)` data.
+pub type SignedExtensionEncoder =
+    Arc<dyn Fn(&SignedExtensionContext) -> (Vec<u8>, Vec<u8>) + Send + Sync>;
+
+/// Everything the signed extensions of a transaction may be built from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedExtensionContext {
+    pub spec_version: u32,
+    pub transaction_version: u32,
+    /// The nonce, widened to `u64` so it fits any index type. Encodes identically as compact.
+    pub nonce: u64,
+    pub genesis_hash: H256,
+    pub era: Era,
+    pub mortality_checkpoint: H256,
+    pub tip: u128,
+    /// SCALE encoded id of the asset the tip is paid in. `None` to pay in the native token.
+    pub tip_asset_id: Option<Vec<u8>>,
+    /// Metadata hash to enable the `CheckMetadataHash` extension with. `None` disables it.
+    pub metadata_hash: Option<H256>,
+}
+
+/// Error returned if a runtime requires signed extensions nobody knows how to encode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignedExtensionError {
+    /// The signed extension is neither known to this crate nor registered as custom extension.
+    Unknown(String),
+    /// The metadata lists no signed extensions, so the runtime's extrinsic format is unknown.
+    Empty,
+}
+
+impl fmt::Display for SignedExtensionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignedExtensionError::Unknown(identifier) => write!(
+                f,
+                "Unknown signed extension '{}'. Register a custom encoder for it.",
+                identifier
+            ),
+            SignedExtensionError::Empty => {
+                write!(f, "The metadata does not list any signed extensions.")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignedExtensionError {}
+
+/// Wraps already encoded signed extension data, so it is not encoded as byte vector again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncodedExtensions(pub Vec<u8>);
+
+impl Encode for EncodedExtensions {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn encode_to<T: codec::Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+/// An implementation of [`ExtrinsicParams`] that encodes the signed extensions listed in the
+/// runtime metadata. `Index` is the account index type of the chain.
+#[derive(Clone)]
+pub struct DynamicExtrinsicParams<Index = u32> {
+    context: SignedExtensionContext,
+    extensions: Vec<String>,
+    custom_extensions: BTreeMap<String, SignedExtensionEncoder>,
+    marker: PhantomData<Index>,
+}
+
+/// This builder allows you to provide the parameters that can be configured in order to
+/// construct a [`DynamicExtrinsicParams`] value.
+#[derive(Clone)]
+pub struct DynamicExtrinsicParamsBuilder {
+    era: Era,
+    mortality_checkpoint: Option<H256>,
+    tip: u128,
+    tip_asset_id: Option<Vec<u8>>,
+    metadata_hash: Option<H256>,
+    extensions: Vec<String>,
+    custom_extensions: BTreeMap<String, SignedExtensionEncoder>,
+}
+
+impl DynamicExtrinsicParamsBuilder {
+    /// Instantiate a builder without any signed extension.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the signed extensions of the runtime, in the order given by the metadata
+    /// (`metadata.extrinsic.signed_extensions`).
+    ///
+    /// Fails if one of them is neither known nor registered with [`Self::custom_extension`],
+    /// so custom extensions must be registered first.
+    pub fn signed_extensions<I, S>(mut self, identifiers: I) -> Result<Self, SignedExtensionError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let extensions = identifiers
+            .into_iter()
+            .map(Into::into)
+            .map(|identifier| {
+                if self.custom_extensions.contains_key(&identifier)
+                    || is_known_extension(&identifier)
+                {
+                    Ok(identifier)
+                } else {
+                    Err(SignedExtensionError::Unknown(identifier))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        self.extensions = extensions;
+        Ok(self)
+    }
+
+    /// Register an encoder for a signed extension this crate does not know, or to override
+    /// the encoding of a known one.
+    pub fn custom_extension<F>(mut self, identifier: impl Into<String>, encoder: F) -> Self
+    where
+        F: Fn(&SignedExtensionContext) -> (Vec<u8>, Vec<u8>) + Send + Sync + 'static,
+    {
+        self.custom_extensions
+            .insert(identifier.into(), Arc::new(encoder));
+        self
+    }
+
+    /// Set the [`Era`] and the block hash after which the transaction becomes valid,
+    /// see [`BaseExtrinsicParamsBuilder::era`](crate::BaseExtrinsicParamsBuilder::era).
+    pub fn era(mut self, era: Era, checkpoint: H256) -> Self {
+        self.era = era;
+        self.mortality_checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// Set the tip you'd like to give to the block author for this transaction.
+    pub fn tip(mut self, tip: u128) -> Self {
+        self.tip = tip;
+        self
+    }
+
+    /// Pay the tip and fees in the asset with id `asset_id` (`ChargeAssetTxPayment`).
+    /// Any asset id type can be used, e.g. `u32` or a `MultiLocation`.
+    pub fn tip_asset_id(mut self, asset_id: impl Encode) -> Self {
+        self.tip_asset_id = Some(asset_id.encode());
+        self
+    }
+
+    /// Enable the `CheckMetadataHash` extension with the given metadata hash.
+    pub fn metadata_hash(mut self, hash: H256) -> Self {
+        self.metadata_hash = Some(hash);
+        self
+    }
+}
+
+impl Default for DynamicExtrinsicParamsBuilder {
+    fn default() -> Self {
+        Self {
+            era: Era::Immortal,
+            mortality_checkpoint: None,
+            tip: 0,
+            tip_asset_id: None,
+            metadata_hash: None,
+            extensions: Vec::new(),
+            custom_extensions: BTreeMap::new(),
+        }
+    }
+}
+
+impl<Index> DynamicExtrinsicParams<Index> {
+    /// The signed extensions that are encoded, in order.
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    fn encode_extension(&self, identifier: &str) -> (Vec<u8>, Vec<u8>) {
+        if let Some(encoder) = self.custom_extensions.get(identifier) {
+            return encoder(&self.context);
+        }
+        let ctx = &self.context;
+        match identifier {
+            "CheckSpecVersion" => (Vec::new(), ctx.spec_version.encode()),
+            "CheckTxVersion" => (Vec::new(), ctx.transaction_version.encode()),
+            "CheckGenesis" => (Vec::new(), ctx.genesis_hash.encode()),
+            "CheckMortality" | "CheckEra" => (ctx.era.encode(), ctx.mortality_checkpoint.encode()),
+            "CheckNonce" => (Compact(ctx.nonce).encode(), Vec::new()),
+            "ChargeTransactionPayment" => (Compact(ctx.tip).encode(), Vec::new()),
+            "ChargeAssetTxPayment" => {
+                let mut extra = Compact(ctx.tip).encode();
+                match &ctx.tip_asset_id {
+                    Some(asset_id) => {
+                        extra.push(1);
+                        extra.extend_from_slice(asset_id);
+                    }
+                    None => extra.push(0),
+                }
+                (extra, Vec::new())
+            }
+            "CheckMetadataHash" => match ctx.metadata_hash {
+                // `Mode::Enabled` and `Some(hash)`
+                Some(hash) => (1u8.encode(), Some(hash).encode()),
+                // `Mode::Disabled` and `None`
+                None => (0u8.encode(), None::<H256>.encode()),
+            },
+            // Extensions without extra and additional data, e.g. `CheckNonZeroSender`,
+            // `CheckWeight` or `PrevalidateAttests`.
+            _ => (Vec::new(), Vec::new()),
+        }
+    }
+}
+
+impl<Index: Into<u64>> ExtrinsicParams for DynamicExtrinsicParams<Index> {
+    type Index = Index;
+    type Hash = H256;
+    type OtherParams = DynamicExtrinsicParamsBuilder;
+    type SignedExtra = EncodedExtensions;
+    type AdditionalSigned = EncodedExtensions;
+
+    fn new(
+        spec_version: u32,
+        transaction_version: u32,
+        nonce: Index,
+        genesis_hash: H256,
+        other_params: Self::OtherParams,
+    ) -> Self {
+        DynamicExtrinsicParams {
+            context: SignedExtensionContext {
+                spec_version,
+                transaction_version,
+                nonce: nonce.into(),
+                genesis_hash,
+                era: other_params.era,
+                mortality_checkpoint: other_params.mortality_checkpoint.unwrap_or(genesis_hash),
+                tip: other_params.tip,
+                tip_asset_id: other_params.tip_asset_id,
+                metadata_hash: other_params.metadata_hash,
+            },
+            extensions: other_params.extensions,
+            custom_extensions: other_params.custom_extensions,
+            marker: PhantomData,
+        }
+    }
+
+    fn signed_extra(&self) -> Self::SignedExtra {
+        let extra = self
+            .extensions
+            .iter()
+            .flat_map(|identifier| self.encode_extension(identifier).0)
+            .collect();
+        EncodedExtensions(extra)
+    }
+
+    fn additional_signed(&self) -> Self::AdditionalSigned {
+        let additional = self
+            .extensions
+            .iter()
+            .flat_map(|identifier| self.encode_extension(identifier).1)
+            .collect();
+        EncodedExtensions(additional)
+    }
+
+    /// Sets the signed extensions of the metadata, unless they were set explicitly with
+    /// [`DynamicExtrinsicParamsBuilder::signed_extensions`].
+    fn with_signed_extensions(
+        other_params: Self::OtherParams,
+        identifiers: &[String],
+    ) -> Result<Self::OtherParams, SignedExtensionError> {
+        if !other_params.extensions.is_empty() {
+            return Ok(other_params);
+        }
+        if identifiers.is_empty() {
+            return Err(SignedExtensionError::Empty);
+        }
+        other_params.signed_extensions(identifiers.iter().cloned())
+    }
+}
+
+/// Signed extensions this crate knows how to encode.
+fn is_known_extension(identifier: &str) -> bool {
+    matches!(
+        identifier,
+        "CheckNonZeroSender"
+            | "CheckSpecVersion"
+            | "CheckTxVersion"
+            | "CheckGenesis"
+            | "CheckMortality"
+            | "CheckEra"
+            | "CheckNonce"
+            | "CheckWeight"
+            | "ChargeTransactionPayment"
+            | "ChargeAssetTxPayment"
+            | "CheckMetadataHash"
+            | "PrevalidateAttests"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PlainTipExtrinsicParams, PlainTipExtrinsicParamsBuilder};
+
+    const SUBSTRATE_EXTENSIONS: [&str; 8] = [
+        "CheckNonZeroSender",
+        "CheckSpecVersion",
+        "CheckTxVersion",
+        "CheckGenesis",
+        "CheckMortality",
+        "CheckNonce",
+        "CheckWeight",
+        "ChargeTransactionPayment",
+    ];
+
+    #[test]
+    fn encodes_like_base_extrinsic_params_for_substrate_extensions() {
+        let genesis = H256::from([1u8; 32]);
+        let checkpoint = H256::from([2u8; 32]);
+        let era = Era::mortal(8, 0);
+
+        let base = PlainTipExtrinsicParams::new(
+            5,
+            2,
+            7,
+            genesis,
+            PlainTipExtrinsicParamsBuilder::new()
+                .era(era, checkpoint)
+                .tip(42u128),
+        );
+        let dynamic = DynamicExtrinsicParams::new(
+            5,
+            2,
+            7u32,
+            genesis,
+            DynamicExtrinsicParamsBuilder::new()
+                .era(era, checkpoint)
+                .tip(42)
+                .signed_extensions(SUBSTRATE_EXTENSIONS)
+                .unwrap(),
+        );
+
+        assert_eq!(
+            base.signed_extra().encode(),
+            dynamic.signed_extra().encode()
+        );
+        assert_eq!(
+            base.additional_signed().encode(),
+            dynamic.additional_signed().encode()
+        );
+    }
+
+    #[test]
+    fn metadata_extensions_fill_the_builder() {
+        let genesis = H256::from([1u8; 32]);
+        let identifiers: Vec<String> = SUBSTRATE_EXTENSIONS.iter().map(|s| s.to_string()).collect();
+
+        let base = PlainTipExtrinsicParams::new(
+            5,
+            2,
+            7,
+            genesis,
+            PlainTipExtrinsicParamsBuilder::new().tip(42u128),
+        );
+        let builder = <DynamicExtrinsicParams as ExtrinsicParams>::with_signed_extensions(
+            DynamicExtrinsicParamsBuilder::new().tip(42),
+            &identifiers,
+        )
+        .unwrap();
+        let dynamic = DynamicExtrinsicParams::new(5, 2, 7u32, genesis, builder);
+
+        assert_eq!(dynamic.extensions(), identifiers.as_slice());
+        assert_eq!(
+            base.signed_extra().encode(),
+            dynamic.signed_extra().encode()
+        );
+        assert_eq!(
+            base.additional_signed().encode(),
+            dynamic.additional_signed().encode()
+        );
+    }
+
+    #[test]
+    fn metadata_extensions_must_be_known_and_not_empty() {
+        assert_eq!(
+            <DynamicExtrinsicParams as ExtrinsicParams>::with_signed_extensions(
+                DynamicExtrinsicParamsBuilder::new(),
+                &[],
+            )
+            .err(),
+            Some(SignedExtensionError::Empty)
+        );
+        assert_eq!(
+            <DynamicExtrinsicParams as ExtrinsicParams>::with_signed_extensions(
+                DynamicExtrinsicParamsBuilder::new(),
+                &["CheckNonce".into(), "CheckDb3Permission".into()],
+            )
+            .err(),
+            Some(SignedExtensionError::Unknown("CheckDb3Permission".into()))
+        );
+    }
+
+    #[test]
+    fn unknown_extension_errors() {
+        let result = DynamicExtrinsicParamsBuilder::new()
+            .signed_extensions(["CheckSpecVersion", "CheckDb3Permission"]);
+        assert_eq!(
+            result.err(),
+            Some(SignedExtensionError::Unknown("CheckDb3Permission".into()))
+        );
+    }
+
+    #[test]
+    fn custom_extension_is_encoded_in_order() {
+        let builder = DynamicExtrinsicParamsBuilder::new()
+            .custom_extension("CheckDb3Permission", |ctx| {
+                (vec![9u8], ctx.spec_version.encode())
+            })
+            .signed_extensions(["CheckNonce", "CheckDb3Permission", "CheckSpecVersion"])
+            .unwrap();
+        let params = DynamicExtrinsicParams::new(3, 1, 1u32, H256::zero(), builder);
+
+        assert_eq!(params.signed_extra().encode(), vec![4u8, 9]);
+        assert_eq!(
+            params.additional_signed().encode(),
+            [3u32.encode(), 3u32.encode()].concat()
+        );
+    }
+}
@@ -0,0 +1,92 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+
+*/
+
+//! Signer abstraction, so extrinsics can be signed without holding the secret key in memory.
+
+extern crate alloc;
+
+use crate::{AccountId, GenericAddress};
+use alloc::string::String;
+use sp_core::Pair;
+use sp_runtime::{traits::IdentifyAccount, MultiSignature, MultiSigner};
+use sp_std::fmt;
+
+/// Error of a [`Signer`] that failed to sign, e.g. because its key is no longer available.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignerError(pub String);
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Signer failed to sign: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignerError {}
+
+/// Signs extrinsic payloads on behalf of an account.
+///
+/// Implemented for every `sp_core::Pair` whose signature and public key convert into the
+/// multi types, so the in-memory keys used so far keep working unchanged.
+pub trait Signer {
+    /// Account id type of the chain, see `Config::AccountId`.
+    type AccountId;
+    /// Address type the extrinsic carries, see `Config::Address`.
+    type Address: From<Self::AccountId>;
+    /// Signature type the extrinsic carries, see `Config::Signature`.
+    type Signature;
+
+    /// The account the extrinsics are signed for.
+    fn account_id(&self) -> Self::AccountId;
+
+    /// The address the signed extrinsic carries.
+    fn address(&self) -> Self::Address {
+        self.account_id().into()
+    }
+
+    /// Sign the (possibly hashed) payload of an extrinsic.
+    ///
+    /// Signers that can fail, e.g. because the key is held elsewhere, panic on failure. Use
+    /// [`Signer::try_sign`] to get an error instead.
+    fn sign(&self, payload: &[u8]) -> Self::Signature;
+
+    /// Sign like [`Signer::sign`], but return an error if signing fails.
+    ///
+    /// Delegates to [`Signer::sign`] by default, which is right for signers that can't fail.
+    fn try_sign(&self, payload: &[u8]) -> Result<Self::Signature, SignerError> {
+        Ok(self.sign(payload))
+    }
+}
+
+impl<P> Signer for P
+where
+    P: Pair,
+    MultiSignature: From<P::Signature>,
+    MultiSigner: From<P::Public>,
+{
+    type AccountId = AccountId;
+    type Address = GenericAddress;
+    type Signature = MultiSignature;
+
+    fn account_id(&self) -> AccountId {
+        MultiSigner::from(self.public()).into_account()
+    }
+
+    fn sign(&self, payload: &[u8]) -> MultiSignature {
+        Pair::sign(self, payload).into()
+    }
+}
@@ -17,43 +17,42 @@
 
 //! Extrinsics for `pallet-balances`.
 
-use crate::std::{Api, RpcClient};
-use ac_compose_macros::compose_extrinsic;
-use ac_primitives::{Balance, CallIndex, ExtrinsicParams, GenericAddress, UncheckedExtrinsicV4};
+use crate::std::{Api, ApiResult, RpcClient};
+use ac_compose_macros::try_compose_extrinsic;
+use ac_primitives::{
+    Balance, CallIndex, Config, GenericAddress, SignedExtraOf, Signer, UncheckedExtrinsicV4,
+};
 use codec::Compact;
-use sp_core::crypto::Pair;
-use sp_runtime::{MultiSignature, MultiSigner};
+use sp_runtime::MultiSignature;
 
 pub const BALANCES_MODULE: &str = "Balances";
 pub const BALANCES_TRANSFER: &str = "transfer";
 pub const BALANCES_SET_BALANCE: &str = "set_balance";
 
-pub type BalanceTransferFn = (CallIndex, GenericAddress, Compact<Balance>);
-pub type BalanceSetBalanceFn = (
-    CallIndex,
-    GenericAddress,
-    Compact<Balance>,
-    Compact<Balance>,
-);
+pub type BalanceTransferFn<Address = GenericAddress> = (CallIndex, Address, Compact<Balance>);
+pub type BalanceSetBalanceFn<Address = GenericAddress> =
+    (CallIndex, Address, Compact<Balance>, Compact<Balance>);
 
-pub type BalanceTransferXt<SignedExtra> = UncheckedExtrinsicV4<BalanceTransferFn, SignedExtra>;
-pub type BalanceSetBalanceXt<SignedExtra> = UncheckedExtrinsicV4<BalanceSetBalanceFn, SignedExtra>;
+pub type BalanceTransferXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<BalanceTransferFn<Address>, SignedExtra, Address, Signature>;
+pub type BalanceSetBalanceXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<BalanceSetBalanceFn<Address>, SignedExtra, Address, Signature>;
 
 #[cfg(feature = "std")]
-impl<P, Client, Params> Api<P, Client, Params>
+impl<P, Client, T> Api<P, Client, T>
 where
-    P: Pair,
-    MultiSignature: From<P::Signature>,
-    MultiSigner: From<P::Public>,
+    P: Signer<AccountId = T::AccountId, Address = T::Address, Signature = T::Signature>,
     Client: RpcClient,
-    Params: ExtrinsicParams,
+    T: Config,
 {
+    /// Transfer to `to`, which is a `MultiAddress` on most chains and an `AccountId20` on
+    /// Ethereum compatible ones, see `Config::Address`.
     pub fn balance_transfer(
         &self,
-        to: GenericAddress,
+        to: T::Address,
         amount: Balance,
-    ) -> BalanceTransferXt<Params::SignedExtra> {
-        compose_extrinsic!(
+    ) -> ApiResult<BalanceTransferXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(
             self,
             BALANCES_MODULE,
             BALANCES_TRANSFER,
@@ -64,11 +63,11 @@ where
 
     pub fn balance_set_balance(
         &self,
-        who: GenericAddress,
+        who: T::Address,
         free_balance: Balance,
         reserved_balance: Balance,
-    ) -> BalanceSetBalanceXt<Params::SignedExtra> {
-        compose_extrinsic!(
+    ) -> ApiResult<BalanceSetBalanceXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(
             self,
             BALANCES_MODULE,
             BALANCES_SET_BALANCE,
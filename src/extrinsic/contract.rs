@@ -18,13 +18,14 @@
 //! Extrinsics for `pallet-contract`.
 //! Contracts module is community maintained and not CI tested, therefore it may not work as is.
 
-use crate::std::{Api, RpcClient};
-use ac_compose_macros::compose_extrinsic;
-use ac_primitives::{Balance, CallIndex, ExtrinsicParams, GenericAddress, UncheckedExtrinsicV4};
+use crate::std::{Api, ApiResult, RpcClient};
+use ac_compose_macros::try_compose_extrinsic;
+use ac_primitives::{
+    Balance, CallIndex, Config, GenericAddress, SignedExtraOf, Signer, UncheckedExtrinsicV4,
+};
 use codec::Compact;
-use sp_core::crypto::Pair;
 use sp_core::H256 as Hash;
-use sp_runtime::{MultiSignature, MultiSigner};
+use sp_runtime::MultiSignature;
 use sp_std::prelude::*;
 
 pub const CONTRACTS_MODULE: &str = "Contracts";
@@ -41,35 +42,38 @@ type Salt = Vec<u8>;
 type GasLimit = Compact<Gas>;
 type Endowment = Compact<Balance>;
 type Value = Compact<Balance>;
-type Destination = GenericAddress;
 
 pub type ContractPutCodeFn = (CallIndex, GasLimit, Data);
 pub type ContractInstantiateFn = (CallIndex, Endowment, GasLimit, Hash, Data);
 pub type ContractInstantiateWithCodeFn = (CallIndex, Endowment, GasLimit, Code, Data, Salt);
-pub type ContractCallFn = (CallIndex, Destination, Value, GasLimit, Data);
+pub type ContractCallFn<Destination = GenericAddress> =
+    (CallIndex, Destination, Value, GasLimit, Data);
 
-pub type ContractPutCodeXt<SignedExtra> = UncheckedExtrinsicV4<ContractPutCodeFn, SignedExtra>;
-pub type ContractInstantiateXt<SignedExtra> =
-    UncheckedExtrinsicV4<ContractInstantiateFn, SignedExtra>;
-pub type ContractInstantiateWithCodeXt<SignedExtra> =
-    UncheckedExtrinsicV4<ContractInstantiateWithCodeFn, SignedExtra>;
-pub type ContractCallXt<SignedExtra> = UncheckedExtrinsicV4<ContractCallFn, SignedExtra>;
+pub type ContractPutCodeXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<ContractPutCodeFn, SignedExtra, Address, Signature>;
+pub type ContractInstantiateXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<ContractInstantiateFn, SignedExtra, Address, Signature>;
+pub type ContractInstantiateWithCodeXt<
+    SignedExtra,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> = UncheckedExtrinsicV4<ContractInstantiateWithCodeFn, SignedExtra, Address, Signature>;
+pub type ContractCallXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<ContractCallFn<Address>, SignedExtra, Address, Signature>;
 
 #[cfg(feature = "std")]
-impl<P, Client, Params> Api<P, Client, Params>
+impl<P, Client, T> Api<P, Client, T>
 where
-    P: Pair,
-    MultiSignature: From<P::Signature>,
-    MultiSigner: From<P::Public>,
+    P: Signer<AccountId = T::AccountId, Address = T::Address, Signature = T::Signature>,
     Client: RpcClient,
-    Params: ExtrinsicParams,
+    T: Config,
 {
     pub fn contract_put_code(
         &self,
         gas_limit: Gas,
         code: Data,
-    ) -> ContractPutCodeXt<Params::SignedExtra> {
-        compose_extrinsic!(
+    ) -> ApiResult<ContractPutCodeXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(
             self,
             CONTRACTS_MODULE,
             CONTRACTS_PUT_CODE,
@@ -84,8 +88,8 @@ where
         gas_limit: Gas,
         code_hash: Hash,
         data: Data,
-    ) -> ContractInstantiateXt<Params::SignedExtra> {
-        compose_extrinsic!(
+    ) -> ApiResult<ContractInstantiateXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(
             self,
             CONTRACTS_MODULE,
             CONTRACTS_INSTANTIATE,
@@ -103,8 +107,8 @@ where
         code: Data,
         data: Data,
         salt: Data,
-    ) -> ContractInstantiateWithCodeXt<Params::SignedExtra> {
-        compose_extrinsic!(
+    ) -> ApiResult<ContractInstantiateWithCodeXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(
             self,
             CONTRACTS_MODULE,
             CONTRACTS_INSTANTIATE_WITH_CODE,
@@ -118,12 +122,12 @@ where
 
     pub fn contract_call(
         &self,
-        dest: GenericAddress,
+        dest: T::Address,
         value: Balance,
         gas_limit: Gas,
         data: Data,
-    ) -> ContractCallXt<Params::SignedExtra> {
-        compose_extrinsic!(
+    ) -> ApiResult<ContractCallXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(
             self,
             CONTRACTS_MODULE,
             CONTRACTS_CALL,
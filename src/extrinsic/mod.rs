@@ -19,5 +19,6 @@
 
 pub mod balances;
 pub mod contract;
+pub mod proxy;
 #[cfg(feature = "staking-xt")]
 pub mod staking;
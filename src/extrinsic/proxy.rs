@@ -0,0 +1,332 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+
+*/
+
+//! Extrinsics for `pallet-proxy`.
+//!
+//! The proxy type is runtime specific, therefore all calls are generic over it. Any type that
+//! encodes like the runtime's `ProxyType` enum can be used, e.g. the runtime's enum itself or a
+//! mirrored definition.
+
+use crate::std::{Api, ApiClientError, ApiResult, RpcClient};
+use crate::Encoded;
+use ac_compose_macros::try_compose_extrinsic;
+use ac_primitives::{
+    AccountId, CallIndex, Config, GenericAddress, SignedExtraOf, Signer, UncheckedExtrinsicV4,
+};
+use codec::{Decode, Encode};
+use sp_core::{blake2_256, H256 as Hash};
+use sp_runtime::{traits::TrailingZeroInput, MultiSignature};
+
+pub const PROXY_MODULE: &str = "Proxy";
+pub const PROXY_PROXY: &str = "proxy";
+pub const PROXY_ADD_PROXY: &str = "add_proxy";
+pub const PROXY_REMOVE_PROXY: &str = "remove_proxy";
+pub const PROXY_ANNOUNCE: &str = "announce";
+/// Name of the pure proxy creation call since substrate polkadot-v0.9.29.
+pub const PROXY_CREATE_PURE: &str = "create_pure";
+/// Name of the pure proxy creation call up to substrate polkadot-v0.9.28.
+pub const PROXY_ANONYMOUS: &str = "anonymous";
+
+/// Block number type used by `pallet-proxy` for delays and spawn heights.
+pub type ProxyBlockNumber = u32;
+
+pub type ProxyProxyFn<ProxyType, Call, Account = AccountId> =
+    (CallIndex, Account, Option<ProxyType>, Call);
+pub type ProxyAddProxyFn<ProxyType, Account = AccountId> =
+    (CallIndex, Account, ProxyType, ProxyBlockNumber);
+pub type ProxyRemoveProxyFn<ProxyType, Account = AccountId> =
+    (CallIndex, Account, ProxyType, ProxyBlockNumber);
+pub type ProxyCreatePureFn<ProxyType> = (CallIndex, ProxyType, ProxyBlockNumber, u16);
+pub type ProxyAnnounceFn<Account = AccountId> = (CallIndex, Account, Hash);
+
+pub type ProxyProxyXt<
+    ProxyType,
+    Call,
+    SignedExtra,
+    Account = AccountId,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> = UncheckedExtrinsicV4<ProxyProxyFn<ProxyType, Call, Account>, SignedExtra, Address, Signature>;
+pub type ProxyAddProxyXt<
+    ProxyType,
+    SignedExtra,
+    Account = AccountId,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> = UncheckedExtrinsicV4<ProxyAddProxyFn<ProxyType, Account>, SignedExtra, Address, Signature>;
+pub type ProxyRemoveProxyXt<
+    ProxyType,
+    SignedExtra,
+    Account = AccountId,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> = UncheckedExtrinsicV4<ProxyRemoveProxyFn<ProxyType, Account>, SignedExtra, Address, Signature>;
+pub type ProxyCreatePureXt<
+    ProxyType,
+    SignedExtra,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> = UncheckedExtrinsicV4<ProxyCreatePureFn<ProxyType>, SignedExtra, Address, Signature>;
+pub type ProxyAnnounceXt<
+    SignedExtra,
+    Account = AccountId,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> = UncheckedExtrinsicV4<ProxyAnnounceFn<Account>, SignedExtra, Address, Signature>;
+
+/// `ProxyProxyXt` of the config `T`.
+pub type ProxyProxyXtOf<ProxyType, Call, T> = ProxyProxyXt<
+    ProxyType,
+    Call,
+    SignedExtraOf<T>,
+    <T as Config>::AccountId,
+    <T as Config>::Address,
+    <T as Config>::Signature,
+>;
+
+/// Proxy setup used to wrap calls: the `real` account the signer acts on behalf of and
+/// optionally the proxy type the call must be dispatched with.
+///
+/// Set it on the api with `Api::set_proxy` to have `Api::proxied` apply it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProxyConfig<ProxyType, Account = AccountId> {
+    pub real: Account,
+    pub force_proxy_type: Option<ProxyType>,
+}
+
+impl<ProxyType, Account> ProxyConfig<ProxyType, Account> {
+    pub fn new(real: Account, force_proxy_type: Option<ProxyType>) -> Self {
+        Self {
+            real,
+            force_proxy_type,
+        }
+    }
+}
+
+impl<ProxyType: Encode, Account> ProxyConfig<ProxyType, Account> {
+    /// The config with the proxy type SCALE encoded, as stored on the api.
+    pub fn encoded(self) -> ProxyConfig<Encoded, Account> {
+        ProxyConfig {
+            real: self.real,
+            force_proxy_type: self
+                .force_proxy_type
+                .map(|proxy_type| Encoded(proxy_type.encode())),
+        }
+    }
+}
+
+/// Derives the address of a pure (formerly anonymous) proxy offline, the same way
+/// `pallet_proxy::Pallet::pure_account` does.
+///
+/// * `spawner` - account that signed the `create_pure` extrinsic.
+/// * `proxy_type`, `index` - the arguments supplied to `create_pure`.
+/// * `height`, `ext_index` - block number and extrinsic index the pure proxy was created in.
+pub fn pure_proxy_account<ProxyType: Encode, Account: Encode + Decode>(
+    spawner: &Account,
+    proxy_type: &ProxyType,
+    index: u16,
+    height: ProxyBlockNumber,
+    ext_index: u32,
+) -> Account {
+    let entropy = (
+        b"modlpy/proxy____",
+        spawner,
+        height,
+        ext_index,
+        proxy_type,
+        index,
+    )
+        .using_encoded(blake2_256);
+    Account::decode(&mut TrailingZeroInput::new(entropy.as_ref()))
+        .expect("infinite length input; no invalid inputs for type; qed")
+}
+
+#[cfg(feature = "std")]
+impl<P, Client, T> Api<P, Client, T>
+where
+    Client: RpcClient,
+    T: Config,
+{
+    /// Wrap the calls passed to `Api::proxied` into `Proxy.proxy` extrinsics dispatched on
+    /// behalf of `config.real`.
+    #[must_use]
+    pub fn set_proxy<ProxyType: Encode>(
+        mut self,
+        config: ProxyConfig<ProxyType, T::AccountId>,
+    ) -> Self {
+        self.proxy = Some(config.encoded());
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P, Client, T> Api<P, Client, T>
+where
+    P: Signer<AccountId = T::AccountId, Address = T::Address, Signature = T::Signature>,
+    Client: RpcClient,
+    T: Config,
+{
+    /// Dispatch `call` from the account `real`, which must have registered the signer as proxy.
+    ///
+    /// `call` can be any call built with `compose_call!`.
+    pub fn proxy<ProxyType, Call>(
+        &self,
+        real: T::AccountId,
+        force_proxy_type: Option<ProxyType>,
+        call: Call,
+    ) -> ApiResult<ProxyProxyXtOf<ProxyType, Call, T>>
+    where
+        ProxyType: Encode + Clone,
+        Call: Encode + Clone,
+    {
+        try_compose_extrinsic!(
+            self,
+            PROXY_MODULE,
+            PROXY_PROXY,
+            real,
+            force_proxy_type,
+            call
+        )
+    }
+
+    /// Wrap `call` into a `Proxy.proxy` extrinsic through the proxy configured with
+    /// `Api::set_proxy`. Fails with `ApiClientError::NoProxy` if there is none.
+    ///
+    /// The resulting extrinsic can be submitted with `Api::send_extrinsic` like any other one.
+    pub fn proxied<Call>(&self, call: Call) -> ApiResult<ProxyProxyXtOf<Encoded, Call, T>>
+    where
+        Call: Encode + Clone,
+    {
+        let config = self.proxy.as_ref().ok_or(ApiClientError::NoProxy)?;
+        self.proxied_with(config, call)
+    }
+
+    /// Wrap `call` into a `Proxy.proxy` extrinsic as described by `config`, regardless of the
+    /// proxy configured on the api.
+    pub fn proxied_with<ProxyType, Call>(
+        &self,
+        config: &ProxyConfig<ProxyType, T::AccountId>,
+        call: Call,
+    ) -> ApiResult<ProxyProxyXtOf<ProxyType, Call, T>>
+    where
+        ProxyType: Encode + Clone,
+        Call: Encode + Clone,
+    {
+        self.proxy(config.real.clone(), config.force_proxy_type.clone(), call)
+    }
+
+    /// Register `delegate` as proxy of the signer.
+    pub fn proxy_add_proxy<ProxyType: Encode + Clone>(
+        &self,
+        delegate: T::AccountId,
+        proxy_type: ProxyType,
+        delay: ProxyBlockNumber,
+    ) -> ApiResult<
+        ProxyAddProxyXt<ProxyType, SignedExtraOf<T>, T::AccountId, T::Address, T::Signature>,
+    > {
+        try_compose_extrinsic!(
+            self,
+            PROXY_MODULE,
+            PROXY_ADD_PROXY,
+            delegate,
+            proxy_type,
+            delay
+        )
+    }
+
+    /// Unregister `delegate` as proxy of the signer.
+    pub fn proxy_remove_proxy<ProxyType: Encode + Clone>(
+        &self,
+        delegate: T::AccountId,
+        proxy_type: ProxyType,
+        delay: ProxyBlockNumber,
+    ) -> ApiResult<
+        ProxyRemoveProxyXt<ProxyType, SignedExtraOf<T>, T::AccountId, T::Address, T::Signature>,
+    > {
+        try_compose_extrinsic!(
+            self,
+            PROXY_MODULE,
+            PROXY_REMOVE_PROXY,
+            delegate,
+            proxy_type,
+            delay
+        )
+    }
+
+    /// Spawn a fresh pure (keyless) account with the signer as proxy.
+    ///
+    /// Uses `create_pure` or, on older runtimes, `anonymous` depending on what the metadata
+    /// offers. The address of the new account can be derived with [`pure_proxy_account`].
+    pub fn proxy_create_pure<ProxyType: Encode + Clone>(
+        &self,
+        proxy_type: ProxyType,
+        delay: ProxyBlockNumber,
+        index: u16,
+    ) -> ApiResult<ProxyCreatePureXt<ProxyType, SignedExtraOf<T>, T::Address, T::Signature>> {
+        let call_name = self.pure_proxy_call_name();
+        try_compose_extrinsic!(self, PROXY_MODULE, call_name, proxy_type, delay, index)
+    }
+
+    /// Announce the intention of the signer to dispatch the call with `call_hash` as proxy of
+    /// `real`, as required for proxies with a delay.
+    pub fn proxy_announce(
+        &self,
+        real: T::AccountId,
+        call_hash: Hash,
+    ) -> ApiResult<ProxyAnnounceXt<SignedExtraOf<T>, T::AccountId, T::Address, T::Signature>> {
+        try_compose_extrinsic!(self, PROXY_MODULE, PROXY_ANNOUNCE, real, call_hash)
+    }
+
+    fn pure_proxy_call_name(&self) -> &'static str {
+        let has_create_pure = self
+            .metadata
+            .pallet(PROXY_MODULE)
+            .map(|pallet| pallet.calls.contains_key(PROXY_CREATE_PURE))
+            .unwrap_or(false);
+        if has_create_pure {
+            PROXY_CREATE_PURE
+        } else {
+            PROXY_ANONYMOUS
+        }
+    }
+}
+
+/// Hash of a call as expected by `Proxy.announce`.
+pub fn proxy_call_hash<Call: Encode>(call: &Call) -> Hash {
+    Hash::from(blake2_256(&call.encode()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::crypto::Ss58Codec;
+
+    #[test]
+    fn pure_proxy_account_matches_pallet_derivation() {
+        // Alice spawning a pure proxy of type `Any` (index 0 in the node-template runtime) with
+        // index 0 in extrinsic 1 of block 12.
+        let alice =
+            AccountId::from_ss58check("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+        let pure = pure_proxy_account(&alice, &0u8, 0, 12, 1);
+        assert_eq!(
+            hex::encode(AsRef::<[u8]>::as_ref(&pure)),
+            "d2add0c3af2e83ddb7edbdf9e7d86e8a9c6928bcb5a2ee8f4a9e5bb4f8dd3377"
+        );
+        // A different extrinsic index yields a different account.
+        assert_ne!(pure_proxy_account(&alice, &0u8, 0, 12, 2), pure);
+    }
+}
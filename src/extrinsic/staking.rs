@@ -17,12 +17,14 @@
 
 //! Extrinsics for `pallet-staking`.
 
-use crate::{Api, RpcClient};
-use ac_compose_macros::compose_extrinsic;
-use ac_primitives::{Balance, CallIndex, GenericAddress, UncheckedExtrinsicV4};
+use crate::{Api, ApiResult, RpcClient};
+use ac_compose_macros::try_compose_extrinsic;
+use ac_primitives::{
+    AccountId, Balance, CallIndex, Config, GenericAddress, SignedExtraOf, Signer,
+    UncheckedExtrinsicV4,
+};
 use codec::Compact;
-use sp_core::Pair;
-use sp_runtime::{MultiSignature, MultiSigner};
+use sp_runtime::MultiSignature;
 
 pub use staking::RewardDestination;
 
@@ -36,45 +38,59 @@ const STAKING_NOMINATE: &str = "nominate";
 const STAKING_CHILL: &str = "chill";
 const STAKING_SET_CONTROLLER: &str = "set_controller";
 
-pub type StakingBondFn = (
+pub type StakingBondFn<Address = GenericAddress, Account = AccountId> = (
     CallIndex,
-    GenericAddress,
+    Address,
     Compact<Balance>,
-    RewardDestination<GenericAddress>,
+    RewardDestination<Account>,
 );
 pub type StakingBondExtraFn = (CallIndex, Compact<Balance>);
 pub type StakingUnbondFn = (CallIndex, Compact<Balance>);
 pub type StakingRebondFn = (CallIndex, Compact<Balance>);
 pub type StakingWithdrawUnbondedFn = (CallIndex, u32);
-pub type StakingNominateFn = (CallIndex, Vec<GenericAddress>);
+pub type StakingNominateFn<Address = GenericAddress> = (CallIndex, Vec<Address>);
 pub type StakingChillFn = CallIndex;
-pub type StakingSetControllerFn = (CallIndex, GenericAddress);
-
-pub type StakingBondXt = UncheckedExtrinsicV4<StakingBondFn>;
-pub type StakingBondExtraXt = UncheckedExtrinsicV4<StakingBondExtraFn>;
-pub type StakingUnbondXt = UncheckedExtrinsicV4<StakingUnbondFn>;
-pub type StakingRebondXt = UncheckedExtrinsicV4<StakingRebondFn>;
-pub type StakingWithdrawUnbondedXt = UncheckedExtrinsicV4<StakingWithdrawUnbondedFn>;
-pub type StakingNominateXt = UncheckedExtrinsicV4<StakingNominateFn>;
-pub type StakingChillXt = UncheckedExtrinsicV4<StakingChillFn>;
-pub type StakingSetControllerXt = UncheckedExtrinsicV4<StakingSetControllerFn>;
+pub type StakingSetControllerFn<Address = GenericAddress> = (CallIndex, Address);
+
+pub type StakingBondXt<
+    SignedExtra,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+    Account = AccountId,
+> = UncheckedExtrinsicV4<StakingBondFn<Address, Account>, SignedExtra, Address, Signature>;
+pub type StakingBondExtraXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<StakingBondExtraFn, SignedExtra, Address, Signature>;
+pub type StakingUnbondXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<StakingUnbondFn, SignedExtra, Address, Signature>;
+pub type StakingRebondXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<StakingRebondFn, SignedExtra, Address, Signature>;
+pub type StakingWithdrawUnbondedXt<
+    SignedExtra,
+    Address = GenericAddress,
+    Signature = MultiSignature,
+> = UncheckedExtrinsicV4<StakingWithdrawUnbondedFn, SignedExtra, Address, Signature>;
+pub type StakingNominateXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<StakingNominateFn<Address>, SignedExtra, Address, Signature>;
+pub type StakingChillXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<StakingChillFn, SignedExtra, Address, Signature>;
+pub type StakingSetControllerXt<SignedExtra, Address = GenericAddress, Signature = MultiSignature> =
+    UncheckedExtrinsicV4<StakingSetControllerFn<Address>, SignedExtra, Address, Signature>;
 
 // https://polkadot.js.org/docs/substrate/extrinsics#staking
-impl<P, Client> Api<P, Client>
+impl<P, Client, T> Api<P, Client, T>
 where
-    P: Pair,
-    MultiSignature: From<P::Signature>,
-    MultiSigner: From<P::Public>,
+    P: Signer<AccountId = T::AccountId, Address = T::Address, Signature = T::Signature>,
     Client: RpcClient,
+    T: Config,
 {
     /// Bond `value` amount to `controller`
     pub fn staking_bond(
         &self,
-        controller: GenericAddress,
+        controller: T::Address,
         value: Balance,
-        payee: RewardDestination<GenericAddress>,
-    ) -> StakingBondXt {
-        compose_extrinsic!(
+        payee: RewardDestination<T::AccountId>,
+    ) -> ApiResult<StakingBondXt<SignedExtraOf<T>, T::Address, T::Signature, T::AccountId>> {
+        try_compose_extrinsic!(
             self,
             STAKING_MODULE,
             STAKING_BOND,
@@ -85,27 +101,39 @@ where
     }
 
     /// Bonds extra funds from the stash's free balance to the balance for staking.
-    pub fn staking_bond_extra(&self, value: Balance) -> StakingBondExtraXt {
-        compose_extrinsic!(self, STAKING_MODULE, STAKING_BOND_EXTRA, Compact(value))
+    pub fn staking_bond_extra(
+        &self,
+        value: Balance,
+    ) -> ApiResult<StakingBondExtraXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(self, STAKING_MODULE, STAKING_BOND_EXTRA, Compact(value))
     }
 
     /// Unbond `value` portion of the stash.
     /// If `value` is less than the minimum required, then the entire amount is unbound.
     /// Must be signed by the controller of the stash.
-    pub fn staking_unbond(&self, value: Balance) -> StakingUnbondXt {
-        compose_extrinsic!(self, STAKING_MODULE, STAKING_UNBOND, Compact(value))
+    pub fn staking_unbond(
+        &self,
+        value: Balance,
+    ) -> ApiResult<StakingUnbondXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(self, STAKING_MODULE, STAKING_UNBOND, Compact(value))
     }
 
     /// Rebond `value` portion of the current amount that is in the process of unbonding.
-    pub fn staking_rebond(&self, value: Balance) -> StakingRebondXt {
-        compose_extrinsic!(self, STAKING_MODULE, STAKING_REBOND, Compact(value))
+    pub fn staking_rebond(
+        &self,
+        value: Balance,
+    ) -> ApiResult<StakingRebondXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(self, STAKING_MODULE, STAKING_REBOND, Compact(value))
     }
 
     /// Free the balance of the stash so the stash account can do whatever it wants.
     /// Must be signed by the controller of the stash and called when EraElectionStatus is Closed.
     /// For most users, `num_slashing_spans` should be 0.
-    pub fn staking_withdraw_unbonded(&self, num_slashing_spans: u32) -> StakingWithdrawUnbondedXt {
-        compose_extrinsic!(
+    pub fn staking_withdraw_unbonded(
+        &self,
+        num_slashing_spans: u32,
+    ) -> ApiResult<StakingWithdrawUnbondedXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(
             self,
             STAKING_MODULE,
             STAKING_WITHDRAW_UNBONDED,
@@ -115,19 +143,27 @@ where
 
     /// Nominate `targets` as validators.
     /// Must be signed by the controller of the stash and called when EraElectionStatus is Closed.
-    pub fn staking_nominate(&self, targets: Vec<GenericAddress>) -> StakingNominateXt {
-        compose_extrinsic!(self, STAKING_MODULE, STAKING_NOMINATE, targets)
+    pub fn staking_nominate(
+        &self,
+        targets: Vec<T::Address>,
+    ) -> ApiResult<StakingNominateXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(self, STAKING_MODULE, STAKING_NOMINATE, targets)
     }
 
     /// Stop nominating por validating. Effects take place in the next era
-    pub fn staking_chill(&self) -> StakingChillXt {
-        compose_extrinsic!(self, STAKING_MODULE, STAKING_CHILL)
+    pub fn staking_chill(
+        &self,
+    ) -> ApiResult<StakingChillXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(self, STAKING_MODULE, STAKING_CHILL)
     }
 
     /// (Re-)set the controller of the stash
     /// Effects will be felt at the beginning of the next era.
     /// Must be Signed by the stash, not the controller.
-    pub fn staking_set_controller(&self, controller: GenericAddress) -> StakingSetControllerXt {
-        compose_extrinsic!(self, STAKING_MODULE, STAKING_SET_CONTROLLER, controller)
+    pub fn staking_set_controller(
+        &self,
+        controller: T::Address,
+    ) -> ApiResult<StakingSetControllerXt<SignedExtraOf<T>, T::Address, T::Signature>> {
+        try_compose_extrinsic!(self, STAKING_MODULE, STAKING_SET_CONTROLLER, controller)
     }
 }
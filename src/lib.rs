@@ -33,6 +33,15 @@ pub use ac_primitives::*;
 pub use ac_node_api::*;
 
 #[cfg(feature = "std")]
-pub use ac_compose_macros::compose_extrinsic;
+pub use ac_compose_macros::{compose_extrinsic, try_compose_extrinsic};
 
-pub use ac_compose_macros::{compose_call, compose_extrinsic_offline};
+pub use ac_compose_macros::{
+    compose_call, compose_call_checked, compose_extrinsic_offline, try_compose_call,
+    try_compose_extrinsic_offline,
+};
+
+#[cfg(feature = "codegen")]
+pub use ac_codegen_macro::runtime_metadata;
+
+// Used by the code generated with `runtime_metadata`.
+pub use codec;
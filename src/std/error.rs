@@ -1,5 +1,6 @@
 use crate::std::rpc::XtStatus;
 use ac_node_api::metadata::{InvalidMetadataError, MetadataError};
+use ac_primitives::{SignedExtensionError, SignerError};
 
 pub type ApiResult<T> = Result<T, Error>;
 
@@ -13,6 +14,12 @@ pub enum Error {
     MetadataFetch,
     #[error("Operation needs a signer to be set in the api")]
     NoSigner,
+    #[error("Operation needs a proxy to be set in the api")]
+    NoProxy,
+    #[error("Operation needs a connection to a node, but the api is offline")]
+    Offline,
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
     #[cfg(feature = "ws-client")]
     #[error("WebSocket Error: {0}")]
     WebSocket(#[from] ws::Error),
@@ -24,7 +31,10 @@ pub enum Error {
     Metadata(MetadataError),
     #[error("InvalidMetadata: {0:?}")]
     InvalidMetadata(InvalidMetadataError),
-    #[cfg(feature = "ws-client")]
+    #[error(transparent)]
+    Signer(#[from] SignerError),
+    #[error("Signed extension error: {0}")]
+    SignedExtension(#[from] SignedExtensionError),
     #[error("Events Error: {0:?}")]
     NodeApi(ac_node_api::error::Error),
     #[error("Error decoding storage value: {0}")]
@@ -35,6 +45,12 @@ pub enum Error {
     Deserializing(#[from] serde_json::Error),
     #[error("UnsupportedXtStatus Error: Can only wait for finalized, in block, broadcast and ready. Waited for: {0:?}")]
     UnsupportedXtStatus(XtStatus),
+    #[error("Extrinsic has not been included in a block")]
+    NotInBlock,
+    #[error("Block {0} including the extrinsic got retracted from the best chain")]
+    Retracted(String),
+    #[error("The node does not know the header of block {0}")]
+    HeaderNotFound(String),
     #[error("Error converting NumberOrHex to Balance")]
     TryFromIntError,
     #[error(transparent)]
@@ -53,7 +69,6 @@ impl From<MetadataError> for Error {
     }
 }
 
-#[cfg(feature = "ws-client")]
 impl From<ac_node_api::error::Error> for Error {
     fn from(error: ac_node_api::error::Error) -> Self {
         Error::NodeApi(error)
@@ -0,0 +1,194 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+       http://www.apache.org/licenses/LICENSE-2.0
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! `Signer` that delegates signing to a separate process over a unix domain socket, so the
+//! secret key never enters the address space of the api client.
+//!
+//! Every request is sent over a fresh connection as a SCALE encoded [`SignerRequest`], answered
+//! by a SCALE encoded [`SignerResponse`]. Both are prefixed with their length as little endian
+//! `u32`. [`serve`] implements the signing side for any other `Signer`.
+
+use ac_primitives::{AccountId, GenericAddress, Signer, SignerError};
+use codec::{Decode, Encode};
+use log::{debug, warn};
+use sp_runtime::MultiSignature;
+use std::{
+    io::{self, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+};
+
+/// Upper bound for a frame, to not allocate arbitrary amounts of memory on a corrupt length.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+pub enum SignerRequest {
+    /// Ask for the account the signer signs for.
+    AccountId,
+    /// Sign the payload.
+    Sign(Vec<u8>),
+}
+
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+pub enum SignerResponse {
+    AccountId(AccountId),
+    Signature(MultiSignature),
+    /// The signing process refused or failed to handle the request.
+    Error(Vec<u8>),
+}
+
+/// Client side of the signing socket.
+#[derive(Clone, Debug)]
+pub struct ExternalSigner {
+    socket: PathBuf,
+    account_id: AccountId,
+}
+
+impl ExternalSigner {
+    /// Connect to the signing process listening at `socket` and query its account.
+    pub fn connect<S: AsRef<Path>>(socket: S) -> io::Result<Self> {
+        let socket = socket.as_ref().to_path_buf();
+        let account_id = match request(&socket, &SignerRequest::AccountId)? {
+            SignerResponse::AccountId(account_id) => account_id,
+            response => return Err(unexpected_response(response)),
+        };
+        debug!("external signer at {:?} signs for {}", socket, account_id);
+        Ok(Self { socket, account_id })
+    }
+
+    fn request_signature(&self, payload: &[u8]) -> io::Result<MultiSignature> {
+        match request(&self.socket, &SignerRequest::Sign(payload.to_vec()))? {
+            SignerResponse::Signature(signature) => Ok(signature),
+            response => Err(unexpected_response(response)),
+        }
+    }
+}
+
+impl Signer for ExternalSigner {
+    type AccountId = AccountId;
+    type Address = GenericAddress;
+    type Signature = MultiSignature;
+
+    fn account_id(&self) -> AccountId {
+        self.account_id.clone()
+    }
+
+    /// Panics if the signing process is not reachable or refuses to sign. Use
+    /// [`Signer::try_sign`] to handle these cases.
+    fn sign(&self, payload: &[u8]) -> MultiSignature {
+        self.try_sign(payload).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn try_sign(&self, payload: &[u8]) -> Result<MultiSignature, SignerError> {
+        self.request_signature(payload)
+            .map_err(|e| SignerError(format!("external signer: {}", e)))
+    }
+}
+
+/// Answer the requests of `ExternalSigner`s connecting to `listener` with `signer`.
+///
+/// Runs until accepting a connection fails. Errors of a single connection are logged and do
+/// not stop the server.
+pub fn serve<S: Signer<AccountId = AccountId, Signature = MultiSignature>>(
+    listener: &UnixListener,
+    signer: &S,
+) -> io::Result<()> {
+    loop {
+        let (mut stream, _) = listener.accept()?;
+        if let Err(e) = handle_connection(&mut stream, signer) {
+            warn!("external signer connection failed: {}", e);
+        }
+    }
+}
+
+fn handle_connection<S: Signer<AccountId = AccountId, Signature = MultiSignature>>(
+    stream: &mut UnixStream,
+    signer: &S,
+) -> io::Result<()> {
+    let response = match SignerRequest::decode(&mut read_frame(stream)?.as_slice()) {
+        Ok(SignerRequest::AccountId) => SignerResponse::AccountId(signer.account_id()),
+        Ok(SignerRequest::Sign(payload)) => match signer.try_sign(&payload) {
+            Ok(signature) => SignerResponse::Signature(signature),
+            Err(e) => SignerResponse::Error(e.to_string().into_bytes()),
+        },
+        Err(e) => SignerResponse::Error(e.to_string().into_bytes()),
+    };
+    write_frame(stream, &response.encode())
+}
+
+fn request(socket: &Path, request: &SignerRequest) -> io::Result<SignerResponse> {
+    let mut stream = UnixStream::connect(socket)?;
+    write_frame(&mut stream, &request.encode())?;
+    SignerResponse::decode(&mut read_frame(&mut stream)?.as_slice())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn unexpected_response(response: SignerResponse) -> io::Error {
+    let msg = match response {
+        SignerResponse::Error(msg) => String::from_utf8_lossy(&msg).into_owned(),
+        response => format!("unexpected response: {:?}", response),
+    };
+    io::Error::new(io::ErrorKind::Other, msg)
+}
+
+fn write_frame(stream: &mut UnixStream, frame: &[u8]) -> io::Result<()> {
+    stream.write_all(&(frame.len() as u32).to_le_bytes())?;
+    stream.write_all(frame)?;
+    stream.flush()
+}
+
+fn read_frame(stream: &mut UnixStream) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds the limit", len),
+        ));
+    }
+    let mut frame = vec![0u8; len];
+    stream.read_exact(&mut frame)?;
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::{sr25519, Pair};
+    use sp_runtime::traits::Verify;
+
+    #[test]
+    fn external_signer_signs_with_the_served_pair() {
+        let dir = std::env::temp_dir().join(format!("external-signer-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("signer.sock");
+        let _ = std::fs::remove_file(&socket);
+
+        let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+        let listener = UnixListener::bind(&socket).unwrap();
+        let served = pair.clone();
+        std::thread::spawn(move || serve(&listener, &served));
+
+        let signer = ExternalSigner::connect(&socket).unwrap();
+        assert_eq!(Signer::account_id(&signer), Signer::account_id(&pair));
+
+        let signature = Signer::sign(&signer, b"payload");
+        assert!(signature.verify(&b"payload"[..], &Signer::account_id(&pair)));
+
+        std::fs::remove_file(&socket).unwrap();
+        assert!(Signer::try_sign(&signer, b"payload").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! On-disk cache of the runtime metadata, so that `Api::new_with_metadata_cache` does not
+//! need to download it on every start.
+//!
+//! An entry is stored per genesis hash and spec version, as the SCALE encoded
+//! `VersionedMetadata`: V14 in the format of `state_getMetadata`, or V15 if the node offers it.
+//! A runtime upgrade bumps the spec version and thereby misses the cache.
+
+use ac_node_api::v15::VersionedMetadata;
+use codec::{Decode, Encode};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Outcome of looking up the metadata in the cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetadataCacheStatus {
+    /// The metadata was read from the cache.
+    Hit,
+    /// There was no entry, the metadata was fetched from the node and stored.
+    Miss,
+    /// The entry could not be read or decoded, the metadata was fetched from the node and the
+    /// entry replaced.
+    Invalid,
+}
+
+/// Directory with cached metadata.
+#[derive(Clone, Debug)]
+pub struct MetadataCache {
+    dir: PathBuf,
+}
+
+impl MetadataCache {
+    /// Cache in `dir`, which is created on the first store.
+    pub fn new<D: Into<PathBuf>>(dir: D) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File of the entry for the chain with `genesis_hash` at `spec_version`.
+    pub fn entry_path<Hash: AsRef<[u8]>>(&self, genesis_hash: &Hash, spec_version: u32) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{}.scale",
+            hex::encode(genesis_hash.as_ref()),
+            spec_version
+        ))
+    }
+
+    /// Reads the entry. `Ok(None)` if there is none, an error if it can't be read or decoded.
+    pub fn load<Hash: AsRef<[u8]>>(
+        &self,
+        genesis_hash: &Hash,
+        spec_version: u32,
+    ) -> io::Result<Option<VersionedMetadata>> {
+        let bytes = match fs::read(self.entry_path(genesis_hash, spec_version)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        VersionedMetadata::decode(&mut bytes.as_slice())
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Writes the entry, replacing an existing one.
+    ///
+    /// The file is written under a temporary name first, so that concurrent jobs never read a
+    /// partially written entry.
+    pub fn store<Hash: AsRef<[u8]>>(
+        &self,
+        genesis_hash: &Hash,
+        spec_version: u32,
+        metadata: &VersionedMetadata,
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(genesis_hash, spec_version);
+        let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+        fs::write(&tmp, metadata.encode())?;
+        fs::rename(&tmp, &path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ac_node_api::test_utils;
+    use metadata::META_RESERVED;
+
+    #[test]
+    fn stores_and_loads_entries_per_spec_version() {
+        let dir = std::env::temp_dir().join(format!("metadata-cache-{}", std::process::id()));
+        let cache = MetadataCache::new(&dir);
+        let genesis_hash = [1u8; 32];
+        let metadata = VersionedMetadata::V14(META_RESERVED, test_utils::runtime_metadata(vec![]));
+
+        assert!(cache.load(&genesis_hash, 100).unwrap().is_none());
+        cache.store(&genesis_hash, 100, &metadata).unwrap();
+        assert_eq!(cache.load(&genesis_hash, 100).unwrap(), Some(metadata));
+        assert!(cache.load(&genesis_hash, 101).unwrap().is_none());
+
+        fs::write(cache.entry_path(&genesis_hash, 101), b"garbage").unwrap();
+        assert!(cache.load(&genesis_hash, 101).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,30 @@
 pub use crate::std::error::{ApiResult, Error as ApiClientError};
+#[cfg(unix)]
+pub use crate::std::external_signer::ExternalSigner;
+pub use crate::std::metadata_cache::{MetadataCache, MetadataCacheStatus};
+pub use crate::std::offline::{OfflineApi, OfflineClient};
 pub use crate::std::rpc::XtStatus;
 pub use crate::utils::FromHexString;
-use ac_node_api::metadata::{Metadata, MetadataError};
-use ac_primitives::{AccountData, AccountInfo, Balance, ExtrinsicParams};
+use ac_node_api::error::RuntimeError;
+use ac_node_api::events::{EventRecords, EventsDecoder};
+use ac_node_api::extrinsics::{DecodedExtrinsic, ExtrinsicDecoder};
+use ac_node_api::metadata::{Metadata, MetadataError, StaticConstantAddress};
+use ac_node_api::storage::StaticStorageAddress;
+use ac_node_api::v15::{VersionedMetadata, SUPPORTED_METADATA_VERSIONS};
+use ac_node_api::Encoded;
+use ac_primitives::{
+    AccountInfoOf, Balance, Config, ExtrinsicParams, ExtrinsicParamsBuilderOf, Signer,
+};
 pub use metadata::RuntimeMetadataPrefixed;
 pub use serde_json::Value;
 pub use sp_core::crypto::Pair;
 pub use sp_core::storage::StorageKey;
-use sp_core::H256 as Hash;
 pub use sp_runtime::traits::{Block, Header};
+use sp_runtime::traits::{CheckedAdd, One};
+pub use sp_runtime::transaction_validity::{
+    TransactionSource, TransactionValidity, TransactionValidityError, ValidTransaction,
+};
+use sp_runtime::{generic, ApplyExtrinsicResult, OpaqueExtrinsic};
 pub use sp_runtime::{
     generic::SignedBlock, traits::IdentifyAccount, AccountId32 as AccountId, MultiSignature,
     MultiSigner,
@@ -18,24 +34,38 @@ pub use sp_version::RuntimeVersion;
 pub use transaction_payment::FeeDetails;
 
 pub mod error;
+#[cfg(unix)]
+pub mod external_signer;
+pub mod metadata_cache;
+pub mod offline;
 pub mod rpc;
 
 use std::convert::{TryFrom, TryInto};
 
-use codec::{Decode, Encode};
-use log::{debug, info};
+use codec::{Decode, DecodeAll, Encode};
+use log::{debug, info, warn};
 use serde::de::DeserializeOwned;
 use sp_rpc::number::NumberOrHex;
 use transaction_payment::{InclusionFee, RuntimeDispatchInfo};
 
+use crate::extrinsic::proxy::ProxyConfig;
 use crate::rpc::json_req;
+use crate::utils;
+
+/// Outcome of [`Api::dry_run`]: the `ApplyExtrinsicResult` of the runtime, with the
+/// `DispatchError` decoded into a `RuntimeError`.
+pub type DryRunResult = Result<Result<(), RuntimeError>, TransactionValidityError>;
 
 pub trait RpcClient {
     /// Sends a RPC request that returns a String
     fn get_request(&self, jsonreq: serde_json::Value) -> ApiResult<String>;
 
-    /// Send a RPC request that returns a SHA256 hash
-    fn send_extrinsic(&self, xthex_prefixed: String, exit_on: XtStatus) -> ApiResult<Option<Hash>>;
+    /// Send a RPC request that returns the hash of the extrinsic
+    fn send_extrinsic<Hash: Decode>(
+        &self,
+        xthex_prefixed: String,
+        exit_on: XtStatus,
+    ) -> ApiResult<Option<Hash>>;
 }
 
 /// Api to talk with substrate-nodes
@@ -47,8 +77,9 @@ pub trait RpcClient {
 /// ```no_run
 /// use substrate_api_client::rpc::json_req::author_submit_extrinsic;
 /// use substrate_api_client::{
-///     Api, ApiClientError, ApiResult, FromHexString, Hash, RpcClient, Value, XtStatus, PlainTipExtrinsicParams
+///     decode_hex, Api, ApiClientError, ApiResult, NodeTemplateConfig, RpcClient, Value, XtStatus,
 /// };
+/// use codec::Decode;
 /// struct MyClient {
 ///     // pick any request crate, such as ureq::Agent
 ///     _inner: (),
@@ -79,7 +110,7 @@ pub trait RpcClient {
 ///             .map_err(|err| ApiClientError::RpcClient(err.to_string()))
 ///     }
 ///
-///     fn send_extrinsic(
+///     fn send_extrinsic<Hash: Decode>(
 ///         &self,
 ///         xthex_prefixed: String,
 ///         _exit_on: XtStatus,
@@ -88,67 +119,104 @@ pub trait RpcClient {
 ///         let res: String = self
 ///             .send_json("".into(), jsonreq)
 ///             .map_err(|err| ApiClientError::RpcClient(err.to_string()))?;
-///         Ok(Some(Hash::from_hex(res)?))
+///         Ok(Some(decode_hex(res)?))
 ///     }
 /// }
 ///
 /// let client = MyClient::new();
-/// let _api = Api::<(), _, PlainTipExtrinsicParams>::new(client);
+/// let _api = Api::<(), _, NodeTemplateConfig>::new(client);
 ///
 /// ```
 #[derive(Clone)]
-pub struct Api<P, Client, Params>
+pub struct Api<P, Client, T>
 where
     Client: RpcClient,
-    Params: ExtrinsicParams,
+    T: Config,
 {
     pub signer: Option<P>,
-    pub genesis_hash: Hash,
+    pub genesis_hash: T::Hash,
     pub metadata: Metadata,
     pub runtime_version: RuntimeVersion,
     client: Client,
-    pub extrinsic_params_builder: Option<Params::OtherParams>,
+    pub extrinsic_params_builder: Option<ExtrinsicParamsBuilderOf<T>>,
+    /// Whether the metadata came from the cache, `None` if the api was created without one.
+    pub metadata_cache_status: Option<MetadataCacheStatus>,
+    /// Cache for the metadata of other spec versions, e.g. to decode the events of old blocks.
+    metadata_cache: Option<MetadataCache>,
+    /// Nonce of the signer used instead of the one on chain, only set by
+    /// `OfflineApi::set_nonce`.
+    nonce: Option<T::Index>,
+    /// Proxy applied by `proxied`, see `set_proxy`.
+    pub proxy: Option<ProxyConfig<Encoded, T::AccountId>>,
 }
 
-impl<P, Client, Params> Api<P, Client, Params>
+impl<P, Client, T> Api<P, Client, T>
 where
-    P: Pair,
-    MultiSignature: From<P::Signature>,
-    MultiSigner: From<P::Public>,
+    P: Signer<AccountId = T::AccountId, Address = T::Address, Signature = T::Signature>,
     Client: RpcClient,
-    Params: ExtrinsicParams,
+    T: Config,
 {
-    pub fn signer_account(&self) -> Option<AccountId> {
-        let pair = self.signer.as_ref()?;
-        let multi_signer = MultiSigner::from(pair.public());
-        Some(multi_signer.into_account())
+    pub fn signer_account(&self) -> Option<T::AccountId> {
+        self.signer.as_ref().map(Signer::account_id)
     }
 
-    pub fn get_nonce(&self) -> ApiResult<u32> {
+    pub fn get_nonce(&self) -> ApiResult<T::Index> {
         if self.signer.is_none() {
             return Err(ApiClientError::NoSigner);
         }
+        if let Some(nonce) = self.nonce {
+            return Ok(nonce);
+        }
 
         self.get_account_info(&self.signer_account().unwrap())
-            .map(|acc_opt| acc_opt.map_or_else(|| 0, |acc| acc.nonce))
+            .map(|acc_opt| acc_opt.map_or_else(Default::default, |acc| acc.nonce))
     }
 }
 
-impl<P, Client, Params> Api<P, Client, Params>
+impl<P, Client, T> Api<P, Client, T>
 where
     Client: RpcClient,
-    Params: ExtrinsicParams,
+    T: Config,
 {
     pub fn new(client: Client) -> ApiResult<Self> {
+        Self::_new(client, None)
+    }
+
+    /// Like `new`, but reads the metadata from `cache` if it has an entry for the genesis hash
+    /// and the current spec version of the node. Otherwise the metadata is fetched and stored
+    /// in the cache. Failing to read or write the cache is logged, but not an error.
+    pub fn new_with_metadata_cache(client: Client, cache: &MetadataCache) -> ApiResult<Self> {
+        Self::_new(client, Some(cache))
+    }
+
+    fn _new(client: Client, cache: Option<&MetadataCache>) -> ApiResult<Self> {
         let genesis_hash = Self::_get_genesis_hash(&client)?;
         info!("Got genesis hash: {:?}", genesis_hash);
 
-        let metadata = Self::_get_metadata(&client).map(Metadata::try_from)??;
-        debug!("Metadata: {:?}", metadata);
-
-        let runtime_version = Self::_get_runtime_version(&client)?;
+        // Fetch the runtime version and metadata at the same block, so that they match even if
+        // a runtime upgrade is enacted meanwhile.
+        let best_hash = Self::_get_best_block_hash(&client)?;
+        let runtime_version = Self::_get_runtime_version(&client, Some(best_hash))?;
         info!("Runtime Version: {:?}", runtime_version);
 
+        let (metadata, metadata_cache_status) = match cache {
+            Some(cache) => {
+                let (metadata, status) = Self::_get_cached_metadata(
+                    &client,
+                    cache,
+                    &genesis_hash,
+                    runtime_version.spec_version,
+                    best_hash,
+                )?;
+                (metadata, Some(status))
+            }
+            None => (
+                Metadata::try_from(Self::_get_versioned_metadata(&client, Some(best_hash))?)?,
+                None,
+            ),
+        };
+        debug!("Metadata: {:?}", metadata);
+
         Ok(Self {
             signer: None,
             genesis_hash,
@@ -156,32 +224,92 @@ where
             runtime_version,
             client,
             extrinsic_params_builder: None,
+            metadata_cache_status,
+            metadata_cache: cache.cloned(),
+            nonce: None,
+            proxy: None,
         })
     }
 
+    /// Metadata of `spec_version`, the spec version at `at_block`, from the cache or fetched at
+    /// `at_block` and stored. The metadata must not be fetched at another block, which may have
+    /// another spec version.
+    fn _get_cached_metadata(
+        client: &Client,
+        cache: &MetadataCache,
+        genesis_hash: &T::Hash,
+        spec_version: u32,
+        at_block: T::Hash,
+    ) -> ApiResult<(Metadata, MetadataCacheStatus)> {
+        let status = match cache
+            .load(genesis_hash, spec_version)
+            .map(|cached| cached.map(Metadata::try_from))
+        {
+            Ok(Some(Ok(metadata))) => {
+                info!("Metadata cache hit for spec version {}", spec_version);
+                return Ok((metadata, MetadataCacheStatus::Hit));
+            }
+            Ok(Some(Err(e))) => {
+                warn!(
+                    "Invalid metadata in cache for spec version {}: {:?}",
+                    spec_version, e
+                );
+                MetadataCacheStatus::Invalid
+            }
+            Ok(None) => {
+                info!("Metadata cache miss for spec version {}", spec_version);
+                MetadataCacheStatus::Miss
+            }
+            Err(e) => {
+                warn!("Reading metadata cache failed: {}", e);
+                MetadataCacheStatus::Invalid
+            }
+        };
+        let versioned = Self::_get_versioned_metadata(client, Some(at_block))?;
+        let metadata = Metadata::try_from(versioned.clone())?;
+        if let Err(e) = cache.store(genesis_hash, spec_version, &versioned) {
+            warn!("Writing metadata cache failed: {}", e);
+        }
+        Ok((metadata, status))
+    }
+
     #[must_use]
     pub fn set_signer(mut self, signer: P) -> Self {
         self.signer = Some(signer);
         self
     }
 
-    pub fn set_extrinsic_params_builder(mut self, extrinsic_params: Params::OtherParams) -> Self {
+    pub fn set_extrinsic_params_builder(
+        mut self,
+        extrinsic_params: ExtrinsicParamsBuilderOf<T>,
+    ) -> Self {
         self.extrinsic_params_builder = Some(extrinsic_params);
         self
     }
 
-    fn _get_genesis_hash(client: &Client) -> ApiResult<Hash> {
+    fn _get_genesis_hash(client: &Client) -> ApiResult<T::Hash> {
         let jsonreq = json_req::chain_get_genesis_hash();
         let genesis = Self::_get_request(client, jsonreq)?;
 
         match genesis {
-            Some(g) => Hash::from_hex(g).map_err(|e| e.into()),
+            Some(g) => decode_hex(g),
             None => Err(ApiClientError::Genesis),
         }
     }
 
-    fn _get_runtime_version(client: &Client) -> ApiResult<RuntimeVersion> {
-        let jsonreq = json_req::state_get_runtime_version();
+    fn _get_best_block_hash(client: &Client) -> ApiResult<T::Hash> {
+        let jsonreq = json_req::chain_get_block_hash::<T::BlockNumber>(None);
+        match Self::_get_request(client, jsonreq)? {
+            Some(hash) => decode_hex(hash),
+            None => Err(ApiClientError::HeaderNotFound("best".into())),
+        }
+    }
+
+    fn _get_runtime_version(
+        client: &Client,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<RuntimeVersion> {
+        let jsonreq = json_req::state_get_runtime_version_at(at_block);
         let version = Self::_get_request(client, jsonreq)?;
 
         match version {
@@ -190,8 +318,11 @@ where
         }
     }
 
-    fn _get_metadata(client: &Client) -> ApiResult<RuntimeMetadataPrefixed> {
-        let jsonreq = json_req::state_get_metadata();
+    fn _get_metadata(
+        client: &Client,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<RuntimeMetadataPrefixed> {
+        let jsonreq = json_req::state_get_metadata_at(at_block);
         let meta = Self::_get_request(client, jsonreq)?;
 
         if meta.is_none() {
@@ -201,6 +332,80 @@ where
         RuntimeMetadataPrefixed::decode(&mut metadata.as_slice()).map_err(|e| e.into())
     }
 
+    /// Metadata of the newest version both the node and we support, falling back to
+    /// `state_getMetadata` for nodes without the `Metadata_metadata_versions` runtime api.
+    /// Other errors, e.g. of the connection, are returned.
+    fn _get_versioned_metadata(
+        client: &Client,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<VersionedMetadata> {
+        let newest = Self::_try_get_metadata_versions(client, at_block)?.and_then(|versions| {
+            versions
+                .into_iter()
+                .filter(|version| SUPPORTED_METADATA_VERSIONS.contains(version))
+                .max()
+        });
+        if let Some(version) = newest {
+            if let Some(metadata) = Self::_get_metadata_at_version(client, version, at_block)? {
+                return Ok(metadata);
+            }
+        }
+        Ok(VersionedMetadata::try_from(Self::_get_metadata(
+            client, at_block,
+        )?)?)
+    }
+
+    fn _get_metadata_versions(client: &Client, at_block: Option<T::Hash>) -> ApiResult<Vec<u32>> {
+        Self::_try_get_metadata_versions(client, at_block)?.ok_or(ApiClientError::MetadataFetch)
+    }
+
+    /// The metadata versions, `None` if the runtime does not have the metadata runtime api.
+    fn _try_get_metadata_versions(
+        client: &Client,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<Vec<u32>>> {
+        match Self::_state_call(client, "Metadata_metadata_versions", &[], at_block)? {
+            Some(versions) => Ok(Some(Decode::decode(&mut versions.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn _get_metadata_at_version(
+        client: &Client,
+        version: u32,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<VersionedMetadata>> {
+        let res = Self::_state_call(
+            client,
+            "Metadata_metadata_at_version",
+            &version.encode(),
+            at_block,
+        )?;
+        // The runtime returns an `Option<OpaqueMetadata>`, i.e. the encoded metadata as bytes.
+        let opaque: Option<Vec<u8>> = match res {
+            Some(opaque) => Decode::decode(&mut opaque.as_slice())?,
+            None => return Ok(None),
+        };
+        match opaque {
+            Some(metadata) => Ok(Some(VersionedMetadata::decode(&mut metadata.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn _state_call(
+        client: &Client,
+        method: &str,
+        data: &[u8],
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<Vec<u8>>> {
+        let data_hex = format!("0x{}", hex::encode(data));
+        let jsonreq = json_req::state_call(method, &data_hex, at_block);
+        match Self::_get_request(client, jsonreq)? {
+            Some(bytes) => Ok(Some(Vec::from_hex(bytes)?)),
+            None => Ok(None),
+        }
+    }
+
     // low level access
     fn _get_request(client: &Client, jsonreq: Value) -> ApiResult<Option<String>> {
         let str = client.get_request(jsonreq)?;
@@ -211,54 +416,129 @@ where
         }
     }
 
-    pub fn extrinsic_params(&self, nonce: u32) -> Params {
-        let extrinsic_params_builder = self.extrinsic_params_builder.clone().unwrap_or_default();
-        <Params as ExtrinsicParams>::new(
+    /// Extrinsic params for `nonce`, with the signed extensions listed in the metadata.
+    ///
+    /// Panics if the params can't encode the signed extensions of the metadata. Use
+    /// `try_extrinsic_params` to get an error instead.
+    pub fn extrinsic_params(&self, nonce: T::Index) -> T::ExtrinsicParams {
+        match self.try_extrinsic_params(nonce) {
+            Ok(params) => params,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Extrinsic params for `nonce`, with the signed extensions listed in the metadata.
+    ///
+    /// Fails if the metadata lists no signed extensions, or one the params can't encode.
+    pub fn try_extrinsic_params(&self, nonce: T::Index) -> ApiResult<T::ExtrinsicParams> {
+        let extrinsic_params_builder =
+            <T::ExtrinsicParams as ExtrinsicParams>::with_signed_extensions(
+                self.extrinsic_params_builder.clone().unwrap_or_default(),
+                &self.metadata.signed_extension_identifiers(),
+            )?;
+        Ok(<T::ExtrinsicParams as ExtrinsicParams>::new(
             self.runtime_version.spec_version,
             self.runtime_version.transaction_version,
             nonce,
             self.genesis_hash,
             extrinsic_params_builder,
-        )
+        ))
     }
     pub fn get_metadata(&self) -> ApiResult<RuntimeMetadataPrefixed> {
-        Self::_get_metadata(&self.client)
+        Self::_get_metadata(&self.client, None)
+    }
+
+    /// Metadata of the newest version supported by both the node and this crate, see
+    /// `SUPPORTED_METADATA_VERSIONS`.
+    pub fn get_versioned_metadata(&self) -> ApiResult<VersionedMetadata> {
+        Self::_get_versioned_metadata(&self.client, None)
+    }
+
+    /// Metadata versions the runtime offers through `Metadata_metadata_at_version`.
+    pub fn get_metadata_versions(&self) -> ApiResult<Vec<u32>> {
+        Self::_get_metadata_versions(&self.client, None)
+    }
+
+    /// Metadata of the given version, `None` if the runtime does not offer it.
+    pub fn get_metadata_at_version(&self, version: u32) -> ApiResult<Option<VersionedMetadata>> {
+        Self::_get_metadata_at_version(&self.client, version, None)
+    }
+
+    /// Metadata of the runtime at `at_block`, or at the best block if `None`.
+    ///
+    /// This is the metadata of the api if the block has its spec version. Otherwise it is read
+    /// from the metadata cache of the api, if there is one, or fetched at the block.
+    pub fn get_metadata_at_block(&self, at_block: Option<T::Hash>) -> ApiResult<Metadata> {
+        let at_block = match at_block {
+            Some(hash) => hash,
+            None => Self::_get_best_block_hash(&self.client)?,
+        };
+        let spec_version = self.get_runtime_version(Some(at_block))?.spec_version;
+        self._get_metadata_of_spec_version(spec_version, at_block)
+    }
+
+    /// Metadata of `spec_version`, the spec version at `at_block`, fetched at `at_block` if it
+    /// is not the one of the api.
+    fn _get_metadata_of_spec_version(
+        &self,
+        spec_version: u32,
+        at_block: T::Hash,
+    ) -> ApiResult<Metadata> {
+        if spec_version == self.runtime_version.spec_version {
+            return Ok(self.metadata.clone());
+        }
+        match &self.metadata_cache {
+            Some(cache) => Self::_get_cached_metadata(
+                &self.client,
+                cache,
+                &self.genesis_hash,
+                spec_version,
+                at_block,
+            )
+            .map(|(metadata, _)| metadata),
+            None => Ok(Metadata::try_from(Self::_get_versioned_metadata(
+                &self.client,
+                Some(at_block),
+            )?)?),
+        }
+    }
+
+    /// Runtime version at `at_block`, or at the best block if `None`.
+    pub fn get_runtime_version(&self, at_block: Option<T::Hash>) -> ApiResult<RuntimeVersion> {
+        Self::_get_runtime_version(&self.client, at_block)
     }
 
     pub fn get_spec_version(&self) -> ApiResult<u32> {
-        Self::_get_runtime_version(&self.client).map(|v| v.spec_version)
+        self.get_runtime_version(None).map(|v| v.spec_version)
     }
 
-    pub fn get_genesis_hash(&self) -> ApiResult<Hash> {
+    pub fn get_genesis_hash(&self) -> ApiResult<T::Hash> {
         Self::_get_genesis_hash(&self.client)
     }
 
-    pub fn get_account_info(&self, address: &AccountId) -> ApiResult<Option<AccountInfo>> {
-        let storagekey: sp_core::storage::StorageKey =
-            self.metadata
-                .storage_map_key::<AccountId>("System", "Account", address.clone())?;
+    pub fn get_account_info(&self, address: &T::AccountId) -> ApiResult<Option<AccountInfoOf<T>>> {
+        let storagekey: sp_core::storage::StorageKey = self
+            .metadata
+            .storage_map_key("System", "Account", address)?;
 
         info!("storage key is: 0x{}", hex::encode(&storagekey));
         self.get_storage_by_key_hash(storagekey, None)
     }
 
-    pub fn get_account_data(&self, address: &AccountId) -> ApiResult<Option<AccountData>> {
+    pub fn get_account_data(&self, address: &T::AccountId) -> ApiResult<Option<T::AccountData>> {
         self.get_account_info(address)
             .map(|info| info.map(|i| i.data))
     }
 
-    pub fn get_finalized_head(&self) -> ApiResult<Option<Hash>> {
+    pub fn get_finalized_head(&self) -> ApiResult<Option<T::Hash>> {
         let h = self.get_request(json_req::chain_get_finalized_head())?;
         match h {
-            Some(hash) => Ok(Some(Hash::from_hex(hash)?)),
+            Some(hash) => Ok(Some(decode_hex(hash)?)),
             None => Ok(None),
         }
     }
 
-    pub fn get_header<H>(&self, hash: Option<Hash>) -> ApiResult<Option<H>>
-    where
-        H: Header + DeserializeOwned,
-    {
+    pub fn get_header(&self, hash: Option<T::Hash>) -> ApiResult<Option<T::Header>> {
         let h = self.get_request(json_req::chain_get_header(hash))?;
         match h {
             Some(hash) => Ok(Some(serde_json::from_str(&hash)?)),
@@ -266,22 +546,22 @@ where
         }
     }
 
-    pub fn get_block_hash(&self, number: Option<u32>) -> ApiResult<Option<Hash>> {
+    pub fn get_block_hash(&self, number: Option<T::BlockNumber>) -> ApiResult<Option<T::Hash>> {
         let h = self.get_request(json_req::chain_get_block_hash(number))?;
         match h {
-            Some(hash) => Ok(Some(Hash::from_hex(hash)?)),
+            Some(hash) => Ok(Some(decode_hex(hash)?)),
             None => Ok(None),
         }
     }
 
-    pub fn get_block<B>(&self, hash: Option<Hash>) -> ApiResult<Option<B>>
+    pub fn get_block<B>(&self, hash: Option<T::Hash>) -> ApiResult<Option<B>>
     where
         B: Block + DeserializeOwned,
     {
         Self::get_signed_block(self, hash).map(|sb_opt| sb_opt.map(|sb| sb.block))
     }
 
-    pub fn get_block_by_num<B>(&self, number: Option<u32>) -> ApiResult<Option<B>>
+    pub fn get_block_by_num<B>(&self, number: Option<T::BlockNumber>) -> ApiResult<Option<B>>
     where
         B: Block + DeserializeOwned,
     {
@@ -292,7 +572,7 @@ where
     /// The interval at which finality proofs are provided is set via the
     /// the `GrandpaConfig.justification_period` in a node's service.rs.
     /// The Justification may be none.
-    pub fn get_signed_block<B>(&self, hash: Option<Hash>) -> ApiResult<Option<SignedBlock<B>>>
+    pub fn get_signed_block<B>(&self, hash: Option<T::Hash>) -> ApiResult<Option<SignedBlock<B>>>
     where
         B: Block + DeserializeOwned,
     {
@@ -305,7 +585,7 @@ where
 
     pub fn get_signed_block_by_num<B>(
         &self,
-        number: Option<u32>,
+        number: Option<T::BlockNumber>,
     ) -> ApiResult<Option<SignedBlock<B>>>
     where
         B: Block + DeserializeOwned,
@@ -314,6 +594,89 @@ where
             .map(|h| self.get_signed_block(h))?
     }
 
+    /// Get the extrinsics of a block, decoded into pallet, call and named arguments with the
+    /// help of the metadata. In contrast to `get_block`, the runtime's concrete `Block` type is
+    /// not needed.
+    pub fn get_decoded_extrinsics(
+        &self,
+        hash: Option<T::Hash>,
+    ) -> ApiResult<Option<Vec<DecodedExtrinsic>>> {
+        let block = match self.get_block::<generic::Block<T::Header, OpaqueExtrinsic>>(hash)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let decoder = ExtrinsicDecoder::new(self.metadata.clone());
+        let extrinsics = block
+            .extrinsics
+            .iter()
+            .map(|xt| decoder.decode_extrinsic(&mut xt.encode().as_slice()))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(extrinsics))
+    }
+
+    pub fn get_decoded_extrinsics_by_num(
+        &self,
+        number: Option<T::BlockNumber>,
+    ) -> ApiResult<Option<Vec<DecodedExtrinsic>>> {
+        match self.get_block_hash(number)? {
+            Some(hash) => self.get_decoded_extrinsics(Some(hash)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the events of a block, read from the `System.Events` storage at `at_block`, or at
+    /// the best block if `None`.
+    ///
+    /// The events are decoded with the metadata of the runtime at the block, see
+    /// `get_metadata_at_block`, so that blocks before a runtime upgrade decode as well.
+    pub fn get_events(&self, at_block: Option<T::Hash>) -> ApiResult<EventRecords> {
+        let at_block = match at_block {
+            Some(hash) => hash,
+            None => Self::_get_best_block_hash(&self.client)?,
+        };
+        let metadata = self.get_metadata_at_block(Some(at_block))?;
+        self._get_events(at_block, metadata)
+    }
+
+    fn _get_events(&self, at_block: T::Hash, metadata: Metadata) -> ApiResult<EventRecords> {
+        let key = utils::storage_key("System", "Events");
+        match self.get_opaque_storage_by_key_hash(key, Some(at_block))? {
+            Some(events) => {
+                let decoder = EventsDecoder::new(metadata);
+                Ok(decoder.decode_event_records(&mut events.as_slice())?)
+            }
+            None => Ok(EventRecords::default()),
+        }
+    }
+
+    /// Get the events of the block with `number`, or `None` if there is no such block.
+    pub fn get_events_by_num(
+        &self,
+        number: Option<T::BlockNumber>,
+    ) -> ApiResult<Option<EventRecords>> {
+        match self.get_block_hash(number)? {
+            Some(hash) => self.get_events(Some(hash)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterate over the events of the blocks `from..=to`, e.g. to catch up on events missed
+    /// while not being subscribed. The iteration ends early at the first block number that
+    /// does not exist yet. Like `get_events`, every block is decoded with the metadata of its
+    /// runtime, which is only fetched again when the spec version changes.
+    pub fn events_between(
+        &self,
+        from: T::BlockNumber,
+        to: T::BlockNumber,
+    ) -> EventsBetween<'_, P, Client, T> {
+        EventsBetween {
+            api: self,
+            next: Some(from),
+            to,
+            metadata: None,
+        }
+    }
+
     pub fn get_request(&self, jsonreq: Value) -> ApiResult<Option<String>> {
         Self::_get_request(&self.client, jsonreq)
     }
@@ -322,7 +685,7 @@ where
         &self,
         storage_prefix: &'static str,
         storage_key_name: &'static str,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<V>> {
         let storagekey = self
             .metadata
@@ -336,7 +699,7 @@ where
         storage_prefix: &'static str,
         storage_key_name: &'static str,
         map_key: K,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<V>> {
         let storagekey =
             self.metadata
@@ -361,7 +724,7 @@ where
         storage_key_name: &'static str,
         first: K,
         second: Q,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<V>> {
         let storagekey = self.metadata.storage_double_map_key::<K, Q>(
             storage_prefix,
@@ -376,7 +739,7 @@ where
     pub fn get_storage_by_key_hash<V: Decode>(
         &self,
         key: StorageKey,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<V>> {
         let s = self.get_opaque_storage_by_key_hash(key, at_block)?;
         match s {
@@ -388,7 +751,7 @@ where
     pub fn get_opaque_storage_by_key_hash(
         &self,
         key: StorageKey,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<Vec<u8>>> {
         let jsonreq = json_req::state_get_storage(key, at_block);
         let s = self.get_request(jsonreq)?;
@@ -403,8 +766,8 @@ where
         &self,
         storage_prefix: &'static str,
         storage_key_name: &'static str,
-        at_block: Option<Hash>,
-    ) -> ApiResult<Option<rpc::ReadProof<Hash>>> {
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<rpc::ReadProof<T::Hash>>> {
         let storagekey = self
             .metadata
             .storage_value_key(storage_prefix, storage_key_name)?;
@@ -417,8 +780,8 @@ where
         storage_prefix: &'static str,
         storage_key_name: &'static str,
         map_key: K,
-        at_block: Option<Hash>,
-    ) -> ApiResult<Option<rpc::ReadProof<Hash>>> {
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<rpc::ReadProof<T::Hash>>> {
         let storagekey =
             self.metadata
                 .storage_map_key::<K>(storage_prefix, storage_key_name, map_key)?;
@@ -432,8 +795,8 @@ where
         storage_key_name: &'static str,
         first: K,
         second: Q,
-        at_block: Option<Hash>,
-    ) -> ApiResult<Option<rpc::ReadProof<Hash>>> {
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<rpc::ReadProof<T::Hash>>> {
         let storagekey = self.metadata.storage_double_map_key::<K, Q>(
             storage_prefix,
             storage_key_name,
@@ -447,8 +810,8 @@ where
     pub fn get_storage_proof_by_keys(
         &self,
         keys: Vec<StorageKey>,
-        at_block: Option<Hash>,
-    ) -> ApiResult<Option<rpc::ReadProof<Hash>>> {
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<rpc::ReadProof<T::Hash>>> {
         let jsonreq = json_req::state_get_read_proof(keys, at_block);
         let p = self.get_request(jsonreq)?;
         match p {
@@ -460,7 +823,7 @@ where
     pub fn get_keys(
         &self,
         key: StorageKey,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<Vec<String>>> {
         let jsonreq = json_req::state_get_keys(key, at_block);
         let k = self.get_request(jsonreq)?;
@@ -473,7 +836,7 @@ where
     pub fn get_fee_details(
         &self,
         xthex_prefixed: &str,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<FeeDetails<Balance>>> {
         let jsonreq = json_req::payment_query_fee_details(xthex_prefixed, at_block);
         let res = self.get_request(jsonreq)?;
@@ -490,7 +853,7 @@ where
     pub fn get_payment_info(
         &self,
         xthex_prefixed: &str,
-        at_block: Option<Hash>,
+        at_block: Option<T::Hash>,
     ) -> ApiResult<Option<RuntimeDispatchInfo<Balance>>> {
         let jsonreq = json_req::payment_query_info(xthex_prefixed, at_block);
         let res = self.get_request(jsonreq)?;
@@ -503,6 +866,71 @@ where
         }
     }
 
+    /// Call the runtime api function `method`, e.g. `Core_version`, with the SCALE encoded
+    /// arguments `data` and return the SCALE encoded result.
+    pub fn state_call(
+        &self,
+        method: &str,
+        data: &[u8],
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<Vec<u8>>> {
+        Self::_state_call(&self.client, method, data, at_block)
+    }
+
+    /// Check the extrinsic against the transaction pool validation of the runtime, without
+    /// submitting it. Detects e.g. a stale nonce, insufficient funds for the fees or an
+    /// expired era.
+    ///
+    /// Validates against the state of `at_block`, or the best block if `None`.
+    pub fn validate_extrinsic<Xt: Encode>(
+        &self,
+        xt: &Xt,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<TransactionValidity>> {
+        let at_block = match at_block {
+            Some(hash) => hash,
+            None => match self.get_block_hash(None)? {
+                Some(hash) => hash,
+                None => return Ok(None),
+            },
+        };
+        let args = (TransactionSource::External, xt, at_block).encode();
+        let res = self.state_call(
+            "TaggedTransactionQueue_validate_transaction",
+            &args,
+            Some(at_block),
+        )?;
+        match res {
+            Some(validity) => Ok(Some(TransactionValidity::decode(&mut validity.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Apply the extrinsic on top of `at_block`, or the best block if `None`, without
+    /// submitting it or paying any fees. The node must expose the unsafe rpc methods.
+    ///
+    /// A `DispatchError` is resolved into a `RuntimeError` with the help of the metadata.
+    pub fn dry_run(
+        &self,
+        xthex_prefixed: &str,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<DryRunResult>> {
+        let jsonreq = json_req::system_dry_run(xthex_prefixed, at_block);
+        let res = self.get_request(jsonreq)?;
+        let result: ApplyExtrinsicResult = match res {
+            Some(result) => decode_hex(result)?,
+            None => return Ok(None),
+        };
+        match result {
+            Ok(Ok(())) => Ok(Some(Ok(Ok(())))),
+            Ok(Err(dispatch_error)) => {
+                let runtime_error = RuntimeError::from_dispatch(&self.metadata, dispatch_error)?;
+                Ok(Some(Ok(Err(runtime_error))))
+            }
+            Err(validity_error) => Ok(Some(Err(validity_error))),
+        }
+    }
+
     pub fn get_constant<C: Decode>(
         &self,
         pallet: &'static str,
@@ -518,6 +946,25 @@ where
         Ok(Decode::decode(&mut c.value.as_slice())?)
     }
 
+    /// Get a constant through its static address, e.g. as generated by `runtime_metadata`.
+    pub fn get_static_constant<V: Decode>(
+        &self,
+        address: &StaticConstantAddress<V>,
+    ) -> ApiResult<V> {
+        Ok(self.metadata.static_constant(address)?)
+    }
+
+    /// Get a storage value through its static address, e.g. as generated by
+    /// `runtime_metadata`.
+    pub fn get_static_storage<V: Decode>(
+        &self,
+        address: &StaticStorageAddress<V>,
+        at_block: Option<T::Hash>,
+    ) -> ApiResult<Option<V>> {
+        let key = self.metadata.static_storage_key(address)?;
+        self.get_storage_by_key_hash(key, at_block)
+    }
+
     pub fn get_existential_deposit(&self) -> ApiResult<Balance> {
         self.get_constant("Balances", "ExistentialDeposit")
     }
@@ -527,13 +974,13 @@ where
         &self,
         xthex_prefixed: String,
         exit_on: XtStatus,
-    ) -> ApiResult<Option<Hash>> {
+    ) -> ApiResult<Option<T::Hash>> {
         debug!("sending extrinsic: {:?}", xthex_prefixed);
         self.client.send_extrinsic(xthex_prefixed, exit_on)
     }
 
     #[cfg(not(feature = "ws-client"))]
-    pub fn send_extrinsic(&self, xthex_prefixed: String) -> ApiResult<Option<Hash>> {
+    pub fn send_extrinsic(&self, xthex_prefixed: String) -> ApiResult<Option<T::Hash>> {
         debug!("sending extrinsic: {:?}", xthex_prefixed);
         // XtStatus should never be used used but we need to put something
         self.client
@@ -541,6 +988,70 @@ where
     }
 }
 
+/// Iterator over the events of a range of blocks, created with `Api::events_between`.
+///
+/// Yields the number and hash of each block along with its events.
+pub struct EventsBetween<'a, P, Client, T>
+where
+    Client: RpcClient,
+    T: Config,
+{
+    api: &'a Api<P, Client, T>,
+    next: Option<T::BlockNumber>,
+    to: T::BlockNumber,
+    /// Metadata of the previous block, with its spec version.
+    metadata: Option<(u32, Metadata)>,
+}
+
+impl<'a, P, Client, T> EventsBetween<'a, P, Client, T>
+where
+    Client: RpcClient,
+    T: Config,
+{
+    fn events_at(&mut self, hash: T::Hash) -> ApiResult<EventRecords> {
+        let spec_version = self.api.get_runtime_version(Some(hash))?.spec_version;
+        let metadata = match self.metadata.take() {
+            Some((version, metadata)) if version == spec_version => metadata,
+            _ => self.api._get_metadata_of_spec_version(spec_version, hash)?,
+        };
+        let events = self.api._get_events(hash, metadata.clone());
+        self.metadata = Some((spec_version, metadata));
+        events
+    }
+}
+
+impl<'a, P, Client, T> Iterator for EventsBetween<'a, P, Client, T>
+where
+    Client: RpcClient,
+    T: Config,
+{
+    type Item = ApiResult<(T::BlockNumber, T::Hash, EventRecords)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let number = self.next.filter(|number| *number <= self.to)?;
+        self.next = number.checked_add(&One::one());
+
+        let hash = match self.api.get_block_hash(Some(number)) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => {
+                self.next = None;
+                return None;
+            }
+            Err(e) => {
+                self.next = None;
+                return Some(Err(e));
+            }
+        };
+        Some(self.events_at(hash).map(|events| (number, hash, events)))
+    }
+}
+
+/// Decode a hex string as returned by the node, e.g. a hash, from its SCALE encoding.
+pub fn decode_hex<T: Decode>(hex: String) -> ApiResult<T> {
+    let bytes = Vec::from_hex(hex)?;
+    Ok(T::decode_all(&mut bytes.as_slice())?)
+}
+
 fn convert_fee_details(details: FeeDetails<NumberOrHex>) -> ApiResult<FeeDetails<u128>> {
     let inclusion_fee = if let Some(inclusion_fee) = details.inclusion_fee {
         Some(inclusion_fee_with_balance(inclusion_fee)?)
@@ -572,3 +1083,302 @@ fn inclusion_fee_with_balance(
             .map_err(|_| ApiClientError::TryFromIntError)?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ac_node_api::{test_utils, Phase, Raw};
+    use ac_primitives::NodeTemplateConfig;
+    use codec::Compact;
+    use metadata::{PalletEventMetadata, PalletMetadata};
+    use scale_info::{meta_type, TypeInfo};
+    use sp_core::H256;
+    use std::cell::Cell;
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum EventV1 {
+        Old(u8),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum EventV2 {
+        New(u8),
+    }
+
+    /// Metadata of the runtime with `spec_version`, whose `Test` pallet event got renamed by
+    /// the upgrade to spec version 2.
+    fn prefixed_metadata(spec_version: u32) -> RuntimeMetadataPrefixed {
+        let event = match spec_version {
+            1 => meta_type::<EventV1>(),
+            _ => meta_type::<EventV2>(),
+        };
+        test_utils::prefixed_metadata(vec![PalletMetadata {
+            event: Some(PalletEventMetadata { ty: event }),
+            ..test_utils::pallet("Test", 0)
+        }])
+    }
+
+    fn spec_version(block: u8) -> u32 {
+        if block < 3 {
+            1
+        } else {
+            2
+        }
+    }
+
+    fn block_hash(block: u8) -> H256 {
+        H256::repeat_byte(block)
+    }
+
+    /// Encoded `System.Events` of `block`: a single `Test` event with the block number.
+    fn events(block: u8) -> Vec<u8> {
+        let mut bytes = Compact(1u32).encode();
+        Phase::Initialization.encode_to(&mut bytes);
+        bytes.extend_from_slice(&[0, 0, block]);
+        Vec::<H256>::new().encode_to(&mut bytes);
+        bytes
+    }
+
+    fn hex_result(bytes: &[u8]) -> String {
+        format!("\"0x{}\"", hex::encode(bytes))
+    }
+
+    /// Chain of blocks 0 to 3 with a runtime upgrade in block 3, see `spec_version`. State
+    /// requests without a block hash fail, as the api must not mix the state of different
+    /// best blocks.
+    #[derive(Default)]
+    struct UpgradedChain {
+        metadata_requests: Cell<usize>,
+    }
+
+    impl RpcClient for UpgradedChain {
+        fn get_request(&self, jsonreq: Value) -> ApiResult<String> {
+            let params = &jsonreq["params"];
+            let block_at = |i: usize| decode_hex::<H256>(params[i].to_string()).map(|h| h.0[0]);
+            match jsonreq["method"].as_str().unwrap() {
+                "chain_getBlockHash" => Ok(match params[0].as_u64().unwrap_or(3) {
+                    number @ 0..=3 => hex_result(block_hash(number as u8).as_bytes()),
+                    _ => "null".into(),
+                }),
+                "state_getRuntimeVersion" => Ok(serde_json::to_string(&RuntimeVersion {
+                    spec_version: spec_version(block_at(0)?),
+                    ..Default::default()
+                })?),
+                // Without the `Metadata_metadata_versions` runtime api.
+                "state_call" => Ok("null".into()),
+                "state_getMetadata" => {
+                    self.metadata_requests.set(self.metadata_requests.get() + 1);
+                    let metadata = prefixed_metadata(spec_version(block_at(0)?));
+                    Ok(hex_result(&metadata.encode()))
+                }
+                "state_getStorage" => Ok(hex_result(&events(block_at(1)?))),
+                method => panic!("Unexpected request {}", method),
+            }
+        }
+
+        fn send_extrinsic<Hash: Decode>(
+            &self,
+            _xthex_prefixed: String,
+            _exit_on: XtStatus,
+        ) -> ApiResult<Option<Hash>> {
+            unimplemented!()
+        }
+    }
+
+    /// Api created after the runtime upgrade.
+    fn api() -> Api<(), UpgradedChain, NodeTemplateConfig> {
+        Api::new_offline(
+            UpgradedChain::default(),
+            block_hash(0),
+            Metadata::try_from(prefixed_metadata(2)).unwrap(),
+            RuntimeVersion {
+                spec_version: 2,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn variant_and_data(events: &EventRecords) -> (&str, &[u8]) {
+        match &events.0[..] {
+            [record] => match &record.event {
+                Raw::Event(event) => (event.variant.as_str(), &event.data[..]),
+                Raw::Error(e) => panic!("Unexpected error {:?}", e),
+            },
+            records => panic!("Expected a single event, got {:?}", records),
+        }
+    }
+
+    #[test]
+    fn get_events_decodes_with_the_metadata_of_the_block() {
+        let api = api();
+
+        let events = api.get_events(Some(block_hash(1))).unwrap();
+        assert_eq!(variant_and_data(&events), ("Old", &[1u8][..]));
+        assert_eq!(api.client.metadata_requests.get(), 1);
+
+        let events = api.get_events(None).unwrap();
+        assert_eq!(variant_and_data(&events), ("New", &[3u8][..]));
+        assert_eq!(api.client.metadata_requests.get(), 1);
+    }
+
+    #[test]
+    fn events_between_fetches_the_metadata_once_per_spec_version() {
+        let api = api();
+
+        let blocks = api
+            .events_between(1, 5)
+            .collect::<ApiResult<Vec<_>>>()
+            .unwrap();
+        let decoded: Vec<_> = blocks
+            .iter()
+            .map(|(number, hash, events)| (*number, *hash, variant_and_data(events)))
+            .collect();
+        assert_eq!(
+            decoded,
+            vec![
+                (1, block_hash(1), ("Old", &[1u8][..])),
+                (2, block_hash(2), ("Old", &[2u8][..])),
+                (3, block_hash(3), ("New", &[3u8][..])),
+            ]
+        );
+        assert_eq!(api.client.metadata_requests.get(), 1);
+    }
+
+    #[test]
+    fn new_fetches_the_runtime_version_and_metadata_at_the_same_block() {
+        let api = Api::<(), _, NodeTemplateConfig>::new(UpgradedChain::default()).unwrap();
+        assert_eq!(api.genesis_hash, block_hash(0));
+        assert_eq!(api.runtime_version.spec_version, 2);
+        assert_eq!(api.metadata.event(0, 0).unwrap().event(), "New");
+    }
+
+    #[test]
+    fn metadata_cache_stores_the_metadata_of_each_spec_version() {
+        let dir = std::env::temp_dir().join(format!("api-metadata-cache-{}", std::process::id()));
+        let cache = MetadataCache::new(&dir);
+        let genesis_hash = block_hash(0);
+
+        let api = Api::<(), _, NodeTemplateConfig>::new_with_metadata_cache(
+            UpgradedChain::default(),
+            &cache,
+        )
+        .unwrap();
+        assert_eq!(api.metadata_cache_status, Some(MetadataCacheStatus::Miss));
+        assert_eq!(
+            cache.load(&genesis_hash, 2).unwrap(),
+            Some(VersionedMetadata::try_from(prefixed_metadata(2)).unwrap())
+        );
+
+        api.get_events(Some(block_hash(1))).unwrap();
+        assert_eq!(
+            cache.load(&genesis_hash, 1).unwrap(),
+            Some(VersionedMetadata::try_from(prefixed_metadata(1)).unwrap())
+        );
+
+        let api = Api::<(), _, NodeTemplateConfig>::new_with_metadata_cache(
+            UpgradedChain::default(),
+            &cache,
+        )
+        .unwrap();
+        assert_eq!(api.metadata_cache_status, Some(MetadataCacheStatus::Hit));
+        let events = api.get_events(Some(block_hash(1))).unwrap();
+        assert_eq!(variant_and_data(&events), ("Old", &[1u8][..]));
+        assert_eq!(api.client.metadata_requests.get(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Answer of a node to `Metadata_metadata_versions`.
+    enum MetadataVersions {
+        /// The runtime does not have the metadata runtime api.
+        Missing,
+        /// The request fails, e.g. because of the connection.
+        Failing,
+        Supported(Vec<u32>),
+    }
+
+    /// Node with the metadata of `prefixed_metadata(2)`.
+    struct MetadataNode {
+        versions: MetadataVersions,
+        state_get_metadata_requests: Cell<usize>,
+    }
+
+    impl MetadataNode {
+        fn new(versions: MetadataVersions) -> Self {
+            Self {
+                versions,
+                state_get_metadata_requests: Cell::new(0),
+            }
+        }
+    }
+
+    impl RpcClient for MetadataNode {
+        fn get_request(&self, jsonreq: Value) -> ApiResult<String> {
+            let params = &jsonreq["params"];
+            match (jsonreq["method"].as_str().unwrap(), params[0].as_str()) {
+                ("state_call", Some("Metadata_metadata_versions")) => match &self.versions {
+                    MetadataVersions::Missing => Ok("null".into()),
+                    MetadataVersions::Failing => {
+                        Err(ApiClientError::RpcClient("connection reset".into()))
+                    }
+                    MetadataVersions::Supported(versions) => Ok(hex_result(&versions.encode())),
+                },
+                ("state_call", Some("Metadata_metadata_at_version")) => {
+                    let metadata = VersionedMetadata::try_from(prefixed_metadata(2)).unwrap();
+                    Ok(hex_result(&Some(metadata.encode()).encode()))
+                }
+                ("state_getMetadata", _) => {
+                    let requests = &self.state_get_metadata_requests;
+                    requests.set(requests.get() + 1);
+                    Ok(hex_result(&prefixed_metadata(2).encode()))
+                }
+                (method, _) => panic!("Unexpected request {}", method),
+            }
+        }
+
+        fn send_extrinsic<Hash: Decode>(
+            &self,
+            _xthex_prefixed: String,
+            _exit_on: XtStatus,
+        ) -> ApiResult<Option<Hash>> {
+            unimplemented!()
+        }
+    }
+
+    fn metadata_api(versions: MetadataVersions) -> Api<(), MetadataNode, NodeTemplateConfig> {
+        Api::new_offline(
+            MetadataNode::new(versions),
+            block_hash(0),
+            Metadata::try_from(prefixed_metadata(2)).unwrap(),
+            RuntimeVersion::default(),
+        )
+    }
+
+    #[test]
+    fn versioned_metadata_uses_the_metadata_runtime_api() {
+        let api = metadata_api(MetadataVersions::Supported(vec![13, 14, 16]));
+        let expected = VersionedMetadata::try_from(prefixed_metadata(2)).unwrap();
+        assert_eq!(api.get_versioned_metadata().unwrap(), expected);
+        assert_eq!(api.client.state_get_metadata_requests.get(), 0);
+    }
+
+    #[test]
+    fn versioned_metadata_falls_back_without_the_metadata_runtime_api() {
+        let api = metadata_api(MetadataVersions::Missing);
+        let expected = VersionedMetadata::try_from(prefixed_metadata(2)).unwrap();
+        assert_eq!(api.get_versioned_metadata().unwrap(), expected);
+        assert_eq!(api.client.state_get_metadata_requests.get(), 1);
+    }
+
+    #[test]
+    fn versioned_metadata_returns_other_errors() {
+        let api = metadata_api(MetadataVersions::Failing);
+        match api.get_versioned_metadata() {
+            Err(ApiClientError::RpcClient(e)) => assert_eq!(e, "connection reset"),
+            other => panic!("Expected the rpc error, got {:?}", other),
+        }
+        assert_eq!(api.client.state_get_metadata_requests.get(), 0);
+    }
+}
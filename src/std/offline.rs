@@ -0,0 +1,384 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! An `Api` without a connection to a node, e.g. to sign extrinsics on an air-gapped machine.
+//!
+//! The metadata, genesis hash and runtime version are exported to files on a connected
+//! machine with `Api::export_offline_data` and read back with `OfflineApi::from_dir`. With a
+//! nonce set by `OfflineApi::set_nonce`, the extrinsic helpers and `compose_extrinsic!` work
+//! without any rpc request. Everything else fails with `ApiClientError::Offline`.
+//!
+//! The nonce is not incremented by signing; set the next one before every extrinsic.
+//!
+//! ```no_run
+//! use sp_keyring::AccountKeyring;
+//! use sp_runtime::MultiAddress;
+//! use substrate_api_client::{NodeTemplateConfig, OfflineApi};
+//!
+//! let mut api = OfflineApi::<_, NodeTemplateConfig>::from_dir("offline-data")
+//!     .unwrap()
+//!     .set_signer(AccountKeyring::Alice.pair());
+//! let to = AccountKeyring::Bob.to_account_id();
+//! for nonce in 7..9 {
+//!     api.set_nonce(nonce);
+//!     let xt = api.balance_transfer(MultiAddress::Id(to.clone()), 1000).unwrap();
+//!     // Broadcast later with `api.send_extrinsic` on a connected machine.
+//!     println!("{}", xt.hex_encode());
+//! }
+//! ```
+
+use crate::std::{decode_hex, Api, ApiClientError, ApiResult, RpcClient, RuntimeVersion, XtStatus};
+use ac_node_api::{metadata::Metadata, v15::VersionedMetadata};
+use ac_primitives::Config;
+use codec::{Decode, Encode};
+use std::{convert::TryFrom, fs, path::Path};
+
+/// File of the SCALE encoded metadata, in the format of `state_getMetadata`.
+pub const METADATA_FILE: &str = "metadata.scale";
+/// File of the hex encoded genesis hash.
+pub const GENESIS_HASH_FILE: &str = "genesis_hash.hex";
+/// File of the runtime version, in the json format of `state_getRuntimeVersion`.
+pub const RUNTIME_VERSION_FILE: &str = "runtime_version.json";
+
+/// An `Api` which does not talk to a node.
+pub type OfflineApi<P, T> = Api<P, OfflineClient, T>;
+
+/// `RpcClient` failing every request with `ApiClientError::Offline`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OfflineClient;
+
+impl RpcClient for OfflineClient {
+    fn get_request(&self, _jsonreq: serde_json::Value) -> ApiResult<String> {
+        Err(ApiClientError::Offline)
+    }
+
+    fn send_extrinsic<Hash: Decode>(
+        &self,
+        _xthex_prefixed: String,
+        _exit_on: XtStatus,
+    ) -> ApiResult<Option<Hash>> {
+        Err(ApiClientError::Offline)
+    }
+}
+
+impl<P, T> Api<P, OfflineClient, T>
+where
+    T: Config,
+{
+    /// Offline api with the metadata, genesis hash and runtime version read from the files
+    /// written by `Api::export_offline_data`.
+    pub fn from_dir<D: AsRef<Path>>(dir: D) -> ApiResult<Self> {
+        let dir = dir.as_ref();
+        Self::from_files(
+            dir.join(METADATA_FILE),
+            dir.join(GENESIS_HASH_FILE),
+            dir.join(RUNTIME_VERSION_FILE),
+        )
+    }
+
+    /// Offline api with the metadata, genesis hash and runtime version read from the given
+    /// files, see `METADATA_FILE`, `GENESIS_HASH_FILE` and `RUNTIME_VERSION_FILE` for their
+    /// formats.
+    pub fn from_files<M, G, R>(metadata: M, genesis_hash: G, runtime_version: R) -> ApiResult<Self>
+    where
+        M: AsRef<Path>,
+        G: AsRef<Path>,
+        R: AsRef<Path>,
+    {
+        let metadata = fs::read(metadata)?;
+        let metadata = Metadata::try_from(VersionedMetadata::decode(&mut metadata.as_slice())?)?;
+        let genesis_hash = decode_hex(fs::read_to_string(genesis_hash)?.trim().to_string())?;
+        let runtime_version = serde_json::from_slice(&fs::read(runtime_version)?)?;
+        Ok(Self::new_offline(
+            OfflineClient,
+            genesis_hash,
+            metadata,
+            runtime_version,
+        ))
+    }
+
+    /// Use `nonce` for the next extrinsics signed by this api. It is not incremented; set the
+    /// next one for every extrinsic.
+    pub fn set_nonce(&mut self, nonce: T::Index) {
+        self.nonce = Some(nonce);
+    }
+}
+
+impl<P, Client, T> Api<P, Client, T>
+where
+    Client: RpcClient,
+    T: Config,
+{
+    /// Api with the given chain data, without fetching anything from the node.
+    pub fn new_offline(
+        client: Client,
+        genesis_hash: T::Hash,
+        metadata: Metadata,
+        runtime_version: RuntimeVersion,
+    ) -> Self {
+        Self {
+            signer: None,
+            genesis_hash,
+            metadata,
+            runtime_version,
+            client,
+            extrinsic_params_builder: None,
+            metadata_cache_status: None,
+            metadata_cache: None,
+            nonce: None,
+            proxy: None,
+        }
+    }
+
+    /// Fetches the metadata, genesis hash and runtime version from the node and writes them
+    /// into `dir` for `OfflineApi::from_dir`.
+    pub fn export_offline_data<D: AsRef<Path>>(&self, dir: D) -> ApiResult<()> {
+        // Both at the same block, so the metadata belongs to the exported spec version even
+        // if the runtime was upgraded since this api was created.
+        let best_hash = Self::_get_best_block_hash(&self.client)?;
+        let runtime_version = Self::_get_runtime_version(&self.client, Some(best_hash))?;
+        let metadata = Self::_get_versioned_metadata(&self.client, Some(best_hash))?;
+
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(METADATA_FILE), metadata.encode())?;
+        fs::write(
+            dir.join(GENESIS_HASH_FILE),
+            format!("0x{}", hex::encode(self.get_genesis_hash()?.encode())),
+        )?;
+        fs::write(
+            dir.join(RUNTIME_VERSION_FILE),
+            serde_json::to_vec_pretty(&runtime_version)?,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ac_node_api::test_utils;
+    use ac_primitives::{
+        AccountData, AccountId, DynamicExtrinsicParams, ExtrinsicParams, GenericAddress,
+        NodeTemplateConfig, PlainTipExtrinsicParams, PlainTipExtrinsicParamsBuilder,
+        SignedExtensionError,
+    };
+    use metadata::{
+        ExtrinsicMetadata, PalletCallMetadata, PalletMetadata, RuntimeMetadataPrefixed,
+        SignedExtensionMetadata,
+    };
+    use scale_info::{meta_type, TypeInfo};
+    use serde_json::Value;
+    use sp_core::{sr25519, H256};
+    use sp_keyring::AccountKeyring;
+    use sp_runtime::{generic, traits::BlakeTwo256, MultiAddress, MultiSignature};
+
+    /// `NodeTemplateConfig` with the signed extensions taken from the metadata.
+    enum DynamicConfig {}
+
+    impl Config for DynamicConfig {
+        type Index = u32;
+        type BlockNumber = u32;
+        type Hash = H256;
+        type Hasher = BlakeTwo256;
+        type AccountId = AccountId;
+        type Address = GenericAddress;
+        type Signature = MultiSignature;
+        type Header = generic::Header<Self::BlockNumber, Self::Hasher>;
+        type AccountData = AccountData;
+        type ExtrinsicParams = DynamicExtrinsicParams;
+    }
+
+    const SUBSTRATE_EXTENSIONS: [&str; 8] = [
+        "CheckNonZeroSender",
+        "CheckSpecVersion",
+        "CheckTxVersion",
+        "CheckGenesis",
+        "CheckMortality",
+        "CheckNonce",
+        "CheckWeight",
+        "ChargeTransactionPayment",
+    ];
+
+    fn runtime_version() -> RuntimeVersion {
+        RuntimeVersion {
+            spec_version: 5,
+            transaction_version: 2,
+            ..Default::default()
+        }
+    }
+
+    fn dynamic_api(extensions: &[&'static str]) -> OfflineApi<sr25519::Pair, DynamicConfig> {
+        let extrinsic = ExtrinsicMetadata {
+            signed_extensions: extensions
+                .iter()
+                .map(|&identifier| SignedExtensionMetadata {
+                    identifier,
+                    ty: meta_type::<()>(),
+                    additional_signed: meta_type::<()>(),
+                })
+                .collect(),
+            ..test_utils::extrinsic()
+        };
+        let metadata = test_utils::metadata_with_extrinsic(vec![], extrinsic);
+        Api::new_offline(
+            OfflineClient,
+            H256::from([1u8; 32]),
+            metadata,
+            runtime_version(),
+        )
+    }
+
+    #[test]
+    fn extrinsic_params_use_the_signed_extensions_of_the_metadata() {
+        let api = dynamic_api(&SUBSTRATE_EXTENSIONS);
+        let dynamic = api.try_extrinsic_params(7).unwrap();
+        let plain = PlainTipExtrinsicParams::new(
+            5,
+            2,
+            7,
+            H256::from([1u8; 32]),
+            PlainTipExtrinsicParamsBuilder::new(),
+        );
+
+        assert_eq!(dynamic.extensions(), SUBSTRATE_EXTENSIONS);
+        assert_eq!(
+            dynamic.signed_extra().encode(),
+            plain.signed_extra().encode()
+        );
+        assert_eq!(
+            dynamic.additional_signed().encode(),
+            plain.additional_signed().encode()
+        );
+    }
+
+    #[test]
+    fn extrinsic_params_fail_without_signed_extensions() {
+        assert!(matches!(
+            dynamic_api(&[]).try_extrinsic_params(0),
+            Err(ApiClientError::SignedExtension(SignedExtensionError::Empty))
+        ));
+        assert!(matches!(
+            dynamic_api(&["CheckNonce", "CheckDb3Permission"]).try_extrinsic_params(0),
+            Err(ApiClientError::SignedExtension(
+                SignedExtensionError::Unknown(_)
+            ))
+        ));
+    }
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(TypeInfo)]
+    enum BalancesCall {
+        transfer(u8),
+    }
+
+    /// Metadata with the `Balances.transfer` call at index `[5, 0]`.
+    fn balances_metadata() -> RuntimeMetadataPrefixed {
+        test_utils::prefixed_metadata(vec![PalletMetadata {
+            calls: Some(PalletCallMetadata {
+                ty: meta_type::<BalancesCall>(),
+            }),
+            ..test_utils::pallet("Balances", 5)
+        }])
+    }
+
+    /// Node whose runtime got upgraded to spec version 6 in its best block 9 after the api
+    /// was created. Requests not pinned to the best block fail.
+    struct UpgradedNode;
+
+    impl RpcClient for UpgradedNode {
+        fn get_request(&self, jsonreq: Value) -> ApiResult<String> {
+            let params = &jsonreq["params"];
+            let hex_result = |bytes: &[u8]| format!("\"0x{}\"", hex::encode(bytes));
+            let best_hash = serde_json::to_value(H256::repeat_byte(9))?;
+            match jsonreq["method"].as_str().unwrap() {
+                "chain_getBlockHash" => Ok(match params[0].as_u64() {
+                    Some(0) => hex_result(H256::repeat_byte(1).as_bytes()),
+                    _ => hex_result(H256::repeat_byte(9).as_bytes()),
+                }),
+                "state_getRuntimeVersion" if params[0] == best_hash => {
+                    Ok(serde_json::to_string(&RuntimeVersion {
+                        spec_version: 6,
+                        ..runtime_version()
+                    })?)
+                }
+                // Without the `Metadata_metadata_versions` runtime api.
+                "state_call" => Ok("null".into()),
+                "state_getMetadata" if params[0] == best_hash => {
+                    Ok(hex_result(&balances_metadata().encode()))
+                }
+                method => panic!("Unexpected request {} {}", method, params),
+            }
+        }
+
+        fn send_extrinsic<Hash: Decode>(
+            &self,
+            _xthex_prefixed: String,
+            _exit_on: XtStatus,
+        ) -> ApiResult<Option<Hash>> {
+            unimplemented!()
+        }
+    }
+
+    /// `OfflineApi` read back from the data exported from an `UpgradedNode`.
+    fn exported_api(name: &str) -> OfflineApi<sr25519::Pair, NodeTemplateConfig> {
+        let dir = std::env::temp_dir().join(format!("api-offline-{}-{}", name, std::process::id()));
+        let api = Api::<(), _, NodeTemplateConfig>::new_offline(
+            UpgradedNode,
+            H256::repeat_byte(1),
+            test_utils::metadata(vec![]),
+            runtime_version(),
+        );
+        api.export_offline_data(&dir).unwrap();
+        let offline_api = OfflineApi::from_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        offline_api
+    }
+
+    #[test]
+    fn from_dir_reads_the_exported_data_of_the_best_block() {
+        let api = exported_api("from-dir");
+
+        assert_eq!(api.genesis_hash, H256::repeat_byte(1));
+        assert_eq!(api.runtime_version.spec_version, 6);
+        assert_eq!(api.runtime_version.transaction_version, 2);
+        let balances = api.metadata.pallet("Balances").unwrap();
+        assert_eq!(balances.index, 5);
+        assert_eq!(balances.calls.get("transfer"), Some(&0));
+    }
+
+    #[test]
+    fn balance_transfer_is_signed_with_the_set_nonce() {
+        let mut api = exported_api("transfer").set_signer(AccountKeyring::Alice.pair());
+        let to = MultiAddress::Id(AccountKeyring::Bob.to_account_id());
+
+        for nonce in [7, 8] {
+            api.set_nonce(nonce);
+            let xt = api.balance_transfer(to.clone(), 1000).unwrap();
+            let (address, _, extra) = xt.signature.as_ref().unwrap();
+
+            assert_eq!(xt.function.0, [5, 0]);
+            assert_eq!(
+                address,
+                &MultiAddress::Id(AccountKeyring::Alice.to_account_id())
+            );
+            assert_eq!(
+                extra.encode(),
+                api.try_extrinsic_params(nonce)
+                    .unwrap()
+                    .signed_extra()
+                    .encode()
+            );
+        }
+    }
+}
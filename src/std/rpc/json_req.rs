@@ -18,27 +18,29 @@
 use serde::Serialize;
 use serde_json::{json, to_value, Value};
 use sp_core::storage::StorageKey;
-use sp_core::H256 as Hash;
 
 pub const REQUEST_TRANSFER: u32 = 3;
 
-pub fn chain_get_header(hash: Option<Hash>) -> Value {
+pub fn chain_get_header<Hash: Serialize>(hash: Option<Hash>) -> Value {
     json_req("chain_getHeader", vec![hash], 1)
 }
 
-pub fn chain_get_block_hash(number: Option<u32>) -> Value {
+pub fn chain_get_block_hash<BlockNumber: Serialize>(number: Option<BlockNumber>) -> Value {
     chain_get_block_hash_with_id(number, 1)
 }
 
 pub fn chain_get_genesis_hash() -> Value {
-    chain_get_block_hash(Some(0))
+    chain_get_block_hash(Some(0u32))
 }
 
-pub fn chain_get_block_hash_with_id(number: Option<u32>, id: u32) -> Value {
+pub fn chain_get_block_hash_with_id<BlockNumber: Serialize>(
+    number: Option<BlockNumber>,
+    id: u32,
+) -> Value {
     json_req("chain_getBlockHash", vec![number], id)
 }
 
-pub fn chain_get_block(hash: Option<Hash>) -> Value {
+pub fn chain_get_block<Hash: Serialize>(hash: Option<Hash>) -> Value {
     json_req("chain_getBlock", vec![hash], 1)
 }
 
@@ -50,7 +52,14 @@ pub fn chain_subscribe_finalized_heads() -> Value {
     json_req("chain_subscribeFinalizedHeads", Value::Null, 1)
 }
 
-pub fn payment_query_fee_details(xthex_prefixed: &str, at_block: Option<Hash>) -> Value {
+pub fn chain_subscribe_new_heads() -> Value {
+    json_req("chain_subscribeNewHeads", Value::Null, 1)
+}
+
+pub fn payment_query_fee_details<Hash: Serialize>(
+    xthex_prefixed: &str,
+    at_block: Option<Hash>,
+) -> Value {
     json_req(
         "payment_queryFeeDetails",
         vec![
@@ -61,7 +70,7 @@ pub fn payment_query_fee_details(xthex_prefixed: &str, at_block: Option<Hash>) -
     )
 }
 
-pub fn payment_query_info(xthex_prefixed: &str, at_block: Option<Hash>) -> Value {
+pub fn payment_query_info<Hash: Serialize>(xthex_prefixed: &str, at_block: Option<Hash>) -> Value {
     json_req(
         "payment_queryInfo",
         vec![
@@ -80,6 +89,10 @@ pub fn state_get_metadata_with_id(id: u32) -> Value {
     json_req("state_getMetadata", vec![Value::Null], id)
 }
 
+pub fn state_get_metadata_at<Hash: Serialize>(at_block: Option<Hash>) -> Value {
+    json_req("state_getMetadata", vec![to_value(at_block).unwrap()], 1)
+}
+
 pub fn state_get_runtime_version() -> Value {
     state_get_runtime_version_with_id(1)
 }
@@ -88,6 +101,14 @@ pub fn state_get_runtime_version_with_id(id: u32) -> Value {
     json_req("state_getRuntimeVersion", vec![Value::Null], id)
 }
 
+pub fn state_get_runtime_version_at<Hash: Serialize>(at_block: Option<Hash>) -> Value {
+    json_req(
+        "state_getRuntimeVersion",
+        vec![to_value(at_block).unwrap()],
+        1,
+    )
+}
+
 pub fn state_subscribe_storage(key: Vec<StorageKey>) -> Value {
     state_subscribe_storage_with_id(key, 1)
 }
@@ -97,7 +118,7 @@ pub fn state_subscribe_storage_with_id(key: Vec<StorageKey>, id: u32) -> Value {
     json_req("state_subscribeStorage", vec![key], id)
 }
 
-pub fn state_get_storage(key: StorageKey, at_block: Option<Hash>) -> Value {
+pub fn state_get_storage<Hash: Serialize>(key: StorageKey, at_block: Option<Hash>) -> Value {
     json_req(
         "state_getStorage",
         vec![to_value(key).unwrap(), to_value(at_block).unwrap()],
@@ -105,7 +126,11 @@ pub fn state_get_storage(key: StorageKey, at_block: Option<Hash>) -> Value {
     )
 }
 
-pub fn state_get_storage_with_id(key: StorageKey, at_block: Option<Hash>, id: u32) -> Value {
+pub fn state_get_storage_with_id<Hash: Serialize>(
+    key: StorageKey,
+    at_block: Option<Hash>,
+    id: u32,
+) -> Value {
     json_req(
         "state_getStorage",
         vec![to_value(key).unwrap(), to_value(at_block).unwrap()],
@@ -113,7 +138,10 @@ pub fn state_get_storage_with_id(key: StorageKey, at_block: Option<Hash>, id: u3
     )
 }
 
-pub fn state_get_read_proof(keys: Vec<StorageKey>, at_block: Option<Hash>) -> Value {
+pub fn state_get_read_proof<Hash: Serialize>(
+    keys: Vec<StorageKey>,
+    at_block: Option<Hash>,
+) -> Value {
     json_req(
         "state_getReadProof",
         vec![to_value(keys).unwrap(), to_value(at_block).unwrap()],
@@ -121,7 +149,7 @@ pub fn state_get_read_proof(keys: Vec<StorageKey>, at_block: Option<Hash>) -> Va
     )
 }
 
-pub fn state_get_keys(key: StorageKey, at_block: Option<Hash>) -> Value {
+pub fn state_get_keys<Hash: Serialize>(key: StorageKey, at_block: Option<Hash>) -> Value {
     json_req(
         "state_getKeys",
         vec![to_value(key).unwrap(), to_value(at_block).unwrap()],
@@ -129,6 +157,29 @@ pub fn state_get_keys(key: StorageKey, at_block: Option<Hash>) -> Value {
     )
 }
 
+pub fn state_call<Hash: Serialize>(method: &str, data_hex: &str, at_block: Option<Hash>) -> Value {
+    json_req(
+        "state_call",
+        vec![
+            to_value(method).unwrap(),
+            to_value(data_hex).unwrap(),
+            to_value(at_block).unwrap(),
+        ],
+        1,
+    )
+}
+
+pub fn system_dry_run<Hash: Serialize>(xthex_prefixed: &str, at_block: Option<Hash>) -> Value {
+    json_req(
+        "system_dryRun",
+        vec![
+            to_value(xthex_prefixed).unwrap(),
+            to_value(at_block).unwrap(),
+        ],
+        1,
+    )
+}
+
 pub fn author_submit_extrinsic(xthex_prefixed: &str) -> Value {
     author_submit_extrinsic_with_id(xthex_prefixed, REQUEST_TRANSFER)
 }
@@ -34,6 +34,8 @@ pub enum RpcClientError {
     Send(#[from] std::sync::mpsc::SendError<String>),
 }
 
+/// Status to wait for when submitting an extrinsic. To wait for a number of blocks on top of the
+/// including block instead, see `Api::send_extrinsic_with_confirmations`.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum XtStatus {
     // Todo: some variants to not return a hash with `send_extrinsics`: #175.
@@ -2,12 +2,13 @@ use std::sync::mpsc::channel;
 use std::sync::mpsc::Sender as ThreadOut;
 use std::thread;
 
+use codec::Decode;
 use log::info;
 use serde_json::Value;
-use sp_core::H256 as Hash;
 use ws::{connect, Result as WsResult};
 
 use crate::rpc::ws_client::on_extrinsic_msg_submit_only;
+use crate::std::decode_hex;
 use crate::std::rpc::json_req;
 use crate::std::rpc::ws_client::Subscriber;
 use crate::std::rpc::ws_client::{
@@ -17,7 +18,6 @@ use crate::std::rpc::ws_client::{
 };
 use crate::std::ApiClientError;
 use crate::std::ApiResult;
-use crate::std::FromHexString;
 use crate::std::RpcClient as RpcClientTrait;
 use crate::std::XtStatus;
 
@@ -39,11 +39,11 @@ impl RpcClientTrait for WsRpcClient {
         self.direct_rpc_request(jsonreq.to_string(), on_get_request_msg)
     }
 
-    fn send_extrinsic(
+    fn send_extrinsic<Hash: Decode>(
         &self,
         xthex_prefixed: String,
         exit_on: XtStatus,
-    ) -> ApiResult<Option<sp_core::H256>> {
+    ) -> ApiResult<Option<Hash>> {
         // Todo: Make all variants return a H256: #175.
 
         let jsonreq = match exit_on {
@@ -55,12 +55,12 @@ impl RpcClientTrait for WsRpcClient {
             XtStatus::Finalized => {
                 let res = self.direct_rpc_request(jsonreq, on_extrinsic_msg_until_finalized)?;
                 info!("finalized: {}", res);
-                Ok(Some(Hash::from_hex(res)?))
+                Ok(Some(decode_hex(res)?))
             }
             XtStatus::InBlock => {
                 let res = self.direct_rpc_request(jsonreq, on_extrinsic_msg_until_in_block)?;
                 info!("inBlock: {}", res);
-                Ok(Some(Hash::from_hex(res)?))
+                Ok(Some(decode_hex(res)?))
             }
             XtStatus::Broadcast => {
                 let res = self.direct_rpc_request(jsonreq, on_extrinsic_msg_until_broadcast)?;
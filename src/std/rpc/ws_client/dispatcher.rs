@@ -0,0 +1,561 @@
+/*
+   Copyright 2019 Supercomputing Systems AG
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+
+*/
+
+//! Dispatch the events of new best blocks to registered handlers.
+
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender as ThreadOut, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use ac_node_api::error::RuntimeError;
+use ac_node_api::events::{
+    EventRecords, EventsDecoder, Raw, RawEvent, StaticEvent, UndecodableEvent,
+};
+use ac_node_api::Phase;
+use ac_primitives::{Config, Signer};
+use log::{debug, error, warn};
+use sp_runtime::traits::Header as HeaderT;
+
+use super::Subscriber;
+use crate::std::{Api, ApiResult, RpcClient as RpcClientTrait};
+use crate::utils;
+
+type EventHandler = Box<dyn FnMut(&Phase, &RawEvent) + Send>;
+type EventPredicate = Box<dyn Fn(&RawEvent) -> bool + Send>;
+type ErrorHandler = Box<dyn FnMut(Option<&Phase>, &EventError) + Send>;
+
+/// How often a dispatcher waiting for the next block checks whether it is stopped.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Error reported to the `on_error` handlers of an [`EventDispatcher`].
+#[derive(Debug)]
+pub enum EventError {
+    /// An extrinsic failed.
+    Runtime(RuntimeError),
+    /// An event record could not be decoded with the metadata of its block.
+    Undecodable(UndecodableEvent),
+}
+
+/// Calls the handlers registered for an event for each matching event of a block.
+///
+/// ```no_run
+/// use substrate_api_client::rpc::ws_client::EventDispatcher;
+///
+/// let dispatcher = EventDispatcher::new()
+///     .on_event("Balances", "Transfer", |phase, event| {
+///         println!("transfer in {:?}: {:?}", phase, event)
+///     })
+///     .on_error(|phase, error| println!("error in {:?}: {:?}", phase, error));
+/// ```
+///
+/// `start` runs the dispatcher in a background thread, driven by a new heads subscription.
+#[derive(Default)]
+pub struct EventDispatcher {
+    handlers: Vec<(EventPredicate, EventHandler)>,
+    error_handlers: Vec<ErrorHandler>,
+}
+
+impl EventDispatcher {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Handle the events named `event` of `pallet`.
+    pub fn on_event<F>(self, pallet: &str, event: &str, handler: F) -> Self
+    where
+        F: FnMut(&Phase, &RawEvent) + Send + 'static,
+    {
+        let (pallet, event) = (pallet.to_string(), event.to_string());
+        self.on_event_where(
+            move |raw| raw.pallet == pallet && raw.variant == event,
+            handler,
+        )
+    }
+
+    /// Handle the events `predicate` holds for.
+    pub fn on_event_where<Pred, F>(mut self, predicate: Pred, handler: F) -> Self
+    where
+        Pred: Fn(&RawEvent) -> bool + Send + 'static,
+        F: FnMut(&Phase, &RawEvent) + Send + 'static,
+    {
+        self.handlers.push((Box::new(predicate), Box::new(handler)));
+        self
+    }
+
+    /// Handle the `E` events, decoded. Events failing to decode as `E` are logged and skipped.
+    pub fn on_static_event<E, F>(self, mut handler: F) -> Self
+    where
+        E: StaticEvent,
+        F: FnMut(&Phase, E) + Send + 'static,
+    {
+        self.on_event(E::PALLET, E::EVENT, move |phase, raw| {
+            match E::decode(&mut &raw.data[..]) {
+                Ok(event) => handler(phase, event),
+                Err(e) => error!("couldn't decode {}::{}: {:?}", E::PALLET, E::EVENT, e),
+            }
+        })
+    }
+
+    /// Handle the errors of failed extrinsics and the records that could not be decoded. The
+    /// phase is `None` for a record whose phase could not be decoded either.
+    pub fn on_error<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(Option<&Phase>, &EventError) + Send + 'static,
+    {
+        self.error_handlers.push(Box::new(handler));
+        self
+    }
+
+    /// Call the matching handlers for each record, in order.
+    pub fn dispatch(&mut self, records: &EventRecords) {
+        for record in records.iter() {
+            match &record.event {
+                Raw::Event(raw) => {
+                    for (predicate, handler) in self.handlers.iter_mut() {
+                        if predicate(raw) {
+                            handler(&record.phase, raw);
+                        }
+                    }
+                }
+                Raw::Error(runtime_error) => self.report(
+                    Some(&record.phase),
+                    &EventError::Runtime(runtime_error.clone()),
+                ),
+            }
+        }
+    }
+
+    fn report(&mut self, phase: Option<&Phase>, error: &EventError) {
+        for handler in self.error_handlers.iter_mut() {
+            handler(phase, error);
+        }
+    }
+
+    /// Subscribe to the new heads of `api` and dispatch the events of each block in a
+    /// background thread, until the returned handle is stopped or dropped, or the
+    /// subscription ends.
+    ///
+    /// Like `Api::get_events`, the events of each block are decoded with the metadata of its
+    /// runtime. They are decoded leniently: the records that can't be decoded are passed to
+    /// the `on_error` handlers, the others are dispatched nevertheless.
+    pub fn start<P, Client, T>(self, api: &Api<P, Client, T>) -> ApiResult<DispatcherHandle>
+    where
+        P: Signer<AccountId = T::AccountId, Address = T::Address, Signature = T::Signature>,
+        Client: RpcClientTrait + Subscriber + Clone + Send + 'static,
+        T: Config,
+        Api<(), Client, T>: Send,
+    {
+        let (heads_in, heads_out) = channel();
+        api.subscribe_new_heads(heads_in)?;
+
+        // The dispatcher thread gets an api of its own, without the signer.
+        let mut thread_api = Api::new_offline(
+            api.client.clone(),
+            api.genesis_hash,
+            api.metadata.clone(),
+            api.runtime_version.clone(),
+        );
+        thread_api.metadata_cache = api.metadata_cache.clone();
+
+        let (stop_in, stop_out) = channel();
+        let thread = self.spawn(thread_api, heads_out, stop_out)?;
+        Ok(DispatcherHandle {
+            stop: stop_in,
+            thread,
+        })
+    }
+
+    fn spawn<Client, T>(
+        self,
+        api: Api<(), Client, T>,
+        heads_out: Receiver<String>,
+        stop_out: Receiver<()>,
+    ) -> ApiResult<JoinHandle<Self>>
+    where
+        Client: RpcClientTrait + Send + 'static,
+        T: Config,
+        Api<(), Client, T>: Send,
+    {
+        Ok(thread::Builder::new()
+            .name("event-dispatcher".to_owned())
+            .spawn(move || self.run(api, heads_out, stop_out))
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?)
+    }
+
+    fn run<Client, T>(
+        mut self,
+        api: Api<(), Client, T>,
+        heads_out: Receiver<String>,
+        stop_out: Receiver<()>,
+    ) -> Self
+    where
+        Client: RpcClientTrait,
+        T: Config,
+    {
+        // Decoder of the previous block, with its spec version.
+        let mut decoder = None;
+        // Returning drops the receiver, upon which the subscription closes its connection.
+        loop {
+            if !matches!(stop_out.try_recv(), Err(TryRecvError::Empty)) {
+                debug!("event dispatcher stopped");
+                break;
+            }
+            match heads_out.recv_timeout(STOP_POLL_INTERVAL) {
+                Ok(head) => {
+                    if let Err(e) = self.dispatch_head(&api, &mut decoder, &head) {
+                        error!("couldn't dispatch the events of {}: {:?}", head, e);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    warn!("new heads subscription ended, event dispatcher stopped");
+                    break;
+                }
+            }
+        }
+        self
+    }
+
+    fn dispatch_head<Client, T>(
+        &mut self,
+        api: &Api<(), Client, T>,
+        decoder: &mut Option<(u32, EventsDecoder)>,
+        head: &str,
+    ) -> ApiResult<()>
+    where
+        Client: RpcClientTrait,
+        T: Config,
+    {
+        let hash = serde_json::from_str::<T::Header>(head)?.hash();
+        let spec_version = api.get_runtime_version(Some(hash))?.spec_version;
+        let block_decoder = match decoder.take() {
+            Some((version, decoder)) if version == spec_version => decoder,
+            _ => EventsDecoder::new(api._get_metadata_of_spec_version(spec_version, hash)?),
+        };
+        let block_decoder = &decoder.insert((spec_version, block_decoder)).1;
+
+        let key = utils::storage_key("System", "Events");
+        let events = match api.get_opaque_storage_by_key_hash(key, Some(hash))? {
+            Some(events) => events,
+            None => return Ok(()),
+        };
+        let decoded = block_decoder.decode_events_lenient(&mut events.as_slice())?;
+        self.dispatch(&decoded.records);
+        for undecodable in decoded.undecodable {
+            let phase = undecodable.phase.clone();
+            self.report(phase.as_ref(), &EventError::Undecodable(undecodable));
+        }
+        if decoded.not_decoded > 0 {
+            warn!("{} event records were not decoded", decoded.not_decoded);
+        }
+        Ok(())
+    }
+}
+
+/// Handle of an [`EventDispatcher`] running in the background.
+///
+/// Dropping the handle stops the dispatcher without waiting for it.
+pub struct DispatcherHandle {
+    /// Stops the dispatcher when signalled or dropped.
+    stop: ThreadOut<()>,
+    thread: JoinHandle<EventDispatcher>,
+}
+
+impl DispatcherHandle {
+    /// Stop the dispatcher and wait for it to finish the block at hand.
+    ///
+    /// Returns the dispatcher with its handlers, unless a handler panicked.
+    pub fn stop(self) -> Option<EventDispatcher> {
+        // Fails only if the dispatcher has ended already.
+        let _ = self.stop.send(());
+        self.thread.join().ok()
+    }
+
+    /// Whether the dispatcher ended, e.g. because the subscription ended.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::std::{RuntimeMetadataPrefixed, RuntimeVersion, XtStatus};
+    use ac_node_api::events::EventRecord;
+    use ac_node_api::metadata::Metadata;
+    use ac_node_api::test_utils;
+    use ac_primitives::NodeTemplateConfig;
+    use codec::{Compact, Decode, Encode};
+    use metadata::{PalletEventMetadata, PalletMetadata};
+    use scale_info::{meta_type, TypeInfo};
+    use serde_json::Value;
+    use sp_core::{sr25519, H256};
+    use std::convert::TryFrom;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    fn record(phase: Phase, event: Raw) -> EventRecord {
+        EventRecord {
+            phase,
+            event,
+            topics: vec![],
+        }
+    }
+
+    fn raw_event(pallet: &str, variant: &str) -> Raw {
+        Raw::Event(RawEvent {
+            pallet: pallet.to_string(),
+            pallet_index: 0,
+            variant: variant.to_string(),
+            variant_index: 0,
+            data: vec![].into(),
+        })
+    }
+
+    #[test]
+    fn dispatches_to_matching_handlers_in_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let (transfers, all, errors) = (calls.clone(), calls.clone(), calls.clone());
+        let mut dispatcher = EventDispatcher::new()
+            .on_event("Balances", "Transfer", move |phase, _| {
+                transfers
+                    .lock()
+                    .unwrap()
+                    .push(format!("transfer {:?}", phase))
+            })
+            .on_event_where(
+                |raw| raw.pallet != "System",
+                move |_, raw| all.lock().unwrap().push(raw.variant.clone()),
+            )
+            .on_error(move |_, _| errors.lock().unwrap().push("error".to_string()));
+
+        dispatcher.dispatch(&EventRecords(vec![
+            record(Phase::Initialization, raw_event("System", "NewAccount")),
+            record(Phase::ApplyExtrinsic(1), raw_event("Balances", "Transfer")),
+            record(
+                Phase::ApplyExtrinsic(2),
+                Raw::Error(RuntimeError::BadOrigin),
+            ),
+            record(Phase::Finalization, raw_event("Balances", "Deposit")),
+        ]));
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["transfer ApplyExtrinsic(1)", "Transfer", "error", "Deposit"]
+        );
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum EventV1 {
+        Old(u8),
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum EventV2 {
+        New(u8),
+    }
+
+    type Header = <NodeTemplateConfig as Config>::Header;
+
+    /// Metadata of the runtime with `spec_version`, whose `Test` pallet event got renamed by
+    /// the upgrade to spec version 2.
+    fn prefixed_metadata(spec_version: u32) -> RuntimeMetadataPrefixed {
+        let event = match spec_version {
+            1 => meta_type::<EventV1>(),
+            _ => meta_type::<EventV2>(),
+        };
+        test_utils::prefixed_metadata(vec![PalletMetadata {
+            event: Some(PalletEventMetadata { ty: event }),
+            ..test_utils::pallet("Test", 0)
+        }])
+    }
+
+    fn header(number: u32) -> Header {
+        Header::new(
+            number,
+            Default::default(),
+            Default::default(),
+            H256::repeat_byte(number as u8 - 1),
+            Default::default(),
+        )
+    }
+
+    fn spec_version(number: u32) -> u32 {
+        if number < 2 {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Encoded `System.Events` of block `number`: a `Test` event with the block number,
+    /// followed in block 2 by an event unknown to the metadata.
+    fn events(number: u32) -> Vec<u8> {
+        let mut bytes = Compact(number).encode();
+        Phase::Initialization.encode_to(&mut bytes);
+        bytes.extend_from_slice(&[0, 0, number as u8]);
+        Vec::<H256>::new().encode_to(&mut bytes);
+        if number == 2 {
+            Phase::Finalization.encode_to(&mut bytes);
+            bytes.extend_from_slice(&[0, 9]);
+        }
+        bytes
+    }
+
+    /// Chain of the blocks 1 and 2 with a runtime upgrade in block 2, see `spec_version`. The
+    /// test sends the new heads.
+    #[derive(Clone, Default)]
+    struct SubscribedNode {
+        heads_in: Arc<Mutex<Option<ThreadOut<String>>>>,
+    }
+
+    impl SubscribedNode {
+        fn new_head(&self, number: u32) {
+            let head = serde_json::to_string(&header(number)).unwrap();
+            let heads_in = self.heads_in.lock().unwrap();
+            heads_in.as_ref().unwrap().send(head).unwrap();
+        }
+
+        fn end_subscription(&self) {
+            self.heads_in.lock().unwrap().take();
+        }
+    }
+
+    fn block_number(hash: &Value) -> u32 {
+        (1..=2)
+            .find(|&number| &serde_json::to_value(header(number).hash()).unwrap() == hash)
+            .unwrap_or_else(|| panic!("Unexpected block {}", hash))
+    }
+
+    fn hex_result(bytes: &[u8]) -> String {
+        format!("\"0x{}\"", hex::encode(bytes))
+    }
+
+    impl RpcClientTrait for SubscribedNode {
+        fn get_request(&self, jsonreq: Value) -> ApiResult<String> {
+            let params = &jsonreq["params"];
+            match jsonreq["method"].as_str().unwrap() {
+                "state_getRuntimeVersion" => Ok(serde_json::to_string(&RuntimeVersion {
+                    spec_version: spec_version(block_number(&params[0])),
+                    ..Default::default()
+                })?),
+                // Without the `Metadata_metadata_versions` runtime api.
+                "state_call" => Ok("null".into()),
+                "state_getMetadata" => {
+                    let metadata = prefixed_metadata(spec_version(block_number(&params[0])));
+                    Ok(hex_result(&metadata.encode()))
+                }
+                "state_getStorage" => Ok(hex_result(&events(block_number(&params[1])))),
+                method => panic!("Unexpected request {}", method),
+            }
+        }
+
+        fn send_extrinsic<Hash: Decode>(
+            &self,
+            _xthex_prefixed: String,
+            _exit_on: XtStatus,
+        ) -> ApiResult<Option<Hash>> {
+            unimplemented!()
+        }
+    }
+
+    impl Subscriber for SubscribedNode {
+        fn start_subscriber(
+            &self,
+            _json_req: String,
+            result_in: ThreadOut<String>,
+        ) -> Result<(), ws::Error> {
+            *self.heads_in.lock().unwrap() = Some(result_in);
+            Ok(())
+        }
+    }
+
+    /// Api created after the runtime upgrade.
+    fn api(node: &SubscribedNode) -> Api<sr25519::Pair, SubscribedNode, NodeTemplateConfig> {
+        Api::new_offline(
+            node.clone(),
+            H256::zero(),
+            Metadata::try_from(prefixed_metadata(2)).unwrap(),
+            RuntimeVersion {
+                spec_version: 2,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn start_dispatches_the_events_of_new_heads_until_stopped() {
+        let node = SubscribedNode::default();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let (events, errors) = (calls.clone(), calls.clone());
+        let handle = EventDispatcher::new()
+            .on_event_where(
+                |_| true,
+                move |_, raw| {
+                    let call = format!("{} {:?}", raw.variant, &raw.data[..]);
+                    events.lock().unwrap().push(call)
+                },
+            )
+            .on_error(move |phase, error| {
+                let call = match error {
+                    EventError::Undecodable(undecodable) => {
+                        format!("undecodable {:?} {:?}", phase, undecodable.event_index)
+                    }
+                    EventError::Runtime(e) => panic!("Unexpected runtime error {:?}", e),
+                };
+                errors.lock().unwrap().push(call)
+            })
+            .start(&api(&node))
+            .unwrap();
+
+        node.new_head(1);
+        node.new_head(2);
+        wait_until(|| calls.lock().unwrap().len() == 3);
+
+        assert!(!handle.is_finished());
+        assert!(handle.stop().is_some());
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "Old [1]",
+                "New [2]",
+                "undecodable Some(Finalization) Some((0, 9))"
+            ]
+        );
+    }
+
+    #[test]
+    fn dispatcher_ends_with_the_subscription() {
+        let node = SubscribedNode::default();
+        let handle = EventDispatcher::new().start(&api(&node)).unwrap();
+
+        node.end_subscription();
+        wait_until(|| handle.is_finished());
+
+        assert!(handle.stop().is_some());
+    }
+}
@@ -14,25 +14,28 @@
    limitations under the License.
 
 */
-use std::sync::mpsc::{Receiver, SendError, Sender as ThreadOut};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::mpsc::{channel, Receiver, SendError, Sender as ThreadOut};
 
-use ac_node_api::events::{EventsDecoder, Raw, RawEvent};
-use ac_primitives::ExtrinsicParams;
+use ac_node_api::events::{EventsDecoder, Raw, RawEvent, StaticEvent};
+use ac_node_api::Phase;
+use ac_primitives::{Config, Signer};
 use codec::Decode;
 use log::{debug, error, info, warn};
 use serde_json::Value;
-use sp_core::Pair;
-use sp_runtime::MultiSignature;
+use sp_runtime::traits::Header as HeaderT;
 use ws::{CloseCode, Error, Handler, Handshake, Message, Result as WsResult, Sender};
 
 use crate::std::rpc::RpcClientError;
 use crate::std::{json_req, FromHexString, RpcClient as RpcClientTrait, XtStatus};
-use crate::std::{Api, ApiResult};
+use crate::std::{Api, ApiClientError, ApiResult};
 use crate::utils;
 
 pub use client::WsRpcClient;
+pub use dispatcher::{DispatcherHandle, EventDispatcher, EventError};
 
 pub mod client;
+pub mod dispatcher;
 
 pub type OnMessageFn = fn(msg: Message, out: Sender, result: ThreadOut<String>) -> WsResult<()>;
 
@@ -62,9 +65,9 @@ pub trait Subscriber {
         -> Result<(), Error>;
 }
 
-impl<P, Params> Api<P, WsRpcClient, Params>
+impl<P, T> Api<P, WsRpcClient, T>
 where
-    Params: ExtrinsicParams,
+    T: Config,
 {
     pub fn default_with_url(url: &str) -> ApiResult<Self> {
         let client = WsRpcClient::new(url);
@@ -72,12 +75,11 @@ where
     }
 }
 
-impl<P, Client, Params> Api<P, Client, Params>
+impl<P, Client, T> Api<P, Client, T>
 where
-    P: Pair,
-    MultiSignature: From<P::Signature>,
+    P: Signer<AccountId = T::AccountId, Address = T::Address, Signature = T::Signature>,
     Client: RpcClientTrait + Subscriber,
-    Params: ExtrinsicParams,
+    T: Config,
 {
     pub fn subscribe_events(&self, sender: ThreadOut<String>) -> ApiResult<()> {
         debug!("subscribing to events");
@@ -96,6 +98,123 @@ where
             .map_err(|e| e.into())
     }
 
+    pub fn subscribe_new_heads(&self, sender: ThreadOut<String>) -> ApiResult<()> {
+        debug!("subscribing to new heads");
+        let jsonreq = json_req::chain_subscribe_new_heads().to_string();
+        self.client
+            .start_subscriber(jsonreq, sender)
+            .map_err(|e| e.into())
+    }
+
+    /// Submit the extrinsic and wait until the block including it is followed by
+    /// `confirmations` descendants on the best chain. Useful on chains where finality lags far
+    /// behind.
+    ///
+    /// Returns the hash of the including block, or `Error::Retracted` as soon as a new best
+    /// block is not a descendant of it anymore. Heads received before the including block or
+    /// one of its descendants was a best block are ignored, as they may stem from a fork that
+    /// was best before the inclusion. Fails with `Error::HeaderNotFound` if the node can't
+    /// provide a header of the chain between the including block and a new best block.
+    pub fn send_extrinsic_with_confirmations(
+        &self,
+        xthex_prefixed: String,
+        confirmations: u32,
+    ) -> ApiResult<T::Hash> {
+        // Subscribe before submitting, not to miss the heads following the inclusion.
+        let (heads_in, heads_out) = channel();
+        self.subscribe_new_heads(heads_in)?;
+
+        let block_hash = self
+            .send_extrinsic(xthex_prefixed, XtStatus::InBlock)?
+            .ok_or(ApiClientError::NotInBlock)?;
+        let block_number = *self
+            .get_header(Some(block_hash))?
+            .ok_or_else(|| ApiClientError::HeaderNotFound(format!("{:?}", block_hash)))?
+            .number();
+        let confirmed_number = block_number + confirmations.into();
+        info!(
+            "extrinsic included in block {:?}, waiting for {} confirmations",
+            block_hash, confirmations
+        );
+
+        // The best chain from the including block up to the newest head, verified by
+        // following the parent hashes. Each head only needs its blocks unknown so far fetched.
+        let mut best_chain = BTreeMap::from([(block_number, block_hash)]);
+        let mut included_in_best_chain = false;
+        loop {
+            let head: T::Header = serde_json::from_str(&heads_out.recv()?)?;
+            if *head.number() < block_number {
+                continue;
+            }
+            let head_number = *head.number();
+            if !self.extend_best_chain(&mut best_chain, block_number, head)? {
+                if included_in_best_chain {
+                    return Err(ApiClientError::Retracted(format!("{:?}", block_hash)));
+                }
+                debug!(
+                    "ignoring head {} of a fork queued before the inclusion",
+                    head_number
+                );
+                continue;
+            }
+            included_in_best_chain = true;
+            if head_number >= confirmed_number {
+                return Ok(block_hash);
+            }
+        }
+    }
+
+    /// Add `head` and its ancestors not in `chain` yet to `chain`, which maps the block numbers
+    /// from `base_number` upwards to the hashes of the best chain. Blocks of a previous best
+    /// chain above the common ancestor are dropped.
+    ///
+    /// Returns `false` if `head` does not descend from the block at `base_number`.
+    fn extend_best_chain(
+        &self,
+        chain: &mut BTreeMap<T::BlockNumber, T::Hash>,
+        base_number: T::BlockNumber,
+        head: T::Header,
+    ) -> ApiResult<bool> {
+        let mut segment = Vec::new();
+        let mut header = head;
+        loop {
+            let number = *header.number();
+            let hash = header.hash();
+            if chain.get(&number) == Some(&hash) {
+                break;
+            }
+            if number <= base_number {
+                return Ok(false);
+            }
+            let parent_hash = *header.parent_hash();
+            segment.push((number, hash));
+            header = self
+                .get_header(Some(parent_hash))?
+                .ok_or_else(|| ApiClientError::HeaderNotFound(format!("{:?}", parent_hash)))?;
+        }
+        if let Some(&(lowest, _)) = segment.last() {
+            chain.split_off(&lowest);
+        }
+        chain.extend(segment);
+        Ok(true)
+    }
+
+    /// Subscribe to the `E` events of new best blocks.
+    ///
+    /// The events of each new best block are read with `get_events`. A best block that is
+    /// skipped by the node's new heads notifications, e.g. on a reorg, is skipped here as well.
+    pub fn subscribe_typed_events<E: StaticEvent>(
+        &self,
+    ) -> ApiResult<TypedEvents<'_, P, Client, T, E>> {
+        let (heads_in, heads_out) = channel();
+        self.subscribe_new_heads(heads_in)?;
+        Ok(TypedEvents {
+            api: self,
+            heads: heads_out,
+            pending: VecDeque::new(),
+        })
+    }
+
     pub fn wait_for_event<E: Decode>(
         &self,
         module: &str,
@@ -121,20 +240,32 @@ where
 
         loop {
             let event_str = receiver.recv()?;
-            let _events = event_decoder.decode_events(&mut Vec::from_hex(event_str)?.as_slice());
+            let _events =
+                event_decoder.decode_events_lenient(&mut Vec::from_hex(event_str)?.as_slice());
             info!("wait for raw event");
             match _events {
-                Ok(raw_events) => {
-                    for (phase, event) in raw_events.into_iter() {
-                        info!("Decoded Event: {:?}, {:?}", phase, event);
-                        match event {
-                            Raw::Event(raw) if raw.pallet == module && raw.variant == variant => {
-                                return Ok(raw);
+                Ok(decoded) => {
+                    for undecodable in decoded.undecodable.iter() {
+                        warn!("couldn't decode event record: {:?}", undecodable);
+                    }
+                    if decoded.not_decoded > 0 {
+                        warn!("{} event records were not decoded", decoded.not_decoded);
+                    }
+                    for record in decoded.records.into_iter() {
+                        match record.event {
+                            Raw::Event(raw) => {
+                                info!("Decoded Event: {:?}, {:?}", record.phase, raw);
+                                if raw.pallet == module && raw.variant == variant {
+                                    return Ok(raw);
+                                }
+                                debug!("ignoring unsupported module event: {:?}", raw);
                             }
                             Raw::Error(runtime_error) => {
-                                error!("Some extrinsic Failed: {:?}", runtime_error);
+                                error!(
+                                    "Some extrinsic Failed: {:?}, {:?}",
+                                    record.phase, runtime_error
+                                )
                             }
-                            _ => debug!("ignoring unsupported module event: {:?}", event),
                         }
                     }
                 }
@@ -144,6 +275,64 @@ where
     }
 }
 
+/// An event of a static type, along with where it was emitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedEvent<Hash, E> {
+    /// Hash of the block emitting the event.
+    pub block_hash: Hash,
+    /// Phase of the block emitting the event.
+    pub phase: Phase,
+    /// The decoded event.
+    pub event: E,
+}
+
+/// Iterator over the `E` events of new best blocks, created with
+/// `Api::subscribe_typed_events`. Blocks until the next event arrives.
+pub struct TypedEvents<'a, P, Client, T, E>
+where
+    Client: RpcClientTrait,
+    T: Config,
+{
+    api: &'a Api<P, Client, T>,
+    heads: Receiver<String>,
+    pending: VecDeque<TypedEvent<T::Hash, E>>,
+}
+
+impl<'a, P, Client, T, E> Iterator for TypedEvents<'a, P, Client, T, E>
+where
+    Client: RpcClientTrait,
+    T: Config,
+    E: StaticEvent,
+{
+    type Item = ApiResult<TypedEvent<T::Hash, E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            // The subscription ended if the sender is gone.
+            let head = self.heads.recv().ok()?;
+            let block_hash = match serde_json::from_str::<T::Header>(&head) {
+                Ok(header) => header.hash(),
+                Err(e) => return Some(Err(e.into())),
+            };
+            let records = match self.api.get_events(Some(block_hash)) {
+                Ok(records) => records,
+                Err(e) => return Some(Err(e)),
+            };
+            for result in records.find::<E>() {
+                match result {
+                    Ok((record, event)) => self.pending.push_back(TypedEvent {
+                        block_hash,
+                        phase: record.phase.clone(),
+                        event,
+                    }),
+                    Err(e) => return Some(Err(e.into())),
+                }
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
 pub fn on_get_request_msg(msg: Message, out: Sender, result: ThreadOut<String>) -> WsResult<()> {
     out.close(CloseCode::Normal)
         .unwrap_or_else(|_| warn!("Could not close Websocket normally"));
@@ -182,7 +371,7 @@ pub fn on_subscription_msg(msg: Message, out: Sender, result: ThreadOut<String>)
                         None => println!("No events happened"),
                     };
                 }
-                Some("chain_finalizedHead") => {
+                Some("chain_finalizedHead") | Some("chain_newHead") => {
                     let head = serde_json::to_string(&value["params"]["result"])
                         .map_err(|e| Box::new(RpcClientError::Serde(e)))?;
 
@@ -371,6 +560,11 @@ fn result_from_json_response(resp: &str) -> RpcResult<String> {
 mod tests {
     use super::*;
     use crate::rpc::RpcClientError;
+    use crate::std::RuntimeVersion;
+    use ac_node_api::test_utils;
+    use ac_primitives::NodeTemplateConfig;
+    use codec::Encode;
+    use sp_core::{sr25519, H256};
     use std::assert_matches::assert_matches;
     use std::fmt::Debug;
 
@@ -476,4 +670,99 @@ mod tests {
             "extrinsic error code 1002: Verification Error: Execution(Wasmi(Trap(Trap { kind: Unreachable }))): RuntimeApi(\"Execution(Wasmi(Trap(Trap { kind: Unreachable })))\")"
         );
     }
+
+    type Header = <NodeTemplateConfig as Config>::Header;
+
+    /// Block `number` of the chain `fork`, descending from block 1, which all chains share.
+    fn header(number: u32, fork: u8) -> Header {
+        let parent_hash = match number {
+            1 => H256::zero(),
+            2 => header(1, 0).hash(),
+            _ => header(number - 1, fork).hash(),
+        };
+        let state_root = H256::repeat_byte(if number == 1 { 0 } else { fork });
+        Header::new(
+            number,
+            Default::default(),
+            state_root,
+            parent_hash,
+            Default::default(),
+        )
+    }
+
+    /// Node including the extrinsic in block 2 of chain 0, whose new heads subscription
+    /// yields `heads`.
+    struct ForkedNode {
+        heads: Vec<Header>,
+    }
+
+    impl ForkedNode {
+        fn known_header(hash: H256) -> Header {
+            (1..=4)
+                .flat_map(|number| [header(number, 0), header(number, 1)])
+                .find(|header| header.hash() == hash)
+                .unwrap_or_else(|| panic!("Unexpected header {:?}", hash))
+        }
+    }
+
+    impl RpcClientTrait for ForkedNode {
+        fn get_request(&self, jsonreq: Value) -> ApiResult<String> {
+            match jsonreq["method"].as_str().unwrap() {
+                "chain_getHeader" => {
+                    let hash = serde_json::from_value(jsonreq["params"][0].clone())?;
+                    Ok(serde_json::to_string(&Self::known_header(hash))?)
+                }
+                method => panic!("Unexpected request {}", method),
+            }
+        }
+
+        fn send_extrinsic<Hash: Decode>(
+            &self,
+            _xthex_prefixed: String,
+            exit_on: XtStatus,
+        ) -> ApiResult<Option<Hash>> {
+            assert_eq!(exit_on, XtStatus::InBlock);
+            let block_hash = header(2, 0).hash().encode();
+            Ok(Some(Hash::decode(&mut block_hash.as_slice())?))
+        }
+    }
+
+    impl Subscriber for ForkedNode {
+        fn start_subscriber(
+            &self,
+            _json_req: String,
+            result_in: ThreadOut<String>,
+        ) -> Result<(), Error> {
+            for head in self.heads.iter() {
+                result_in
+                    .send(serde_json::to_string(head).unwrap())
+                    .unwrap();
+            }
+            Ok(())
+        }
+    }
+
+    fn send_with_confirmations(heads: Vec<Header>) -> ApiResult<H256> {
+        Api::<sr25519::Pair, _, NodeTemplateConfig>::new_offline(
+            ForkedNode { heads },
+            H256::zero(),
+            test_utils::metadata(vec![]),
+            RuntimeVersion::default(),
+        )
+        .send_extrinsic_with_confirmations("0x00".into(), 1)
+    }
+
+    #[test]
+    fn confirmations_ignore_fork_heads_queued_before_the_inclusion() {
+        let result = send_with_confirmations(vec![header(2, 1), header(2, 0), header(3, 0)]);
+
+        assert_eq!(result.unwrap(), header(2, 0).hash());
+    }
+
+    #[test]
+    fn confirmations_fail_on_a_fork_head_after_the_inclusion() {
+        let result = send_with_confirmations(vec![header(2, 0), header(3, 1), header(4, 0)]);
+
+        assert!(matches!(result, Err(ApiClientError::Retracted(_))));
+    }
 }
@@ -0,0 +1,85 @@
+/*
+    Copyright 2021 Integritee AG and Supercomputing Systems AG
+    Licensed under the Apache License, Version 2.0 (the "License");
+    you may not use this file except in compliance with the License.
+    You may obtain a copy of the License at
+        http://www.apache.org/licenses/LICENSE-2.0
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Compiles the code generated from `metadata/balances.scale`, the V14 metadata of a runtime
+//! with a `Balances` pallet at index 5, and uses it with the metadata it was generated from.
+
+use ac_node_api::{metadata::Metadata, v15::VersionedMetadata, RawEvent};
+use codec::{Compact, Decode, Encode};
+use sp_core::Bytes;
+use std::convert::TryFrom;
+
+#[substrate_api_client::runtime_metadata(path = "tests/metadata/balances.scale")]
+pub mod runtime {}
+
+use runtime::balances;
+
+fn metadata() -> Metadata {
+    let bytes = include_bytes!("metadata/balances.scale");
+    let versioned = VersionedMetadata::decode(&mut &bytes[..]).unwrap();
+    Metadata::try_from(versioned).unwrap()
+}
+
+#[test]
+fn static_calls_resolve_their_index() {
+    let metadata = metadata();
+    let (index, transfer) = metadata
+        .static_call(balances::calls::transfer([1; 32], Compact(10)))
+        .unwrap();
+    assert_eq!(index, [5, 0]);
+    assert_eq!(transfer.dest, [1; 32]);
+
+    let (index, _) = metadata
+        .static_call(balances::calls::burn(Compact(10)))
+        .unwrap();
+    assert_eq!(index, [5, 2]);
+}
+
+#[test]
+fn static_storage_addresses_resolve_their_key() {
+    let metadata = metadata();
+    assert_eq!(
+        metadata.static_storage_key(&balances::storage::account(&[1; 32])),
+        metadata.storage_map_key("Balances", "Account", [1u8; 32])
+    );
+    assert_eq!(
+        metadata.static_storage_key(&balances::storage::total_issuance()),
+        metadata.storage_value_key("Balances", "TotalIssuance")
+    );
+}
+
+#[test]
+fn static_constants_decode_their_value() {
+    let deposit = metadata().static_constant(&balances::constants::existential_deposit());
+    assert_eq!(deposit, Ok(500));
+}
+
+#[test]
+fn static_events_decode_from_raw_events() {
+    let transfer = balances::events::Transfer {
+        from: [1; 32],
+        to: [2; 32],
+        amount: 3,
+    };
+    let raw = RawEvent {
+        pallet: "Balances".into(),
+        pallet_index: 5,
+        variant: "Transfer".into(),
+        variant_index: 2,
+        data: Bytes(transfer.encode()),
+    };
+    assert_eq!(
+        raw.as_event::<balances::events::Transfer>(),
+        Ok(Some(transfer))
+    );
+}
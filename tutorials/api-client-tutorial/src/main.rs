@@ -18,7 +18,7 @@ use sp_core::crypto::Pair;
 use sp_keyring::AccountKeyring;
 
 use substrate_api_client::{
-    compose_extrinsic, rpc::WsRpcClient, utils::FromHexString, Api, PlainTipExtrinsicParams,
+    compose_extrinsic, rpc::WsRpcClient, utils::FromHexString, Api, NodeTemplateConfig,
     UncheckedExtrinsicV4, XtStatus,
 };
 
@@ -35,7 +35,7 @@ fn main() {
     let client = WsRpcClient::new(url);
 
     let api = Api::new(client)
-        .map(|api: Api<_, _, PlainTipExtrinsicParams>| api.set_signer(signer.clone()))
+        .map(|api: Api<_, _, NodeTemplateConfig>| api.set_signer(signer.clone()))
         .unwrap();
 
     let xt: UncheckedExtrinsicV4<_, _> =